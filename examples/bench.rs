@@ -1,7 +1,20 @@
 // Run: `cargo run -r --example bench`
 
-use frpc::{databuf::Decode, Return};
-use std::{io::Write, time::Instant};
+use bytes::Bytes;
+use frpc::{databuf::Decode, declare, sse, Output, Return};
+use frpc_transport_http::{
+    ctx::{Ctx, RecvRequest, SendResponse, SendStream},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+};
+use std::{
+    future::pending,
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
 
 const ITER: u32 = 10000000;
 
@@ -12,9 +25,19 @@ async fn main() {
     println!("Normal: {:?}", time.elapsed());
 
     let time = Instant::now();
-    let right = rpc().await;
-    println!("RPC: {:?}", time.elapsed());
-    assert_eq!(left, right)
+    let right = rpc(1).await;
+    println!("RPC (sync): {:?}", time.elapsed());
+    assert_eq!(left, right);
+
+    let time = Instant::now();
+    let right = rpc(2).await;
+    println!("RPC (async): {:?}", time.elapsed());
+    assert_eq!(left, right);
+
+    let time = Instant::now();
+    let items = sse().await;
+    println!("RPC (sse): {:?}", time.elapsed());
+    assert_eq!(items, ITER as usize + 1);
 }
 
 #[inline(never)]
@@ -22,6 +45,28 @@ fn add(a: u8, b: u8) -> Return<u8> {
     Return(a.wrapping_add(b))
 }
 
+#[inline(never)]
+async fn add_async(a: u8, b: u8) -> u8 {
+    a.wrapping_add(b)
+}
+
+#[inline(never)]
+fn count(to: u32) -> impl Output {
+    sse! {
+        for i in 0..to {
+            yield i as u8;
+        }
+    }
+}
+
+declare! {
+    service Bench {
+        rpc add = 1;
+        rpc add_async = 2;
+        rpc count = 3;
+    }
+}
+
 #[inline(never)]
 async fn normal() -> Vec<u8> {
     let mut tcp = vec![];
@@ -41,48 +86,96 @@ async fn normal() -> Vec<u8> {
     tcp
 }
 
-#[inline(never)]
-async fn rpc() -> Vec<u8> {
-    let mut tcp = DummyTransport(vec![]);
-    for i in 0..ITER {
-        let data = [i as u8, i as u8];
-        let mut cursor = data.as_slice();
-        frpc::Output::produce(add, (), &mut cursor, &mut tcp).await
+/// Request body, That is received at once.
+struct Req {
+    headers: HeaderMap,
+    body: Option<Bytes>,
+}
+
+impl Req {
+    fn new(body: Vec<u8>) -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+        Self {
+            headers,
+            body: Some(body.into()),
+        }
     }
-    tcp.0
 }
 
-struct DummyTransport(Vec<u8>);
+impl RecvRequest for Req {
+    fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
 
-impl frpc::Transport for DummyTransport {
-    fn unary_sync(
-        &mut self,
-        cb: impl FnOnce(&mut dyn std::io::Write) -> std::io::Result<()> + Send,
-    ) -> impl std::future::Future<Output = ()> + Send {
-        Box::pin(async {
-            cb(&mut self.0).unwrap();
-        })
+    async fn data(&mut self) -> Option<io::Result<Bytes>> {
+        self.body.take().map(Ok)
+    }
+}
+
+/// Response body is written to `tcp`, Streamed frames are only counted.
+#[derive(Default)]
+struct Res {
+    headers: HeaderMap,
+    tcp: Vec<u8>,
+    frames: Frames,
+}
+
+/// Number of written frames.
+#[derive(Default, Clone)]
+struct Frames(Arc<AtomicUsize>);
+
+impl SendResponse for Res {
+    type Stream = Frames;
+
+    fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    async fn cancelled(&mut self) {
+        pending().await
+    }
+
+    async fn send(&mut self, _: StatusCode, chunks: Vec<Bytes>) {
+        for chunk in chunks {
+            self.tcp.extend_from_slice(&chunk);
+        }
     }
 
-    async fn unary(
-        &mut self,
-        _poll: impl FnMut(
-                &mut std::task::Context,
-                &mut dyn std::io::Write,
-            ) -> std::task::Poll<std::io::Result<()>>
-            + Send,
-    ) {
-        todo!()
+    fn send_stream(&mut self) -> Option<Self::Stream> {
+        Some(self.frames.clone())
+    }
+}
+
+impl SendStream for Frames {
+    async fn write(&mut self, _: Bytes, _: bool) -> bool {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        true
     }
 
-    async fn server_stream(
-        &mut self,
-        _poll: impl FnMut(
-                &mut std::task::Context,
-                &mut dyn std::io::Write,
-            ) -> std::task::Poll<std::io::Result<bool>>
-            + Send,
-    ) {
-        todo!()
+    async fn cancelled(&mut self) {
+        pending().await
     }
 }
+
+/// Every call is served by `transport-http`, Same as a real server but without the network.
+#[inline(never)]
+async fn rpc(id: u16) -> Vec<u8> {
+    let mut ctx = Ctx::new(Req::new(vec![]), Res::default());
+    for i in 0..ITER {
+        let [a, b] = id.to_le_bytes();
+        ctx.req = Req::new(vec![a, b, i as u8, i as u8]);
+        ctx.serve(Bench, ()).await;
+    }
+    ctx.res.tcp
+}
+
+/// Every frame is written to the stream, As soon as it's yielded.
+#[inline(never)]
+async fn sse() -> usize {
+    let mut body = 3_u16.to_le_bytes().to_vec();
+    frpc::databuf::Encode::encode::<{ frpc::DATABUF_CONFIG }>(&ITER, &mut body).unwrap();
+    let mut ctx = Ctx::new(Req::new(body), Res::default());
+    ctx.serve(Bench, ()).await;
+    ctx.res.frames.0.load(Ordering::Relaxed)
+}
@@ -1,5 +1,6 @@
 mod src;
 
+use frpc::health::{Health, HealthReporter};
 use frpc_transport::*;
 use src::*;
 use std::{io, net::SocketAddr, sync::Arc};
@@ -8,8 +9,28 @@ use std::{io, net::SocketAddr, sync::Arc};
 async fn main() -> io::Result<()> {
     codegen_init();
 
+    let reporter = HealthReporter::new();
+    reporter.set_serving::<Greeter>();
+    reporter.set_serving::<Stateful>();
+    reporter.set_serving::<ServerSentEvents>();
+
+    let shutdown = Shutdown::new();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            let _ = tokio::signal::ctrl_c().await;
+            println!("Shutting down...");
+            shutdown.trigger();
+        }
+    });
+
     println!("Server Runing at 127.0.0.1:4433");
     Server::new("./examples/key.pem", "./examples/cert.pem")?
+        .metrics("/metrics")
+        .route("/health", Health, reporter)
+        .cors(Cors::default())
+        .http1()
+        .shutdown(shutdown)
         .bind("127.0.0.1:4433", |addr, _| async move {
             App {
                 addr,
@@ -19,6 +40,17 @@ async fn main() -> io::Result<()> {
         .await
 }
 
+/// Same routes for HTTP/2 and HTTP/1.1.
+macro_rules! routes {
+    ($app: ident, $ctx: ident) => {
+        serve! {$ctx:
+            "/greeter" => Greeter; ()
+            "/stateful" => Stateful; $app.user
+            "/sse" => ServerSentEvents; ()
+        }
+    };
+}
+
 #[derive(Clone)]
 struct App {
     addr: SocketAddr,
@@ -28,13 +60,13 @@ struct App {
 impl Application for App {
     async fn stream(self, mut ctx: Ctx) {
         println!("From: {}; {:#?}", self.addr, ctx.req);
-        serve! {ctx:
-            "/greeter" => Greeter; ()
-            "/stateful" => Stateful; self.user
-            "/sse" => ServerSentEvents; ()
-        }
+        routes!(self, ctx);
+    }
+    async fn stream_http1(self, mut ctx: http1::Ctx) {
+        println!("From: {} (HTTP/1.1); {:#?}", self.addr, ctx.req);
+        routes!(self, ctx);
     }
-    async fn close(self) {
-        println!("Connection Closed: {}", self.addr);
+    async fn on_close(self, reason: CloseReason, stats: ConnectionStats) {
+        println!("Connection Closed: {}; {reason:?}, {stats:?}", self.addr);
     }
 }
@@ -21,8 +21,22 @@ fn get_events(count: u8) -> impl Output {
     }
 }
 
+/// Continues after the last received event, When the client reconnects.
+fn get_resumable_events(count: u8, cursor: ResumeCursor) -> impl Output {
+    resumable_sse! {
+        let start: u8 = cursor.as_deref().and_then(|c| c.parse().ok()).unwrap_or(0);
+        for sent in start..count {
+            sleep(Duration::from_secs(1)).await;
+            // Cursor is the number of events sent so far.
+            let cursor = (sent + 1).to_string();
+            yield Resumable::new(cursor, Event { elapsed: sent.into() })
+        }
+    }
+}
+
 declare! {
     pub service ServerSentEvents {
         rpc get_events = 1;
+        rpc get_resumable_events = 2;
     }
 }
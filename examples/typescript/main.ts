@@ -14,6 +14,9 @@ let sse = new ServerSentEvents(new HttpTransport("https://localhost:4433/sse"));
 for await (const ev of sse.get_events(3)) {
   console.log(ev);
 }
+for await (const ev of sse.get_resumable_events(3)) {
+  console.log(ev);
+}
 
 let server = new Stateful(new HttpTransport("https://localhost:4433/stateful"));
 console.log(await server.whats_my_name());
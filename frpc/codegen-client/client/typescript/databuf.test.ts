@@ -1,5 +1,13 @@
 import { assertEquals } from "https://deno.land/std@0.175.0/testing/asserts.ts";
-import { BufWriter, Decoder, Option, Result, Write } from "./databuf.ts";
+import {
+  BufWriter,
+  Decoder,
+  make_resumable_call,
+  Option,
+  Result,
+  RpcTransport,
+  Write,
+} from "./databuf.ts";
 
 class DefaultWriter implements Write {
   bytes: number[] = [];
@@ -191,3 +199,59 @@ Deno.test("Serde test: Complex type", () => {
   assertEquals(decode(), ok);
   assertEquals(decode(), err);
 });
+
+Deno.test("Serde test: uuid, datetime, decimal, json", () => {
+  const uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+  const date = new Date("2023-04-01T12:30:45.123Z");
+  const json = { name: "frpc", tags: ["rpc"], version: 1 };
+
+  const writer = new DefaultWriter();
+  const encoder = new BufWriter(writer);
+
+  encoder.uuid(uuid);
+  encoder.datetime(date);
+  encoder.decimal("-3.1415");
+  encoder.json(json);
+
+  encoder.flush();
+  assertEquals(writer.bytes.slice(0, 4), [0x67, 0xe5, 0x50, 0x44]);
+
+  const decoder = Decoder.from(new Uint8Array(writer.bytes));
+  assertEquals(decoder.uuid(), uuid);
+  assertEquals(decoder.datetime(), date);
+  assertEquals(decoder.decimal(), "-3.1415");
+  assertEquals(decoder.json(), json);
+});
+
+Deno.test("Resumable stream: reconnect with last cursor", async () => {
+  const cursors: (string | null)[] = [];
+  const rpc: RpcTransport = {
+    unary() {
+      throw new Error("unexpected unary call");
+    },
+    sse() {
+      return {
+        write() {},
+        flush() {},
+        async *call(requestInit: RequestInit) {
+          const cursor = new Headers(requestInit.headers).get(
+            "frpc-resume-cursor",
+          );
+          cursors.push(cursor);
+          if (cursor == null) {
+            // Cursor "1" (length prefixed), And the item.
+            yield new Uint8Array([1, 49, 7]);
+            throw new TypeError("connection lost");
+          }
+          return new Uint8Array([9]);
+        },
+      };
+    },
+    async close() {},
+  };
+
+  const stream = make_resumable_call(rpc, 1, {}, () => {}, (s) => s);
+  assertEquals(await stream.next(), { value: new Uint8Array([7]), done: false });
+  assertEquals(await stream.next(), { value: new Uint8Array([9]), done: true });
+  assertEquals(cursors, [null, "1"]);
+});
@@ -72,6 +72,81 @@ export function make_call<
   return decoder(fn.call(requestInit) as any);
 }
 
+/** Request header, Cursor of the last item received by the client. */
+const RESUME_CURSOR = "frpc-resume-cursor";
+/** Milliseconds to wait before reconnecting, Doubled after every failed attempt. */
+const RESUME_MIN_DELAY = 500;
+const RESUME_MAX_DELAY = 30_000;
+
+/**
+ * Server stream that reconnects with the cursor of the last received item, When the connection is lost.
+ * Every item is prefixed with its cursor, Which is stripped before `decoder` sees it.
+ *
+ * Calls rejected by the server (other than `unavailable`) are not retried.
+ * Cursor is sent in a request header, So the transport must send `RequestInit.headers`.
+ */
+export function make_resumable_call<Result>(
+  rpc: RpcTransport,
+  id: number,
+  requestInit: RequestInit,
+  encoder: (d: BufWriter) => void,
+  decoder: (d: AsyncGenerator<Uint8Array>) => Result,
+): Result {
+  return decoder(resume(rpc, id, requestInit, encoder));
+}
+
+async function* resume(
+  rpc: RpcTransport,
+  id: number,
+  requestInit: RequestInit,
+  encoder: (d: BufWriter) => void,
+): AsyncGenerator<Uint8Array, Uint8Array> {
+  let cursor: string | undefined;
+  // Reconnect immediately, If any item was received since the last attempt.
+  let delay = 0;
+  while (true) {
+    const headers = new Headers(requestInit.headers);
+    if (cursor != undefined) {
+      headers.set(RESUME_CURSOR, cursor);
+    }
+    const fn = rpc.sse();
+    const d = new BufWriter(fn);
+    d.u16(id);
+    encoder(d);
+    d.flush();
+    const stream = fn.call({ ...requestInit, headers });
+    while (true) {
+      let next;
+      try {
+        next = await stream.next();
+      } catch (error) {
+        if (requestInit.signal?.aborted || !isRetryable(error)) {
+          throw error;
+        }
+        break;
+      }
+      const { value, done } = next;
+      if (done) {
+        return value;
+      }
+      const d = Decoder.from(value);
+      cursor = d.str();
+      delay = 0;
+      yield value.subarray(d.offset - value.byteOffset);
+    }
+    await new Promise((resolve) => setTimeout(resolve, delay));
+    delay = Math.min(Math.max(delay * 2, RESUME_MIN_DELAY), RESUME_MAX_DELAY);
+  }
+}
+
+/** Network errors are retried, `RpcError` only if the server is `unavailable`. */
+function isRetryable(error: unknown) {
+  if (error instanceof Error && error.name == "RpcError") {
+    return (error as Error & { status?: string }).status == "unavailable";
+  }
+  return true;
+}
+
 export type Decode<T> = (this: Decoder) => T;
 export class Decoder {
   #view: DataView;
@@ -155,6 +230,27 @@ export class Decoder {
     return null;
   }
 
+  uuid() {
+    let hex = "";
+    for (const byte of this.#read_bytes(16)) {
+      hex += byte.toString(16).padStart(2, "0");
+    }
+    return `${hex.slice(0, 8)}-${hex.slice(8, 12)}-${hex.slice(12, 16)}-${
+      hex.slice(16, 20)
+    }-${hex.slice(20)}`;
+  }
+  datetime() {
+    let secs = this.num("I", 64)();
+    let nanos = this.num("U", 32)();
+    return new Date(Number(secs) * 1000 + Math.floor(nanos / 1_000_000));
+  }
+  decimal() {
+    return this.str();
+  }
+  json(): unknown {
+    return JSON.parse(this.str());
+  }
+
   option<T>(v: Decode<T>): () => Option<T> {
     return () => {
       if (this.bool()) {
@@ -368,6 +464,30 @@ export class BufWriter implements Write {
 
   null(_: null) {}
 
+  uuid(value: string) {
+    const hex = value.replace(/-/g, "");
+    if (!/^[0-9a-fA-F]{32}$/.test(hex)) {
+      throw new Error(`invalid uuid: ${value}`);
+    }
+    const bytes = new Uint8Array(16);
+    for (let i = 0; i < 16; i++) {
+      bytes[i] = parseInt(hex.slice(i * 2, i * 2 + 2), 16);
+    }
+    this.write(bytes);
+  }
+  datetime(date: Date) {
+    const ms = date.getTime();
+    const secs = Math.floor(ms / 1000);
+    this.num("I", 64)(BigInt(secs));
+    this.num("U", 32)((ms - secs * 1000) * 1_000_000);
+  }
+  decimal(value: string) {
+    this.str(value);
+  }
+  json(value: unknown) {
+    this.str(JSON.stringify(value));
+  }
+
   option<T>(v: Encode<T>) {
     return (data: Option<T>) => {
       if (data.value) {
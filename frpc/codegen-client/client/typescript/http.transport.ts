@@ -12,32 +12,159 @@ export interface RpcTransport {
 export interface HttpTransportRequestInit
   extends Pick<RequestInit, "mode" | "keepalive" | "headers"> {}
 
+export interface HttpTransportOption {
+  /** Max size of a server stream frame, Large items are split into multiple frames by the server. */
+  maxChunkSize: number;
+  requestInit?: HttpTransportRequestInit;
+  /** Compress request bodies larger than `threshold` bytes, Server must support the `format`. */
+  requestEncoding?: { format: CompressionFormat; threshold: number };
+  /** Ask server to compress server stream frames individually. */
+  frameEncoding?: CompressionFormat;
+  /** W3C `traceparent` of every request, So that server spans continue the trace. See {@link newTraceparent} */
+  traceparent?: () => string | undefined;
+  /** Bearer token of every request, Called before each request so that it can be refreshed. */
+  token?: () => string | undefined;
+  /**
+   * Server stream is aborted with `unavailable` status, If no frame is received within this many milliseconds.
+   * Server should send heartbeats more often than this.
+   */
+  idleTimeout?: number;
+}
+
+/** Starts a new sampled trace, Ids are random. */
+export function newTraceparent() {
+  const hex = (len: number) =>
+    Array.from(
+      crypto.getRandomValues(new Uint8Array(len)),
+      (b) => b.toString(16).padStart(2, "0"),
+    ).join("");
+  return `00-${hex(16)}-${hex(8)}-01`;
+}
+
+/** Request header, Encodings accepted for server stream frames. */
+const FRAME_ACCEPT_ENCODING = "frpc-accept-encoding";
+/** Response header, Encoding of server stream frames. */
+const FRAME_ENCODING = "frpc-frame-encoding";
+
+/** Response header, Typed status of a rejected call. */
+const STATUS_HEADER = "frpc-status";
+const HTTP_STATUS: Record<number, RpcStatus> = {
+  400: "invalid-argument",
+  401: "unauthenticated",
+  404: "not-found",
+  413: "payload-too-large",
+  429: "resource-exhausted",
+};
+
+export type RpcStatus =
+  | "invalid-argument"
+  | "unauthenticated"
+  | "not-found"
+  | "payload-too-large"
+  | "resource-exhausted"
+  | "unavailable"
+  | "unsupported"
+  | "unknown";
+
+/** Call was rejected by the server, `status` tells why. */
+export class RpcError extends Error {
+  constructor(
+    public status: RpcStatus,
+    message: string,
+    /** Seconds to wait, Before the call is retried. */
+    public retryAfter?: number,
+  ) {
+    super(message);
+    this.name = "RpcError";
+  }
+
+  /** Error of an http `status` code, Without a response. */
+  static fromStatus(status: number) {
+    return new RpcError(
+      HTTP_STATUS[status] ?? "unknown",
+      `rpc failed with status: ${status}`,
+    );
+  }
+
+  static fromResponse(res: Response) {
+    const status = (res.headers.get(STATUS_HEADER) as RpcStatus | null) ??
+      HTTP_STATUS[res.status] ?? "unknown";
+    const retryAfter = res.headers.get("retry-after");
+    return new RpcError(
+      status,
+      `${res.status} ${res.statusText}`.trim(),
+      retryAfter == null ? undefined : Number(retryAfter),
+    );
+  }
+}
+
+const FIN = 0b1000_0000;
+const COMPRESSED = 0b0100_0000;
+/** Item continues in the next frame. */
+const CONTINUED = 0b0010_0000;
+
 export class HttpTransport implements RpcTransport {
   constructor(
     public url: URL | RequestInfo,
-    public option = {
+    public option: HttpTransportOption = {
       maxChunkSize: 8 * 1024 * 1024,
-      requestInit: {} as HttpTransportRequestInit | undefined,
+      requestInit: {},
+      frameEncoding: typeof DecompressionStream == "undefined"
+        ? undefined
+        : "gzip",
     },
   ) {}
-  unary() {
+
+  async #fetch(
+    chunks: Uint8Array[],
+    requestInit: RequestInit,
+    accept?: string,
+  ) {
     const { url, option } = this;
+    const headers = new Headers(option.requestInit?.headers);
+    new Headers(requestInit.headers).forEach((value, name) => {
+      headers.set(name, value);
+    });
+
+    let body = concat_uint8(chunks);
+    const encoding = option.requestEncoding;
+    if (encoding && body.byteLength >= encoding.threshold) {
+      body = await transform(body, new CompressionStream(encoding.format));
+      headers.set("content-encoding", encoding.format);
+    }
+    if (accept) {
+      headers.set(FRAME_ACCEPT_ENCODING, accept);
+    }
+    const traceparent = option.traceparent?.();
+    if (traceparent && !headers.has("traceparent")) {
+      headers.set("traceparent", traceparent);
+    }
+    const token = option.token?.();
+    if (token && !headers.has("authorization")) {
+      headers.set("authorization", `Bearer ${token}`);
+    }
+    return fetch(url, {
+      ...option.requestInit,
+      ...requestInit,
+      headers,
+      method: "POST",
+      body,
+    });
+  }
+
+  unary() {
     const chunks: Uint8Array[] = [];
+    const send = (requestInit: RequestInit) => this.#fetch(chunks, requestInit);
     return {
       write(bytes: Uint8Array) {
         chunks.push(bytes);
       },
       flush() {},
-      async call(requestInit: RequestInit) {
-        const body = concat_uint8(chunks);
-        const res = await fetch(url, {
-          ...option.requestInit,
-          ...requestInit,
-          method: "POST",
-          body,
-        });
+      async call(requestInit: RequestInit = {}) {
+        // `content-encoding` of the response is decoded by `fetch`.
+        const res = await send(requestInit);
         if (!res.ok) {
-          throw new Error("Bad request");
+          throw RpcError.fromResponse(res);
         }
         return new Uint8Array(await res.arrayBuffer());
       },
@@ -45,45 +172,30 @@ export class HttpTransport implements RpcTransport {
   }
 
   sse() {
-    const { url, option } = this;
+    const { option } = this;
     const chunks: Uint8Array[] = [];
+    const send = (requestInit: RequestInit) =>
+      this.#fetch(chunks, requestInit, option.frameEncoding);
     return {
       write(bytes: Uint8Array) {
         chunks.push(bytes);
       },
       flush() {},
 
-      async *call(requestInit: RequestInit) {
-        const body = concat_uint8(chunks);
-        const res = await fetch(url, {
-          ...option.requestInit,
-          ...requestInit,
-          method: "POST",
-          body,
-        });
-        if (!res.body) {
-          throw new Error("unexpected empty body");
-        }
-        let reader = new AsyncBufReader(res.body.getReader());
-
-        while (true) {
-          let head = await reader.readExact(4);
-          let fin = (head[3] & 0b1000_0000) == 0b1000_0000;
-          head[3] &= 0b0111_1111;
-          let len = new DataView(head.buffer).getUint32(0, true);
-          if (fin && len == 0) {
-            return new Uint8Array(0);
+      async *call(requestInit: RequestInit = {}) {
+        const idle = new IdleTimer(option.idleTimeout, requestInit.signal);
+        try {
+          idle.reset();
+          const res = await send({ ...requestInit, signal: idle.signal });
+          if (!res.ok) {
+            throw RpcError.fromResponse(res);
           }
-          if (len > option.maxChunkSize) {
-            throw new Error(
-              `Max chunk size is ${option.maxChunkSize}, But actual size is ${len} bytes`,
-            );
+          if (!res.body) {
+            throw new Error("unexpected empty body");
           }
-          let data = await reader.readExact(len);
-          if (fin) {
-            return data;
-          }
-          yield data;
+          return yield* readFrames(res, option, idle);
+        } finally {
+          idle.clear();
         }
       },
     };
@@ -92,6 +204,247 @@ export class HttpTransport implements RpcTransport {
   async close() {}
 }
 
+/** Frames of a server stream, Fragments of an item are joined. */
+async function* readFrames(
+  res: Response,
+  option: HttpTransportOption,
+  idle: IdleTimer,
+) {
+  const encoding = res.headers.get(FRAME_ENCODING);
+  let reader = new AsyncBufReader(res.body!.getReader());
+  let fragments: Uint8Array[] = [];
+
+  while (true) {
+    idle.reset();
+    let head = await reader.readExact(4);
+    let fin = (head[3] & FIN) == FIN;
+    let compressed = (head[3] & COMPRESSED) == COMPRESSED;
+    let continued = (head[3] & CONTINUED) == CONTINUED;
+    head[3] &= ~(FIN | COMPRESSED | CONTINUED);
+    let len = new DataView(head.buffer, head.byteOffset)
+      .getUint32(0, true);
+    if (fin && len == 0 && fragments.length == 0) {
+      return new Uint8Array(0);
+    }
+    if (!fin && !continued && len == 0) {
+      // Heartbeat
+      continue;
+    }
+    if (len > option.maxChunkSize) {
+      throw new Error(
+        `Max chunk size is ${option.maxChunkSize}, But actual size is ${len} bytes`,
+      );
+    }
+    let data = await reader.readExact(len);
+    if (compressed) {
+      if (encoding != option.frameEncoding) {
+        throw new Error(`unsupported frame encoding: ${encoding}`);
+      }
+      data = await transform(
+        data,
+        new DecompressionStream(encoding as CompressionFormat),
+      );
+      if (data.byteLength > option.maxChunkSize) {
+        throw new Error(
+          `Max chunk size is ${option.maxChunkSize}, But actual size is ${data.byteLength} bytes`,
+        );
+      }
+    }
+    if (continued) {
+      fragments.push(data);
+      continue;
+    }
+    if (fragments.length) {
+      fragments.push(data);
+      data = concat_uint8(fragments);
+      fragments = [];
+    }
+    if (fin) {
+      return data;
+    }
+    yield data;
+  }
+}
+
+/** Aborts the request, If it's idle for `timeout` milliseconds. Also aborted by `signal`. */
+class IdleTimer {
+  #controller = new AbortController();
+  #timer?: ReturnType<typeof setTimeout>;
+  #abort = () => this.#controller.abort(this.parent?.reason);
+
+  constructor(
+    public timeout?: number,
+    public parent?: AbortSignal | null,
+  ) {
+    if (parent?.aborted) {
+      this.#abort();
+    }
+    parent?.addEventListener("abort", this.#abort);
+  }
+
+  get signal() {
+    return this.#controller.signal;
+  }
+
+  reset() {
+    const { timeout } = this;
+    if (timeout == undefined) {
+      return;
+    }
+    clearTimeout(this.#timer);
+    this.#timer = setTimeout(() => {
+      this.#controller.abort(
+        new RpcError("unavailable", `Server stream is idle for ${timeout}ms`),
+      );
+    }, timeout);
+  }
+
+  clear() {
+    clearTimeout(this.#timer);
+    this.parent?.removeEventListener("abort", this.#abort);
+  }
+}
+
+/** Reserved rpc id, That marks a batch request. */
+const BATCH_ID = 0xFFFF;
+/** Status of a call in the batch, `0` is ok. */
+const BATCH_STATUS: RpcStatus[] = [
+  "unknown",
+  "not-found",
+  "invalid-argument",
+  "unsupported",
+  "resource-exhausted",
+  "unauthenticated",
+];
+
+export interface BatchTransportOption {
+  /** Max number of calls in a single request. */
+  maxBatchSize: number;
+}
+
+interface BatchCall {
+  id: number;
+  args: Uint8Array;
+  resolve(output: Uint8Array): void;
+  reject(error: Error): void;
+}
+
+/**
+ * Unary calls made in the same tick are sent as a single request,
+ * Server sends back every result as soon as it's completed.
+ *
+ * Calls with a custom `RequestInit` are not batched.
+ */
+export class BatchTransport implements RpcTransport {
+  #queue: BatchCall[] = [];
+
+  constructor(
+    public inner: RpcTransport,
+    public option: BatchTransportOption = { maxBatchSize: 64 },
+  ) {}
+
+  #flush() {
+    const calls = this.#queue.splice(0, this.option.maxBatchSize);
+    if (this.#queue.length) {
+      queueMicrotask(() => this.#flush());
+    }
+    if (calls.length == 1) {
+      const [{ id, args, resolve, reject }] = calls;
+      const fn = this.inner.unary();
+      fn.write(Uint8Array.of(id & 0xFF, id >> 8));
+      fn.write(args);
+      fn.flush();
+      fn.call({}).then(resolve, reject);
+      return;
+    }
+    this.#send(calls).catch((error) => {
+      for (const call of calls) {
+        call.reject(error);
+      }
+    });
+  }
+
+  async #send(calls: BatchCall[]) {
+    const fn = this.inner.sse();
+    fn.write(Uint8Array.of(BATCH_ID & 0xFF, BATCH_ID >> 8));
+    for (const { id, args } of calls) {
+      const head = new DataView(new ArrayBuffer(6));
+      head.setUint16(0, id, true);
+      head.setUint32(2, args.byteLength, true);
+      fn.write(new Uint8Array(head.buffer));
+      fn.write(args);
+    }
+    fn.flush();
+
+    let pending = calls.length;
+    for await (const frame of fn.call({})) {
+      const view = new DataView(frame.buffer, frame.byteOffset);
+      const call = calls[view.getUint16(0, true)];
+      const status = frame[2];
+      pending--;
+      if (status == 0) {
+        call.resolve(frame.subarray(3));
+      } else {
+        const name = BATCH_STATUS[status] ?? "unknown";
+        call.reject(new RpcError(name, `rpc ${call.id}: ${name}`));
+      }
+    }
+    if (pending > 0) {
+      // Calls that are still pending are rejected by the caller.
+      throw new Error("incomplete batch response");
+    }
+  }
+
+  unary() {
+    const chunks: Uint8Array[] = [];
+    const inner = this.inner;
+    const enqueue = (call: BatchCall) => {
+      if (this.#queue.push(call) == 1) {
+        queueMicrotask(() => this.#flush());
+      }
+    };
+    return {
+      write(bytes: Uint8Array) {
+        chunks.push(bytes);
+      },
+      flush() {},
+      call(requestInit: RequestInit = {}) {
+        if (Object.keys(requestInit).length) {
+          const fn = inner.unary();
+          chunks.forEach((chunk) => fn.write(chunk));
+          fn.flush();
+          return fn.call(requestInit);
+        }
+        const body = concat_uint8(chunks);
+        return new Promise<Uint8Array>((resolve, reject) => {
+          enqueue({
+            id: body[0] | (body[1] << 8),
+            args: body.subarray(2),
+            resolve,
+            reject,
+          });
+        });
+      },
+    };
+  }
+
+  sse() {
+    return this.inner.sse();
+  }
+
+  close() {
+    return this.inner.close();
+  }
+}
+
+async function transform(
+  bytes: Uint8Array,
+  stream: ReadableWritablePair<Uint8Array, Uint8Array>,
+) {
+  const readable = new Blob([bytes]).stream().pipeThrough(stream);
+  return new Uint8Array(await new Response(readable).arrayBuffer());
+}
+
 function concat_uint8(chunks: Uint8Array[]) {
   if (chunks.length == 1) {
     return chunks[0];
@@ -0,0 +1,187 @@
+import { RpcError, type RpcTransport } from "./http.transport.ts";
+export type { RpcTransport, Write } from "./http.transport.ts";
+
+const UNARY = 0;
+const YIELD = 1;
+const RETURN = 2;
+const ERROR = 3;
+
+interface Frame {
+  kind: number;
+  data: Uint8Array;
+}
+
+/** Multiplex many concurrent calls over a single WebSocket connection. */
+export class WebSocketTransport implements RpcTransport {
+  #nextId = 0;
+  #calls: Map<number, Call> = new Map();
+
+  static connect(url: string | URL, protocols?: string | string[]) {
+    return new Promise<WebSocketTransport>((resolve, reject) => {
+      const ws = new WebSocket(url, protocols);
+      ws.onopen = () => resolve(new WebSocketTransport(ws));
+      ws.onerror = () => reject(new Error(`failed to connect: ${url}`));
+    });
+  }
+
+  constructor(public ws: WebSocket) {
+    ws.binaryType = "arraybuffer";
+    ws.onmessage = (ev) => {
+      if (!(ev.data instanceof ArrayBuffer) || ev.data.byteLength < 5) {
+        return;
+      }
+      const id = new DataView(ev.data).getUint32(0, true);
+      const bytes = new Uint8Array(ev.data);
+      this.#calls.get(id)?.push({ kind: bytes[4], data: bytes.subarray(5) });
+    };
+    ws.onclose = () => {
+      for (const call of this.#calls.values()) {
+        call.close(new Error("connection closed"));
+      }
+      this.#calls.clear();
+    };
+  }
+
+  #call(chunks: Uint8Array[], requestInit: RequestInit) {
+    const signal = requestInit.signal;
+    signal?.throwIfAborted();
+
+    const id = this.#nextId;
+    this.#nextId = (this.#nextId + 1) >>> 0;
+
+    const call = new Call();
+    this.#calls.set(id, call);
+    signal?.addEventListener("abort", () => {
+      this.#cancel(id);
+      call.close(signal.reason);
+    }, { once: true });
+
+    const header = new Uint8Array(4);
+    new DataView(header.buffer).setUint32(0, id, true);
+    this.ws.send(concat_uint8([header, ...chunks]));
+    return { id, call };
+  }
+
+  #cancel(id: number) {
+    if (this.#calls.delete(id) && this.ws.readyState == WebSocket.OPEN) {
+      const header = new Uint8Array(4);
+      new DataView(header.buffer).setUint32(0, id, true);
+      this.ws.send(header);
+    }
+  }
+
+  #finish(id: number, call: Call) {
+    call.finished = true;
+    this.#calls.delete(id);
+  }
+
+  unary() {
+    const chunks: Uint8Array[] = [];
+    const call = (requestInit: RequestInit) => this.#call(chunks, requestInit);
+    const finish = (id: number, call: Call) => this.#finish(id, call);
+    return {
+      write(bytes: Uint8Array) {
+        chunks.push(bytes.slice());
+      },
+      flush() {},
+      async call(requestInit: RequestInit = {}) {
+        const { id, call: rpc } = call(requestInit);
+        const { kind, data } = await rpc.next().finally(() => finish(id, rpc));
+        if (kind == UNARY) {
+          return data;
+        }
+        throw rpcError(kind, data);
+      },
+    };
+  }
+
+  sse() {
+    const chunks: Uint8Array[] = [];
+    const call = (requestInit: RequestInit) => this.#call(chunks, requestInit);
+    const finish = (id: number, call: Call) => this.#finish(id, call);
+    const cancel = (id: number) => this.#cancel(id);
+    return {
+      write(bytes: Uint8Array) {
+        chunks.push(bytes.slice());
+      },
+      flush() {},
+      async *call(requestInit: RequestInit = {}) {
+        const { id, call: rpc } = call(requestInit);
+        try {
+          while (true) {
+            const { kind, data } = await rpc.next();
+            if (kind == YIELD) {
+              yield data;
+              continue;
+            }
+            finish(id, rpc);
+            if (kind == RETURN) {
+              return data;
+            }
+            throw rpcError(kind, data);
+          }
+        } finally {
+          // Stream was dropped before it has finished.
+          if (!rpc.finished) cancel(id);
+        }
+      },
+    };
+  }
+
+  async close() {
+    this.ws.close();
+  }
+}
+
+class Call {
+  #frames: Frame[] = [];
+  #error?: { reason: unknown };
+  #wake?: () => void;
+  finished = false;
+
+  push(frame: Frame) {
+    this.#frames.push(frame);
+    this.#wakeup();
+  }
+
+  close(reason: unknown) {
+    this.#error = { reason };
+    this.#wakeup();
+  }
+
+  #wakeup() {
+    this.#wake?.();
+    this.#wake = undefined;
+  }
+
+  async next(): Promise<Frame> {
+    while (true) {
+      const frame = this.#frames.shift();
+      if (frame) return frame;
+      if (this.#error) throw this.#error.reason;
+      await new Promise<void>((resolve) => (this.#wake = resolve));
+    }
+  }
+}
+
+function rpcError(kind: number, data: Uint8Array) {
+  if (kind == ERROR && data.byteLength >= 2) {
+    const status = new DataView(data.buffer, data.byteOffset).getUint16(0, true);
+    return RpcError.fromStatus(status);
+  }
+  return new RpcError("unknown", `unknown frame kind: ${kind}`);
+}
+
+function concat_uint8(chunks: Uint8Array[]) {
+  let size = 0;
+  for (const chunk of chunks) {
+    size += chunk.byteLength;
+  }
+  const bytes = new Uint8Array(size);
+  let offset = 0;
+  for (const chunk of chunks) {
+    bytes.set(chunk, offset);
+    offset += chunk.byteLength;
+  }
+  return bytes;
+}
@@ -15,6 +15,10 @@ impl<'a> From<&'a TypeDef> for CodeWriter<'a> {
 impl CodeWriter<'_> {
     pub fn generate_typescript_binding(&self, config: &typescript::Config) -> Result {
         fs::create_dir_all(&config.out_dir)?;
+        let ext = match config.preserve_import_extension {
+            true => ".ts",
+            false => "",
+        };
 
         let prelude_path = config.out_dir.join("databuf.lib.ts");
         if !prelude_path.exists() {
@@ -26,11 +30,12 @@ impl CodeWriter<'_> {
                 config.out_dir.join("http.transport.ts"),
                 include_bytes!("../client/typescript/http.transport.ts"),
             )?;
+            fs::write(
+                config.out_dir.join("ws.transport.ts"),
+                include_str!("../client/typescript/ws.transport.ts")
+                    .replace("./http.transport.ts", &format!("./http.transport{ext}")),
+            )?;
         }
-        let ext = match config.preserve_import_extension {
-            true => ".ts",
-            false => "",
-        };
         let mut code = format!("import * as use from './databuf.lib{ext}'\n");
         write!(code, "{}", self.codegen.typescript())?;
         let filename = format!("{}.ts", self.codegen.type_def.name);
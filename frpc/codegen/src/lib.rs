@@ -24,6 +24,10 @@ impl<'a> From<&'a TypeDef> for CodeGen<'a> {
             FuncOutput::ServerStream {
                 yield_ty,
                 return_ty,
+            }
+            | FuncOutput::ResumableStream {
+                yield_ty,
+                return_ty,
             } => vec![yield_ty, return_ty],
         }));
 
@@ -44,6 +44,11 @@ fn fmt_ty<'a>(ty: &'a Ty, scope: &'a str, ident_map: &'a IdentMap) -> fmt!(type
         // Ty::char => write!(f, "d.char"),
         Ty::String => write!(f, "d.str"),
 
+        Ty::Uuid => write!(f, "d.uuid"),
+        Ty::DateTime => write!(f, "d.datetime"),
+        Ty::Decimal => write!(f, "d.decimal"),
+        Ty::Json => write!(f, "d.json"),
+
         Ty::Option(ty) => write!(f, "d.option({})", fmt_ty(ty, scope, ident_map)),
         Ty::Result(ty) => write!(
             f,
@@ -24,11 +24,15 @@ pub fn main(f: &mut impl Write, type_def: &TypeDef, ident_map: &IdentMap) -> Res
             }
             writeln!(f, ") {{")?;
             {
-                let rpc_type = match output {
-                    FuncOutput::Unary(_) => "unary",
-                    FuncOutput::ServerStream { .. } => "sse",
+                let call = match output {
+                    FuncOutput::Unary(_) => "make_call(this.rpc, \"unary\",",
+                    FuncOutput::ServerStream { .. } => "make_call(this.rpc, \"sse\",",
+                    FuncOutput::ResumableStream { .. } => "make_resumable_call(this.rpc,",
                 };
-                writeln!(f, "return (requestInit: RequestInit = {{}}) => use.make_call(this.rpc, {rpc_type:?}, {index}, requestInit,")?;
+                writeln!(
+                    f,
+                    "return (requestInit: RequestInit = {{}}) => use.{call} {index}, requestInit,"
+                )?;
                 writeln!(f, "d => {{")?;
                 for (num, arg) in args.iter().enumerate() {
                     match arg {
@@ -53,6 +57,10 @@ pub fn main(f: &mut impl Write, type_def: &TypeDef, ident_map: &IdentMap) -> Res
                     FuncOutput::ServerStream {
                         return_ty,
                         yield_ty,
+                    }
+                    | FuncOutput::ResumableStream {
+                        return_ty,
+                        yield_ty,
                     } => {
                         writeln!(f, "async function* (s) {{")?;
                         writeln!(f, "while (true) {{")?;
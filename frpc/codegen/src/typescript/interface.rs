@@ -121,7 +121,9 @@ pub fn fmt_js_ty(ty: &Ty, ident_map: &IdentMap) -> String {
         Ty::u64 | Ty::i64 | Ty::u128 | Ty::i128 => "bigint".into(),
         Ty::bool => "boolean".into(),
         // Ty::char |
-        Ty::String => "string".into(),
+        Ty::String | Ty::Uuid | Ty::Decimal => "string".into(),
+        Ty::DateTime => "Date".into(),
+        Ty::Json => "unknown".into(),
 
         Ty::Array { ty, .. } | Ty::Set { ty, .. } => match **ty {
             Ty::u8 => "Uint8Array",
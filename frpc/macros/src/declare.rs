@@ -1,7 +1,10 @@
 use std::collections::HashSet;
 
+use frpc_transport_core::BATCH_ID;
 use quote2::{proc_macro2::*, quote, Quote, Token};
 
+const RESERVED_ID: &str = "rpc id is reserved for batch requests, See `frpc::BATCH_ID`";
+
 macro_rules! parse {
     ($tokens: ident, $errors: ident, $msg: literal, $($tt:tt)*) => ({
         let msg = $msg;
@@ -32,6 +35,12 @@ pub fn expand(input: TokenStream, output: &mut TokenStream) {
 
                 quote!(output, {
                     struct #service_name;
+                    impl ::std::clone::Clone for #service_name {
+                        fn clone(&self) -> Self {
+                            *self
+                        }
+                    }
+                    impl ::std::marker::Copy for #service_name {}
                 });
 
                 let service_block =
@@ -64,6 +73,14 @@ fn expand_service(
     let mut tokens = service_block.stream().into_iter();
     let mut has_state = false;
     let mut funcs = Token(TokenStream::new());
+    let mut futures = Token(TokenStream::new());
+    let mut variants = Token(TokenStream::new());
+    let mut bounds = Token(TokenStream::new());
+    let mut poll_arms = Token(TokenStream::new());
+    let mut streaming = Token(TokenStream::new());
+    let mut identity = Token(TokenStream::new());
+    let mut names = Token(TokenStream::new());
+    let mut reserved = Token(TokenStream::new());
     let mut items = Token(TokenStream::new());
     let mut func_types = TokenStream::new();
     let mut import_map = HashSet::new();
@@ -82,6 +99,13 @@ fn expand_service(
                 let id = &parse!(tokens, errors, "expected rpc id", v @ (TokenTree::Literal(_) | TokenTree::Group(_)) => v);
                 parse!(tokens, errors, "expected `;`", TokenTree::Punct(v) if v.as_char() == ';' => v);
 
+                if let TokenTree::Literal(lit) = id {
+                    let lit = syn::LitInt::from(lit.clone());
+                    if lit.base10_parse::<u16>().is_ok_and(|v| v == BATCH_ID) {
+                        errors.push(syn::Error::new(lit.span(), RESERVED_ID));
+                        continue;
+                    }
+                }
                 if let Some(prev) = import_map.replace(name.clone()) {
                     errors.push(syn::Error::new(
                         prev.span(),
@@ -90,8 +114,30 @@ fn expand_service(
                     continue;
                 }
                 let rpc_ident = name.to_string();
+                let index = import_map.len() - 1;
+                let variant = Ident::new(&format!("R{index}"), Span::call_site());
+                let future = Ident::new(&format!("F{index}"), Span::call_site());
                 quote!(funcs, {
-                    #id => Output::_produce(#name, state, cursor, transport),
+                    #id => ::std::option::Option::Some(__Rpc::#variant(Output::produce(#name, state, cursor, transport))),
+                });
+                quote!(futures, { #future, });
+                quote!(variants, { #variant(#future), });
+                quote!(bounds, { #future: Future<Output = ()>, });
+                quote!(poll_arms, {
+                    __Rpc::#variant(ref mut fut) => ::std::pin::Pin::new_unchecked(fut).poll(cx),
+                });
+                quote!(streaming, {
+                    #id => ::frpc::__private::is_streaming::<Self::State, _, _>(&#name),
+                });
+                quote!(identity, {
+                    #id => ::frpc::__private::requires_identity::<Self::State, _, _>(&#name),
+                });
+                quote!(names, {
+                    #id => ::std::option::Option::Some(#rpc_ident),
+                });
+                // Constant ids can only be checked at compile time.
+                quote!(reserved, {
+                    ::std::assert!(#id != ::frpc::BATCH_ID, #RESERVED_ID);
                 });
                 let docs_str = rpc_docs.as_str();
                 quote!(func_types, {
@@ -125,6 +171,8 @@ fn expand_service(
             #default_state
             #items
 
+            const NAME: &'static str = #service_ident;
+
             fn execute<'fut, TR: ::frpc::Transport + ::std::marker::Send>(
                 state: Self::State,
                 id: u16,
@@ -133,13 +181,62 @@ fn expand_service(
             ) -> ::std::option::Option<impl ::std::future::Future<Output = ()> + ::std::marker::Send + 'fut>
             {
                 use ::frpc::Output;
+                use ::std::future::Future;
+
+                // Every rpc has its own variant, So the call isn't boxed.
+                #[allow(clippy::large_enum_variant)]
+                enum __Rpc<#futures> {
+                    #variants
+                }
+
+                impl<#futures> Future for __Rpc<#futures>
+                where
+                    #bounds
+                {
+                    type Output = ();
+                    fn poll(
+                        self: ::std::pin::Pin<&mut Self>,
+                        cx: &mut ::std::task::Context<'_>,
+                    ) -> ::std::task::Poll<()> {
+                        // SAFETY: The future is never moved out of the pinned variant.
+                        unsafe {
+                            match *self.get_unchecked_mut() {
+                                #poll_arms
+                            }
+                        }
+                    }
+                }
+
                 match id {
                     #funcs
                     _ => ::std::option::Option::None
                 }
             }
+
+            fn is_streaming(id: u16) -> bool {
+                match id {
+                    #streaming
+                    _ => false
+                }
+            }
+
+            fn requires_identity(id: u16) -> bool {
+                match id {
+                    #identity
+                    _ => false
+                }
+            }
+
+            fn rpc_name(id: u16) -> ::std::option::Option<&'static str> {
+                match id {
+                    #names
+                    _ => ::std::option::Option::None
+                }
+            }
         }
 
+        const _: () = { #reserved };
+
         #[cfg(debug_assertions)]
         impl ::std::convert::From<#service_name> for ::frpc::__private::frpc_message::TypeDef {
             fn from(_: #service_name) -> Self {
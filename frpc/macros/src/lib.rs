@@ -1,4 +1,5 @@
 mod declare;
+mod remote;
 
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
@@ -13,47 +14,95 @@ macro_rules! crate_path {
     });
 }
 
-fn message_expand(input: TokenStream, f: impl FnOnce(databuf_derive_impl::Expand)) -> TokenStream {
+fn message_expand(input: TokenStream, encoder: bool, decoder: bool) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input);
     let mut output = TokenStream2::new();
 
+    let (remote, with) = match (remote::get_remote(&input.attrs), remote::get_with(&input)) {
+        (Ok(remote), Ok(with)) => (remote, with),
+        (Err(err), _) | (_, Err(err)) => return err.to_compile_error().into(),
+    };
     let is_unit_enum = databuf_derive_impl::is_unit_enum(&input);
     let enum_repr = databuf_derive_impl::get_enum_repr(&input.attrs);
 
     type_id_derive_impl::expand(
         crate_path!(::frpc::__private::frpc_message::type_id),
-        &input,
+        &remote::shadowed(&input, &with),
         &mut output,
         is_unit_enum,
         enum_repr.as_ref(),
+        remote.as_ref().map(|(name, _)| name.as_str()),
     );
-    f(databuf_derive_impl::Expand {
+    if let Some((_, path)) = &remote {
+        remote::expand(&input, path, encoder, decoder, &mut output);
+    }
+    if with.iter().any(Option::is_some) {
+        remote::expand_with(&input, &with, encoder, decoder, &mut output);
+        return output.into();
+    }
+    let mut expand = databuf_derive_impl::Expand {
         crate_path: crate_path!(::frpc::databuf),
         input: &input,
         output: &mut output,
         is_unit_enum,
         enum_repr,
-    });
+    };
+    if encoder {
+        expand.encoder();
+    }
+    if decoder {
+        expand.decoder();
+    }
     output.into()
 }
 
 /// Represent both [Input] + [Output]
-#[proc_macro_derive(Message)]
+///
+/// Types from other crates can be used via a local shadow definition (mirroring serde's `remote`),
+/// whose fields must match the remote type:
+///
+/// ```ignore
+/// #[derive(Message)]
+/// #[frpc(remote = "other::Point")]
+/// struct PointDef {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// async fn shift(point: PointDef) -> PointDef {
+///     let other::Point { x, y } = point.into();
+///     other::Point { x: x + 1, y: y + 1 }.into()
+/// }
+/// ```
+///
+/// This generates `From` conversions between `PointDef` and `other::Point`, and registers
+/// the type as `other::Point` (resolved against the current module), so the generated client code stays the same.
+///
+/// The remote type can also be a field of another message, Through its shadow definition.
+/// Such a field is encoded as the shadow definition, So the remote type must be `Clone`:
+///
+/// ```ignore
+/// #[derive(Message)]
+/// struct Line {
+///     #[frpc(with = "PointDef")]
+///     start: other::Point,
+///     #[frpc(with = "PointDef")]
+///     end: other::Point,
+/// }
+/// ```
+#[proc_macro_derive(Message, attributes(frpc))]
 pub fn message(input: TokenStream) -> TokenStream {
-    message_expand(input, |mut expand| {
-        expand.encoder();
-        expand.decoder();
-    })
+    message_expand(input, true, true)
 }
 
-#[proc_macro_derive(Input)]
+#[proc_macro_derive(Input, attributes(frpc))]
 pub fn input(input: TokenStream) -> TokenStream {
-    message_expand(input, |mut expand| expand.decoder())
+    message_expand(input, false, true)
 }
 
-#[proc_macro_derive(Output)]
+#[proc_macro_derive(Output, attributes(frpc))]
 pub fn output(input: TokenStream) -> TokenStream {
-    message_expand(input, |mut expand| expand.encoder())
+    message_expand(input, true, false)
 }
 
 #[proc_macro]
@@ -0,0 +1,267 @@
+use quote2::{proc_macro2::*, quote};
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Index, LitStr, Path, Result, Type};
+
+/// Parse `#[frpc(<name> = "...")]` attribute.
+fn get_attr(attrs: &[Attribute], name: &str) -> Result<Option<LitStr>> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("frpc")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident(name) {
+                return Err(meta.error(format!("unsupported frpc attribute, expected `{name}`")));
+            }
+            value = Some(meta.value()?.parse()?);
+            Ok(())
+        })?;
+    }
+    Ok(value)
+}
+
+/// Parse `#[frpc(remote = "path::to::Type")]` attribute.
+pub fn get_remote(attrs: &[Attribute]) -> Result<Option<(String, Path)>> {
+    let Some(lit) = get_attr(attrs, "remote")? else {
+        return Ok(None);
+    };
+    Ok(Some((lit.value(), lit.parse()?)))
+}
+
+/// Parse `#[frpc(with = "ShadowType")]` attribute of every field.
+///
+/// Only struct fields are supported, Fields of enum variants are rejected.
+pub fn get_with(input: &DeriveInput) -> Result<Vec<Option<Type>>> {
+    match &input.data {
+        Data::Struct(data) => data
+            .fields
+            .iter()
+            .map(|field| {
+                get_attr(&field.attrs, "with")?
+                    .map(|lit| lit.parse())
+                    .transpose()
+            })
+            .collect(),
+        Data::Enum(data) => {
+            for field in data.variants.iter().flat_map(|v| &v.fields) {
+                if let Some(lit) = get_attr(&field.attrs, "with")? {
+                    return Err(Error::new(
+                        lit.span(),
+                        "`with` is only supported on struct fields",
+                    ));
+                }
+            }
+            Ok(Vec::new())
+        }
+        Data::Union(_) => Ok(Vec::new()),
+    }
+}
+
+/// Same input, But every `with` field has the type of its shadow definition, Used for `TypeId`.
+pub fn shadowed(input: &DeriveInput, with: &[Option<Type>]) -> DeriveInput {
+    let mut input = input.clone();
+    if let Data::Struct(data) = &mut input.data {
+        for (field, with) in data.fields.iter_mut().zip(with) {
+            if let Some(with) = with {
+                field.ty = with.clone();
+            }
+        }
+    }
+    input
+}
+
+/// `Encode` and `Decode` of a struct, Where `with` fields are converted through their shadow definition.
+pub fn expand_with(
+    input: &DeriveInput,
+    with: &[Option<Type>],
+    encoder: bool,
+    decoder: bool,
+    output: &mut TokenStream,
+) {
+    let ident = &input.ident;
+    let Data::Struct(data) = &input.data else {
+        return;
+    };
+    if let Some(param) = input.generics.params.first() {
+        let err = Error::new_spanned(param, "`with` isn't supported for generic type");
+        return output.extend(err.to_compile_error());
+    }
+    let mut encode = TokenStream::new();
+    let mut decode = TokenStream::new();
+    for (i, (field, with)) in data.fields.iter().zip(with).enumerate() {
+        let mut member = TokenStream::new();
+        match &field.ident {
+            Some(name) => quote!(member, { #name }),
+            None => {
+                let index = Index::from(i);
+                quote!(member, { #index });
+            }
+        }
+        let member_ = member.clone();
+        match with {
+            Some(with) => {
+                quote!(encode, { <#with>::__encode::<CONFIG, _>(&self.#member, __c)?; });
+                quote!(decode, { #member_: <#with>::__decode::<CONFIG, _>(__c)?, });
+            }
+            None => {
+                quote!(encode, { ::frpc::databuf::Encode::encode::<CONFIG>(&self.#member, __c)?; });
+                quote!(decode, { #member_: ::frpc::databuf::Decode::decode::<CONFIG>(__c)?, });
+            }
+        }
+    }
+    if encoder {
+        quote!(output, {
+            impl ::frpc::databuf::Encode for #ident {
+                fn encode<const CONFIG: u16>(
+                    &self,
+                    __c: &mut (impl ::std::io::Write + ?::std::marker::Sized),
+                ) -> ::std::io::Result<()> {
+                    #encode
+                    ::std::result::Result::Ok(())
+                }
+            }
+        });
+    }
+    if decoder {
+        quote!(output, {
+            impl<'de> ::frpc::databuf::Decode<'de> for #ident {
+                fn decode<const CONFIG: u16>(__c: &mut &'de [u8]) -> ::frpc::databuf::Result<Self> {
+                    ::std::result::Result::Ok(Self { #decode })
+                }
+            }
+        });
+    }
+}
+
+/// Generate conversions between the local shadow definition and the remote type.
+///
+/// And `__encode` / `__decode` functions, Used by `#[frpc(with = "...")]` fields of the remote type.
+pub fn expand(
+    input: &DeriveInput,
+    remote: &Path,
+    encoder: bool,
+    decoder: bool,
+    output: &mut TokenStream,
+) {
+    let mut remote_path = TokenStream::new();
+    quote!(remote_path, { #remote });
+    let mut shadow = TokenStream::new();
+    let ident = &input.ident;
+    quote!(shadow, { #ident });
+
+    let (from_remote, into_remote) = match &input.data {
+        Data::Struct(data) => (
+            convert(&remote_path, &shadow, &data.fields),
+            convert(&shadow, &remote_path, &data.fields),
+        ),
+        Data::Enum(data) => {
+            let mut from_remote = TokenStream::new();
+            let mut into_remote = TokenStream::new();
+            for v in &data.variants {
+                let name = &v.ident;
+                for (arms, from, to) in [
+                    (&mut from_remote, &remote_path, &shadow),
+                    (&mut into_remote, &shadow, &remote_path),
+                ] {
+                    let (from, to) = (from.clone(), to.clone());
+                    let (pat, expr) = (bindings(&v.fields), bindings(&v.fields));
+                    quote!(arms, {
+                        #from::#name #pat => #to::#name #expr,
+                    });
+                }
+            }
+            (match_arms(from_remote), match_arms(into_remote))
+        }
+        Data::Union(_) => {
+            let err = Error::new(ident.span(), "`remote` is not supported for `union`");
+            return output.extend(err.to_compile_error());
+        }
+    };
+    let shadow_ = shadow.clone();
+    let remote_path_ = remote_path.clone();
+    quote!(output, {
+        impl ::std::convert::From<#remote_path> for #shadow {
+            fn from(__v: #remote_path_) -> Self {
+                #from_remote
+            }
+        }
+        impl ::std::convert::From<#shadow_> for #remote {
+            fn from(__v: #ident) -> Self {
+                #into_remote
+            }
+        }
+    });
+    if encoder {
+        // Encoded as the shadow definition, So the remote type has to be `Clone`.
+        quote!(output, {
+            impl #ident {
+                #[doc(hidden)]
+                pub fn __encode<const CONFIG: u16, __R>(
+                    __v: &__R,
+                    __c: &mut (impl ::std::io::Write + ?::std::marker::Sized),
+                ) -> ::std::io::Result<()>
+                where
+                    __R: ::std::clone::Clone + ::std::convert::Into<Self>,
+                {
+                    let __v: Self = __v.clone().into();
+                    ::frpc::databuf::Encode::encode::<CONFIG>(&__v, __c)
+                }
+            }
+        });
+    }
+    if decoder {
+        quote!(output, {
+            impl #ident {
+                #[doc(hidden)]
+                pub fn __decode<'de, const CONFIG: u16, __R>(
+                    __c: &mut &'de [u8],
+                ) -> ::frpc::databuf::Result<__R>
+                where
+                    Self: ::std::convert::Into<__R>,
+                {
+                    let __v = <Self as ::frpc::databuf::Decode<'de>>::decode::<CONFIG>(__c)?;
+                    ::std::result::Result::Ok(__v.into())
+                }
+            }
+        });
+    }
+}
+
+fn match_arms(arms: TokenStream) -> TokenStream {
+    let mut out = TokenStream::new();
+    quote!(out, {
+        match __v { #arms }
+    });
+    out
+}
+
+fn convert(from: &TokenStream, to: &TokenStream, fields: &Fields) -> TokenStream {
+    let (from, to) = (from.clone(), to.clone());
+    let (pat, expr) = (bindings(fields), bindings(fields));
+    let mut out = TokenStream::new();
+    quote!(out, {
+        let #from #pat = __v;
+        #to #expr
+    });
+    out
+}
+
+/// `{ a, b }`, `(_0, _1)` or nothing, Used both as pattern and as expression.
+fn bindings(fields: &Fields) -> TokenStream {
+    let mut names = TokenStream::new();
+    let mut out = TokenStream::new();
+    match fields {
+        Fields::Named(fields) => {
+            for field in &fields.named {
+                let name = &field.ident;
+                quote!(names, { #name, });
+            }
+            out.extend([TokenTree::Group(Group::new(Delimiter::Brace, names))]);
+        }
+        Fields::Unnamed(fields) => {
+            for i in 0..fields.unnamed.len() {
+                let name = Ident::new(&format!("_{i}"), Span::call_site());
+                quote!(names, { #name, });
+            }
+            out.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, names))]);
+        }
+        Fields::Unit => {}
+    }
+    out
+}
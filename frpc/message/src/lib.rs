@@ -10,7 +10,16 @@ use serde::{Deserialize, Serialize};
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FuncOutput {
     Unary(Ty),
-    ServerStream { yield_ty: Ty, return_ty: Ty },
+    ServerStream {
+        yield_ty: Ty,
+        return_ty: Ty,
+    },
+    /// Same as `ServerStream`, But every yielded item is prefixed with a cursor,
+    /// Client resumes the stream from the last one.
+    ResumableStream {
+        yield_ty: Ty,
+        return_ty: Ty,
+    },
 }
 
 #[cfg_attr(feature = "hash", derive(Hash))]
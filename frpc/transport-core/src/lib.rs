@@ -1,9 +1,13 @@
 use std::{
     future::Future,
     io::{self, Result},
+    sync::Arc,
     task::{Context, Poll},
 };
 
+/// Reserved rpc id, That marks a batch request. No rpc can use it.
+pub const BATCH_ID: u16 = u16::MAX;
+
 #[doc(hidden)]
 pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
 
@@ -22,10 +26,53 @@ pub trait Transport {
         &mut self,
         poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<Result<bool>> + Send,
     ) -> impl Future<Output = ()> + Send;
+
+    /// Rest of the request body, that wasn't passed to [`Service::execute`] as `cursor`.
+    ///
+    /// Only used for rpc, where [`Service::is_streaming`] returns `true`.
+    fn take_body(&mut self) -> Option<Box<dyn Body>> {
+        None
+    }
+
+    /// Authenticated caller of the request, `None` if it's anonymous.
+    fn identity(&self) -> Option<Arc<Identity>> {
+        None
+    }
+
+    /// Cursor of the last item received by the client, Sent when it resumes a server stream.
+    fn resume_cursor(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Authenticated caller of a request, Verified by the transport.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Identity {
+    /// `sub` claim of the bearer token.
+    pub subject: Option<String>,
+    /// `scope` claim of the bearer token, Split by whitespace.
+    pub scopes: Vec<String>,
+    /// DER encoded certificate of the client, Verified by mTLS.
+    pub certificate: Option<Arc<[u8]>>,
+}
+
+/// Request body, that is received incrementally.
+pub trait Body: Send {
+    /// Returns `None` when the body has ended.
+    fn poll_data(&mut self, cx: &mut Context) -> Poll<Option<Result<Vec<u8>>>>;
+
+    /// Number of bytes that are yet to be received, `None` if it's unknown.
+    fn remaining(&self) -> Option<usize> {
+        None
+    }
 }
 
 pub trait Service {
     type State;
+
+    /// Name of the service, Used for diagnostics.
+    const NAME: &'static str = "";
+
     fn execute<'fut, TR>(
         state: Self::State,
         id: u16,
@@ -34,4 +81,22 @@ pub trait Service {
     ) -> Option<impl Future<Output = ()> + Send + 'fut>
     where
         TR: Transport + Send;
+
+    /// Last argument of the rpc is consumed as a byte stream,
+    /// So transports may pass only the start of request body as `cursor`, See [`Transport::take_body`].
+    fn is_streaming(_id: u16) -> bool {
+        false
+    }
+
+    /// Rpc takes the authenticated caller, See [`Transport::identity`].
+    ///
+    /// Transports reject anonymous calls, Before [`Service::execute`].
+    fn requires_identity(_id: u16) -> bool {
+        false
+    }
+
+    /// Name of the rpc, Used for diagnostics.
+    fn rpc_name(_id: u16) -> Option<&'static str> {
+        None
+    }
 }
@@ -0,0 +1,225 @@
+use super::*;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Mutex,
+};
+use tokio::{net::TcpStream, net::ToSocketAddrs, task::AbortHandle};
+
+/// `None` once the connection is closed.
+type Calls = Arc<Mutex<Option<HashMap<u32, mpsc::Sender<(u8, Vec<u8>)>>>>>;
+
+/// Default of [`Client::with_max_response_size`].
+pub const DEFAULT_MAX_RESPONSE_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Max unread frames of a call, A call that falls further behind is cancelled.
+pub const MAX_PENDING_FRAMES: usize = 64;
+
+/// Many concurrent calls share one connection, Arguments and outputs are encoded by the caller.
+pub struct Client {
+    next_id: AtomicU32,
+    calls: Calls,
+    tx: mpsc::UnboundedSender<Vec<u8>>,
+    reader: AbortHandle,
+}
+
+impl Client {
+    pub async fn connect_tcp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream))
+    }
+
+    #[cfg(unix)]
+    pub async fn connect_unix(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        Ok(Self::new(tokio::net::UnixStream::connect(path).await?))
+    }
+
+    /// Must be called within tokio runtime.
+    pub fn new(io: impl AsyncRead + AsyncWrite + Send + 'static) -> Self {
+        Self::with_max_response_size(io, DEFAULT_MAX_RESPONSE_SIZE)
+    }
+
+    /// Connection is closed and every pending call is failed, If a frame is larger than `max_response_size`.
+    pub fn with_max_response_size(
+        io: impl AsyncRead + AsyncWrite + Send + 'static,
+        max_response_size: u32,
+    ) -> Self {
+        let (reader, writer) = tokio::io::split(io);
+        let (tx, mut rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let calls = Calls::new(Mutex::new(Some(HashMap::new())));
+
+        tokio::spawn(async move {
+            let mut writer = BufWriter::new(writer);
+            while let Some(frame) = rx.recv().await {
+                writer.write_all(&frame).await?;
+                while let Ok(frame) = rx.try_recv() {
+                    writer.write_all(&frame).await?;
+                }
+                writer.flush().await?;
+            }
+            io::Result::Ok(())
+        });
+        let reader = tokio::spawn({
+            let calls = calls.clone();
+            let writer_tx = tx.clone();
+            async move {
+                let mut reader = BufReader::new(reader);
+                while let Ok(Some((fin, call_id, mut data))) =
+                    read_frame(&mut reader, max_response_size.saturating_add(5)).await
+                {
+                    if data.len() < 5 {
+                        break;
+                    }
+                    let kind = data[4];
+                    data.drain(..5);
+                    let mut calls = calls.lock().unwrap();
+                    let Some(calls) = calls.as_mut() else {
+                        break;
+                    };
+                    let Some(call) = calls.get(&call_id) else {
+                        continue;
+                    };
+                    if call.try_send((kind, data)).is_err() && !fin {
+                        // Isn't read in time, Or dropped.
+                        calls.remove(&call_id);
+                        let mut buf = new_frame(call_id);
+                        let _ = finish_frame(&mut buf, false);
+                        let _ = writer_tx.send(buf);
+                    } else if fin {
+                        calls.remove(&call_id);
+                    }
+                }
+                // Pending calls are failed, by dropping their senders. New calls fail immediately.
+                calls.lock().unwrap().take();
+            }
+        });
+        Self {
+            next_id: AtomicU32::new(0),
+            calls,
+            tx,
+            reader: reader.abort_handle(),
+        }
+    }
+
+    fn call(&self, rpc_id: u16, args: &[u8]) -> io::Result<Call> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut buf = new_frame(id);
+        buf.extend_from_slice(&rpc_id.to_le_bytes());
+        buf.extend_from_slice(args);
+        finish_frame(&mut buf, true)?;
+
+        let (tx, rx) = mpsc::channel(MAX_PENDING_FRAMES);
+        match self.calls.lock().unwrap().as_mut() {
+            Some(calls) => calls.insert(id, tx),
+            None => return Err(closed()),
+        };
+        if self.tx.send(buf).is_err() {
+            return Err(closed());
+        }
+        Ok(Call {
+            id,
+            rx,
+            finished: false,
+            calls: self.calls.clone(),
+            tx: self.tx.clone(),
+        })
+    }
+
+    /// `args` are the encoded arguments of the rpc, Returns the encoded output.
+    pub async fn unary(&self, rpc_id: u16, args: &[u8]) -> io::Result<Vec<u8>> {
+        let mut call = self.call(rpc_id, args)?;
+        let (kind, data) = call.next().await?;
+        call.finished = true;
+        match kind {
+            kind::UNARY => Ok(data),
+            _ => Err(rpc_error(kind, &data)),
+        }
+    }
+
+    pub fn server_stream(&self, rpc_id: u16, args: &[u8]) -> io::Result<ServerStream> {
+        Ok(ServerStream(self.call(rpc_id, args)?))
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Call is cancelled on drop, if it wasn't finished.
+struct Call {
+    id: u32,
+    rx: mpsc::Receiver<(u8, Vec<u8>)>,
+    finished: bool,
+    calls: Calls,
+    tx: mpsc::UnboundedSender<Vec<u8>>,
+}
+
+impl Call {
+    async fn next(&mut self) -> io::Result<(u8, Vec<u8>)> {
+        match self.rx.recv().await {
+            Some(frame) => Ok(frame),
+            // Connection is still open, So the call has fallen behind.
+            None if self.calls.lock().unwrap().is_some() => Err(io::Error::other(
+                "call cancelled, frames aren't read in time",
+            )),
+            None => Err(closed()),
+        }
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let mut calls = self.calls.lock().unwrap();
+        if let Some(Some(_)) = calls.as_mut().map(|calls| calls.remove(&self.id)) {
+            let mut buf = new_frame(self.id);
+            let _ = finish_frame(&mut buf, false);
+            let _ = self.tx.send(buf);
+        }
+    }
+}
+
+pub enum StreamItem {
+    Yield(Vec<u8>),
+    Return(Vec<u8>),
+}
+
+pub struct ServerStream(Call);
+
+impl ServerStream {
+    /// Returns `None` after the stream has returned.
+    pub async fn next(&mut self) -> Option<io::Result<StreamItem>> {
+        if self.0.finished {
+            return None;
+        }
+        let result = match self.0.next().await {
+            Ok((kind::YIELD, data)) => return Some(Ok(StreamItem::Yield(data))),
+            Ok((kind::RETURN, data)) => Ok(StreamItem::Return(data)),
+            Ok((kind, data)) => Err(rpc_error(kind, &data)),
+            Err(err) => Err(err),
+        };
+        self.0.finished = true;
+        Some(result)
+    }
+}
+
+fn closed() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "connection closed")
+}
+
+fn rpc_error(kind: u8, data: &[u8]) -> io::Error {
+    match (kind, data) {
+        (kind::ERROR, [a, b, ..]) => io::Error::other(format!(
+            "rpc failed with status: {}",
+            u16::from_le_bytes([*a, *b])
+        )),
+        _ => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown frame kind: {kind}"),
+        ),
+    }
+}
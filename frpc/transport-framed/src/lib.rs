@@ -0,0 +1,292 @@
+//! Length-prefixed framed transport over `TcpStream` or `UnixStream`,
+//! For service-to-service calls without HTTP/TLS overhead.
+//!
+//! Every frame starts with the same 4 bytes header as server stream frames
+//! (31 bits little-endian length, and FIN bit), followed by `u32` (little-endian) call id.
+//!
+//! - Request: `[header (FIN)][call id][rpc id: u16][arguments]`
+//! - Cancel: `[header][call id]`
+//! - Response: `[header][call id][kind: u8][payload]`, FIN is set on the last frame of a call.
+//!
+//! A request with the id of an unfinished call, Cancels that call first.
+//! Calls are anonymous, So rpc that take the authenticated caller fail with `401` status.
+mod client;
+
+pub use client::*;
+use frpc_transport_core::*;
+use std::{
+    collections::HashMap,
+    future::poll_fn,
+    io,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter},
+    sync::{mpsc, Semaphore},
+    task::{AbortHandle, JoinSet},
+};
+
+/// Kind of response frame.
+pub mod kind {
+    /// Result of unary call.
+    pub const UNARY: u8 = 0;
+    /// Item yielded by server stream.
+    pub const YIELD: u8 = 1;
+    /// Return value of server stream.
+    pub const RETURN: u8 = 2;
+    /// Call failed, payload is a `u16` (little-endian) http status code.
+    pub const ERROR: u8 = 3;
+}
+
+const FIN: u8 = 0b1000_0000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Server {
+    pub max_request_size: u32,
+    /// Max concurrent calls of a connection, Further calls fail with `429` status.
+    pub max_calls: usize,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            max_request_size: 128 * 1024,
+            max_calls: 128,
+        }
+    }
+}
+
+impl Server {
+    /// Serve rpc calls from `io` until the connection is closed.
+    ///
+    /// Unfinished calls are cancelled when the connection is closed,
+    /// But if the client only shuts down its write half, Every pending response is sent first.
+    ///
+    /// ```ignore
+    /// # async fn run() -> std::io::Result<()> {
+    /// let listener = tokio::net::TcpListener::bind("127.0.0.1:4000").await?;
+    /// loop {
+    ///     let (stream, _) = listener.accept().await?;
+    ///     tokio::spawn(Server::default().serve(stream, Greeter, ()));
+    /// }
+    /// # }
+    /// ```
+    pub async fn serve<E, IO>(self, io: IO, _: E, state: E::State) -> io::Result<()>
+    where
+        E: Service + 'static,
+        E::State: Clone + Send + 'static,
+        IO: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (reader, writer) = tokio::io::split(io);
+        let (tx, rx) = mpsc::channel::<Vec<u8>>(64);
+
+        let reader = async move {
+            let mut reader = BufReader::new(reader);
+            let mut tasks = JoinSet::new();
+            let mut calls = HashMap::new();
+            let permits = Arc::new(Semaphore::new(self.max_calls));
+            let max_len = self.max_request_size.saturating_add(4);
+            loop {
+                let (fin, call_id, data) = match read_frame(&mut reader, max_len).await? {
+                    Some(frame) => frame,
+                    None => break,
+                };
+                while tasks.try_join_next().is_some() {}
+                calls.retain(|_, call: &mut AbortHandle| !call.is_finished());
+
+                // Cancel, Or the id is reused by a new call.
+                if let Some(call) = calls.remove(&call_id) {
+                    call.abort();
+                }
+                if !fin {
+                    continue;
+                }
+                let mut transport = FramedResponder {
+                    call_id,
+                    tx: tx.clone(),
+                };
+                let Ok(permit) = permits.clone().try_acquire_owned() else {
+                    transport.error(429).await;
+                    continue;
+                };
+                let state = state.clone();
+                let task = tasks.spawn(async move {
+                    let _permit = permit;
+                    let data = &data[4..];
+                    if data.len() < 2 {
+                        return transport.error(400).await;
+                    }
+                    let rpc_id = u16::from_le_bytes([data[0], data[1]]);
+                    if E::requires_identity(rpc_id) {
+                        return transport.error(401).await;
+                    }
+                    let responder = transport.clone();
+                    let mut cursor = &data[2..];
+                    match E::execute(state, rpc_id, &mut cursor, &mut transport) {
+                        Some(fut) => fut.await,
+                        None => responder.error(404).await,
+                    };
+                });
+                calls.insert(call_id, task);
+            }
+            // Half-closed, Writer is finished once every call has sent its response.
+            drop(tx);
+            while tasks.join_next().await.is_some() {}
+            io::Result::Ok(())
+        };
+        let writer = write_frames(writer, rx);
+        tokio::pin!(writer);
+        tokio::select! {
+            result = &mut writer => result,
+            result = reader => {
+                result?;
+                writer.await
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct FramedResponder {
+    call_id: u32,
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl FramedResponder {
+    fn frame(&self, kind: u8) -> Vec<u8> {
+        let mut buf = new_frame(self.call_id);
+        buf.push(kind);
+        buf
+    }
+
+    fn error_frame(&self, status: u16) -> Vec<u8> {
+        let mut buf = self.frame(kind::ERROR);
+        buf.extend_from_slice(&status.to_le_bytes());
+        let _ = finish_frame(&mut buf, true);
+        buf
+    }
+
+    /// Returns `false` if the call can't continue, Either the connection is closed
+    /// Or the frame is too large, Then the call is failed with `500`.
+    async fn send(&self, mut buf: Vec<u8>, fin: bool) -> bool {
+        if finish_frame(&mut buf, fin).is_err() {
+            self.error(500).await;
+            return false;
+        }
+        self.tx.send(buf).await.is_ok()
+    }
+
+    async fn error(&self, status: u16) {
+        let _ = self.tx.send(self.error_frame(status)).await;
+    }
+}
+
+impl Transport for FramedResponder {
+    async fn unary_sync(&mut self, cb: impl FnOnce(&mut dyn io::Write) -> io::Result<()> + Send) {
+        let mut buf = self.frame(kind::UNARY);
+        match cb(&mut buf) {
+            Ok(()) => {
+                self.send(buf, true).await;
+            }
+            Err(_) => self.error(406).await,
+        }
+    }
+
+    async fn unary(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<()>> + Send,
+    ) {
+        let mut buf = self.frame(kind::UNARY);
+        match poll_fn(|cx| poll(cx, &mut buf)).await {
+            Ok(()) => {
+                self.send(buf, true).await;
+            }
+            Err(_) => self.error(406).await,
+        }
+    }
+
+    async fn server_stream(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<bool>> + Send,
+    ) {
+        loop {
+            let mut buf = self.frame(kind::YIELD);
+            match poll_fn(|cx| poll(cx, &mut buf)).await {
+                Ok(done) => {
+                    if done {
+                        buf[8] = kind::RETURN;
+                    }
+                    if !self.send(buf, done).await || done {
+                        break;
+                    }
+                }
+                Err(_) => break self.error(406).await,
+            }
+        }
+    }
+}
+
+fn new_frame(call_id: u32) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(64);
+    buf.extend_from_slice(&[0; 4]);
+    buf.extend_from_slice(&call_id.to_le_bytes());
+    buf
+}
+
+/// Write frame header, `buf` must start with 4 bytes reserved for the header.
+fn finish_frame(buf: &mut [u8], fin: bool) -> io::Result<()> {
+    let len = buf.len() - 4;
+    if len >= (1 << 31) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame too large",
+        ));
+    }
+    buf[..4].copy_from_slice(&(len as u32).to_le_bytes());
+    if fin {
+        buf[3] |= FIN;
+    }
+    Ok(())
+}
+
+/// Returns `None` when the connection is closed, Returned frame starts with call id.
+async fn read_frame(
+    reader: &mut (impl AsyncRead + Unpin),
+    max_len: u32,
+) -> io::Result<Option<(bool, u32, Vec<u8>)>> {
+    let mut header = [0; 4];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let fin = header[3] & FIN != 0;
+    header[3] &= !FIN;
+    let len = u32::from_le_bytes(header);
+    if len < 4 || len > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid frame length: {len}"),
+        ));
+    }
+    let mut data = vec![0; len as usize];
+    reader.read_exact(&mut data).await?;
+    let call_id = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    Ok(Some((fin, call_id, data)))
+}
+
+async fn write_frames(
+    writer: impl AsyncWrite + Unpin,
+    mut rx: mpsc::Receiver<Vec<u8>>,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    while let Some(frame) = rx.recv().await {
+        writer.write_all(&frame).await?;
+        while let Ok(frame) = rx.try_recv() {
+            writer.write_all(&frame).await?;
+        }
+        writer.flush().await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use frpc_transport_core::{BoxFuture, Service, Transport};
+use frpc_transport_framed::{kind, Client, Server, StreamItem, MAX_PENDING_FRAMES};
+use std::{future::Future, io, task::Poll, time::Duration};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+struct Echo;
+
+impl Service for Echo {
+    type State = ();
+    const NAME: &'static str = "Echo";
+
+    /// `1`: Echo, `2`: Yields the arguments 3 times, `3`: Echo after 50ms, `4`: Yields the arguments forever.
+    fn execute<'fut, TR>(
+        _: Self::State,
+        id: u16,
+        cursor: &'fut mut &[u8],
+        transport: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        let data = cursor.to_vec();
+        let fut: BoxFuture<'fut, ()> = match id {
+            1 => Box::pin(transport.unary_sync(move |w| w.write_all(&data))),
+            2 => {
+                let mut count = 0;
+                Box::pin(transport.server_stream(move |_, w| {
+                    count += 1;
+                    w.write_all(&data)?;
+                    Poll::Ready(Ok(count == 3))
+                }))
+            }
+            3 => Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                transport.unary_sync(|w| w.write_all(&data)).await
+            }),
+            4 => Box::pin(transport.server_stream(move |_, w| {
+                w.write_all(&data)?;
+                Poll::Ready(Ok(false))
+            })),
+            _ => return None,
+        };
+        Some(fut)
+    }
+}
+
+fn serve(server: Server) -> DuplexStream {
+    let (client, io) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(server.serve(io, Echo, ()));
+    client
+}
+
+#[tokio::test]
+async fn unary() {
+    let client = Client::new(serve(Server::default()));
+    assert_eq!(client.unary(1, b"hello").await.unwrap(), b"hello");
+    let err = client.unary(9, b"").await.unwrap_err();
+    assert_eq!(err.to_string(), "rpc failed with status: 404");
+    // Calls are multiplexed.
+    let (a, b) = tokio::join!(client.unary(3, b"slow"), client.unary(1, b"fast"));
+    assert_eq!(a.unwrap(), b"slow");
+    assert_eq!(b.unwrap(), b"fast");
+}
+
+#[tokio::test]
+async fn server_stream() {
+    let client = Client::new(serve(Server::default()));
+    let mut stream = client.server_stream(2, b"item").unwrap();
+    for _ in 0..2 {
+        let Some(Ok(StreamItem::Yield(item))) = stream.next().await else {
+            panic!("expected an item");
+        };
+        assert_eq!(item, b"item");
+    }
+    let Some(Ok(StreamItem::Return(item))) = stream.next().await else {
+        panic!("expected the return value");
+    };
+    assert_eq!(item, b"item");
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn oversized_request() {
+    let client = Client::new(serve(Server {
+        max_request_size: 16,
+        ..Default::default()
+    }));
+    assert!(client.unary(1, &[0; 14]).await.is_ok());
+    let err = client.unary(1, &[0; 15]).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+}
+
+#[tokio::test]
+async fn oversized_response() {
+    let client = Client::with_max_response_size(serve(Server::default()), 16);
+    assert_eq!(client.unary(1, &[1; 16]).await.unwrap(), [1; 16]);
+    let err = client.unary(1, &[1; 17]).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+    // Connection is closed, Every later call fails.
+    assert!(client.unary(1, b"").await.is_err());
+}
+
+#[tokio::test]
+async fn half_close_drains_pending_responses() {
+    let mut io = serve(Server::default());
+    for call_id in [1, 2] {
+        io.write_all(&request(call_id, 3, b"ok")).await.unwrap();
+    }
+    io.shutdown().await.unwrap();
+
+    let mut res = Vec::new();
+    io.read_to_end(&mut res).await.unwrap();
+    let mut frames = Vec::new();
+    let mut res = &res[..];
+    while let [a, b, c, d, rest @ ..] = res {
+        assert_eq!(d & 0b1000_0000, 0b1000_0000);
+        let len = u32::from_le_bytes([*a, *b, *c, d & !0b1000_0000]) as usize;
+        frames.push(rest[..len].to_vec());
+        res = &rest[len..];
+    }
+    frames.sort();
+    assert_eq!(
+        frames,
+        [
+            [&1u32.to_le_bytes()[..], &[kind::UNARY], b"ok"].concat(),
+            [&2u32.to_le_bytes()[..], &[kind::UNARY], b"ok"].concat(),
+        ]
+    );
+}
+
+fn request(call_id: u32, rpc_id: u16, args: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0; 4];
+    frame.extend_from_slice(&call_id.to_le_bytes());
+    frame.extend_from_slice(&rpc_id.to_le_bytes());
+    frame.extend_from_slice(args);
+    let len = frame.len() as u32 - 4;
+    frame[..4].copy_from_slice(&len.to_le_bytes());
+    frame[3] |= 0b1000_0000;
+    frame
+}
+
+/// `(call id, kind, payload)` of the next response frame.
+async fn response(io: &mut DuplexStream) -> (u32, u8, Vec<u8>) {
+    let mut header = [0; 4];
+    io.read_exact(&mut header).await.unwrap();
+    header[3] &= !0b1000_0000;
+    let mut data = vec![0; u32::from_le_bytes(header) as usize];
+    io.read_exact(&mut data).await.unwrap();
+    let call_id = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    (call_id, data[4], data[5..].to_vec())
+}
+
+#[tokio::test]
+async fn reused_id_cancels_the_previous_call() {
+    let mut io = serve(Server::default());
+    io.write_all(&request(1, 3, b"old")).await.unwrap();
+    io.write_all(&request(1, 1, b"new")).await.unwrap();
+    assert_eq!(response(&mut io).await, (1, kind::UNARY, b"new".to_vec()));
+    // Previous call never responds.
+    let next = tokio::time::timeout(Duration::from_millis(150), response(&mut io));
+    assert!(next.await.is_err());
+}
+
+#[tokio::test]
+async fn max_calls() {
+    let mut io = serve(Server {
+        max_calls: 2,
+        ..Default::default()
+    });
+    for call_id in 1..=3 {
+        io.write_all(&request(call_id, 3, b"ok")).await.unwrap();
+    }
+    let status = 429u16.to_le_bytes().to_vec();
+    assert_eq!(response(&mut io).await, (3, kind::ERROR, status));
+    let mut done = [response(&mut io).await.0, response(&mut io).await.0];
+    done.sort();
+    assert_eq!(done, [1, 2]);
+
+    // Finished calls free their permits.
+    io.write_all(&request(4, 1, b"ok")).await.unwrap();
+    assert_eq!(response(&mut io).await, (4, kind::UNARY, b"ok".to_vec()));
+}
+
+#[tokio::test]
+async fn slow_reader_is_cancelled() {
+    let client = Client::new(serve(Server::default()));
+    let mut stream = client.server_stream(4, b"item").unwrap();
+    // Other calls aren't blocked, By the unread stream.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(client.unary(1, b"ok").await.unwrap(), b"ok");
+
+    for _ in 0..MAX_PENDING_FRAMES {
+        assert!(matches!(
+            stream.next().await,
+            Some(Ok(StreamItem::Yield(_)))
+        ));
+    }
+    let err = stream.next().await.unwrap().err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+}
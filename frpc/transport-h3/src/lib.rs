@@ -0,0 +1,284 @@
+//! HTTP/3 (QUIC) transport, Avoids head-of-line blocking on lossy networks.
+//!
+//! Calls are handled by the same [`frpc_transport_http::ctx::Ctx`] as HTTP/2,
+//! So compression, limits, authentication, batches and server stream frames work exactly alike.
+use bytes::{Buf, Bytes};
+use frpc_transport_http::ctx::{self, RecvRequest, SendResponse, SendStream};
+use h3::error::Code;
+pub use h3::server::Connection;
+pub use http::{header, HeaderMap, HeaderValue, Method, StatusCode, Uri};
+pub use quinn::{self, Endpoint};
+use quinn::{crypto::rustls::QuicServerConfig, rustls};
+use std::{future::Future, io, mem, net::SocketAddr, path::Path, sync::Arc};
+use tokio::task::JoinSet;
+
+type SendHalf = h3::server::RequestStream<h3_quinn::SendStream<Bytes>, Bytes>;
+type RecvHalf = h3::server::RequestStream<h3_quinn::RecvStream, Bytes>;
+
+#[derive(Clone)]
+pub struct Server {
+    pub config: quinn::ServerConfig,
+}
+
+impl Server {
+    #[inline]
+    pub fn new(key: impl AsRef<Path>, cert: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_rustls(frpc_transport_http::Server::config(key, cert)?)
+    }
+
+    /// ALPN protocol is set to `h3`, QUIC requires TLS 1.3.
+    pub fn from_rustls(mut config: rustls::ServerConfig) -> io::Result<Self> {
+        config.alpn_protocols = vec![b"h3".to_vec()];
+        let config = QuicServerConfig::try_from(config)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        Ok(Self {
+            config: quinn::ServerConfig::with_crypto(Arc::new(config)),
+        })
+    }
+
+    pub fn endpoint(self, addr: SocketAddr) -> io::Result<Endpoint> {
+        Endpoint::server(self.config, addr)
+    }
+
+    pub async fn bind<Fut, App>(
+        self,
+        addr: SocketAddr,
+        app: impl FnMut(SocketAddr) -> Fut,
+    ) -> io::Result<()>
+    where
+        Fut: Future<Output = App> + Send + 'static,
+        App: Application,
+    {
+        serve(self.endpoint(addr)?, app).await;
+        Ok(())
+    }
+}
+
+/// Accept connections from `endpoint`, until it's closed.
+///
+/// Every connection is handshaked in its own task, So a slow client doesn't block the others.
+/// In-flight requests are completed on graceful close, And aborted if the connection is lost.
+pub async fn serve<Fut, App>(endpoint: Endpoint, mut app: impl FnMut(SocketAddr) -> Fut)
+where
+    Fut: Future<Output = App> + Send + 'static,
+    App: Application,
+{
+    while let Some(incoming) = endpoint.accept().await {
+        let addr = incoming.remote_address();
+        let app = app(addr);
+        tokio::spawn(async move {
+            let Ok(conn) = incoming.await else {
+                return;
+            };
+            let Ok(mut conn) = Connection::new(h3_quinn::Connection::new(conn)).await else {
+                return;
+            };
+            let app = app.await;
+            let mut requests = JoinSet::new();
+            loop {
+                let resolver = tokio::select! {
+                    Some(_) = requests.join_next() => continue,
+                    resolver = conn.accept() => match resolver {
+                        Ok(Some(resolver)) => resolver,
+                        Ok(None) => {
+                            while requests.join_next().await.is_some() {}
+                            break;
+                        }
+                        Err(_) => break,
+                    },
+                };
+                let app = app.clone();
+                requests.spawn(async move {
+                    if let Ok((req, stream)) = resolver.resolve_request().await {
+                        let mut ctx = from_parts(req, stream);
+                        ctx.peer_addr = Some(addr);
+                        app.stream(ctx).await
+                    }
+                });
+            }
+            requests.shutdown().await;
+            app.close().await;
+        });
+    }
+}
+
+pub trait Application: Clone + Send + 'static {
+    fn stream(self, ctx: Ctx) -> impl Future<Output = ()> + Send;
+
+    fn close(self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+pub struct Request {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: RecvHalf,
+}
+
+impl std::fmt::Debug for Request {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Request")
+            .field("method", &self.method)
+            .field("uri", &self.uri)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Response is sent on drop, if it wasn't sent already.
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    stream: Option<SendHalf>,
+}
+
+impl Response {
+    /// Headers are moved into the response.
+    fn head(&mut self) -> http::Response<()> {
+        let mut res = http::Response::new(());
+        *res.status_mut() = self.status;
+        *res.headers_mut() = mem::take(&mut self.headers);
+        res
+    }
+}
+
+impl Drop for Response {
+    fn drop(&mut self) {
+        let Some(mut stream) = self.stream.take() else {
+            return;
+        };
+        let res = self.head();
+        tokio::spawn(async move {
+            if stream.send_response(res).await.is_ok() {
+                let _ = stream.finish().await;
+            }
+        });
+    }
+}
+
+/// HTTP/3 request context.
+pub type Ctx = ctx::Ctx<Request, Response>;
+
+/// Response to a HTTP/3 request.
+pub type RpcResponder<'a> = ctx::RpcResponder<'a, Response>;
+
+/// Context of a resolved request, See [`Connection::accept`].
+pub fn from_parts(
+    req: http::Request<()>,
+    stream: h3::server::RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+) -> Ctx {
+    let (send, recv) = stream.split();
+    let (head, ()) = req.into_parts();
+    Ctx::new(
+        Request {
+            method: head.method,
+            uri: head.uri,
+            headers: head.headers,
+            body: recv,
+        },
+        Response {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            stream: Some(send),
+        },
+    )
+}
+
+impl RecvRequest for Request {
+    fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    async fn data(&mut self) -> Option<io::Result<Bytes>> {
+        match self.body.recv_data().await {
+            Ok(Some(mut buf)) => Some(Ok(buf.copy_to_bytes(buf.remaining()))),
+            Ok(None) => None,
+            Err(err) => Some(Err(io::Error::other(err))),
+        }
+    }
+}
+
+impl SendResponse for Response {
+    type Stream = ResponseStream;
+
+    fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Cancellation of a single call isn't detected while the output is produced,
+    /// h3 reports `STOP_SENDING` of the client only on the next write.
+    /// So the first write fails and the output is dropped.
+    ///
+    /// A lost connection aborts the call, See [`serve`].
+    async fn cancelled(&mut self) {
+        std::future::pending().await
+    }
+
+    async fn send(&mut self, status: StatusCode, chunks: Vec<Bytes>) {
+        let len: usize = chunks.iter().map(Bytes::len).sum();
+        self.status = status;
+        self.headers.insert(header::CONTENT_LENGTH, len.into());
+        let res = self.head();
+        let Some(mut stream) = self.stream.take() else {
+            return;
+        };
+        if stream.send_response(res).await.is_err() {
+            return;
+        }
+        for chunk in chunks {
+            if stream.send_data(chunk).await.is_err() {
+                return;
+            }
+        }
+        let _ = stream.finish().await;
+    }
+
+    fn send_stream(&mut self) -> Option<Self::Stream> {
+        let head = self.head();
+        Some(ResponseStream {
+            stream: self.stream.take()?,
+            head: Some(head),
+            ended: false,
+        })
+    }
+}
+
+/// Headers are sent along with the first frame, Stream is reset if it's dropped before the last one.
+pub struct ResponseStream {
+    stream: SendHalf,
+    head: Option<http::Response<()>>,
+    ended: bool,
+}
+
+impl SendStream for ResponseStream {
+    async fn write(&mut self, bytes: Bytes, end: bool) -> bool {
+        if let Some(head) = self.head.take() {
+            if self.stream.send_response(head).await.is_err() {
+                return false;
+            }
+        }
+        if self.stream.send_data(bytes).await.is_err() {
+            return false;
+        }
+        if end {
+            self.ended = true;
+            return self.stream.finish().await.is_ok();
+        }
+        true
+    }
+
+    /// See [`Response::cancelled`](SendResponse::cancelled).
+    async fn cancelled(&mut self) {
+        std::future::pending().await
+    }
+}
+
+impl Drop for ResponseStream {
+    fn drop(&mut self) {
+        if !self.ended {
+            self.stream.stop_stream(Code::H3_INTERNAL_ERROR);
+        }
+    }
+}
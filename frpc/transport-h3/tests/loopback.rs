@@ -0,0 +1,222 @@
+use bytes::{Buf, Bytes};
+use frpc_transport_core::*;
+use frpc_transport_h3::{quinn::rustls, *};
+use std::{
+    future::{poll_fn, Future},
+    net::SocketAddr,
+    sync::Arc,
+    task::Poll,
+};
+
+struct Echo;
+
+impl Service for Echo {
+    type State = ();
+    fn execute<'fut, TR>(
+        _: (),
+        id: u16,
+        cursor: &'fut mut &[u8],
+        transport: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        let data = cursor.to_vec();
+        if !matches!(id, 1 | 2) {
+            return None;
+        }
+        Some(async move {
+            match id {
+                1 => transport.unary_sync(|buf| buf.write_all(&data)).await,
+                _ => {
+                    let mut count = 0;
+                    transport
+                        .server_stream(move |_, buf| {
+                            count += 1;
+                            buf.write_all(&data)?;
+                            Poll::Ready(Ok(count == 3))
+                        })
+                        .await
+                }
+            }
+        })
+    }
+}
+
+#[derive(Clone)]
+struct App;
+
+impl Application for App {
+    async fn stream(self, mut ctx: Ctx) {
+        ctx.res.status = match ctx.req.uri.path() {
+            "/echo" => ctx.serve(Echo, ()).await,
+            "/chunked" => {
+                ctx.chunk_size = 2;
+                ctx.serve(Echo, ()).await
+            }
+            "/compressed" => {
+                ctx.compression_threshold = 0;
+                ctx.serve(Echo, ()).await
+            }
+            _ => StatusCode::NOT_FOUND,
+        };
+    }
+}
+
+async fn server() -> SocketAddr {
+    let dir = env!("CARGO_MANIFEST_DIR");
+    let endpoint = Server::new(
+        format!("{dir}/../../examples/key.pem"),
+        format!("{dir}/../../examples/cert.pem"),
+    )
+    .unwrap()
+    .endpoint("127.0.0.1:0".parse().unwrap())
+    .unwrap();
+
+    let addr = endpoint.local_addr().unwrap();
+    tokio::spawn(serve(endpoint, |_| async { App }));
+    addr
+}
+
+async fn call(addr: SocketAddr, path: &str, body: Vec<u8>) -> (StatusCode, Vec<u8>) {
+    let req = http::Request::post(format!("https://localhost{path}"))
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(())
+        .unwrap();
+    let (res, data) = send(addr, req, body).await;
+    (res.status(), data)
+}
+
+async fn send(
+    addr: SocketAddr,
+    req: http::Request<()>,
+    body: Vec<u8>,
+) -> (http::Response<()>, Vec<u8>) {
+    let mut crypto = rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(SkipVerify))
+        .with_no_client_auth();
+    crypto.alpn_protocols = vec![b"h3".to_vec()];
+
+    let mut client = quinn::Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+    client.set_default_client_config(quinn::ClientConfig::new(Arc::new(
+        quinn::crypto::rustls::QuicClientConfig::try_from(crypto).unwrap(),
+    )));
+    let conn = client.connect(addr, "localhost").unwrap().await.unwrap();
+    let (mut driver, mut sender) = h3::client::new(h3_quinn::Connection::new(conn))
+        .await
+        .unwrap();
+    tokio::spawn(async move { poll_fn(|cx| driver.poll_close(cx)).await });
+
+    let mut stream = sender.send_request(req).await.unwrap();
+    stream.send_data(Bytes::from(body)).await.unwrap();
+    stream.finish().await.unwrap();
+
+    let res = stream.recv_response().await.unwrap();
+    let mut data = vec![];
+    while let Some(mut chunk) = stream.recv_data().await.unwrap() {
+        while chunk.has_remaining() {
+            let len = chunk.chunk().len();
+            data.extend_from_slice(chunk.chunk());
+            chunk.advance(len);
+        }
+    }
+    (res, data)
+}
+
+#[tokio::test]
+async fn unary() {
+    let addr = server().await;
+    let (status, data) = call(addr, "/echo", vec![1, 0, 42, 43]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(data, [42, 43]);
+
+    let (status, _) = call(addr, "/echo", vec![9, 0]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn server_stream() {
+    let addr = server().await;
+    let (status, data) = call(addr, "/echo", vec![2, 0, 7]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        data,
+        [1, 0, 0, 0, 7, 1, 0, 0, 0, 7, 1, 0, 0, 0b1000_0000, 7]
+    );
+}
+
+#[tokio::test]
+async fn chunked() {
+    let addr = server().await;
+    let (status, data) = call(addr, "/chunked", vec![1, 0, 7, 8, 9]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(data, [7, 8, 9]);
+
+    let (status, data) = call(addr, "/chunked", vec![2, 0, 7, 8, 9]).await;
+    assert_eq!(status, StatusCode::OK);
+    // Every item is split into 2 frames, First one is marked as `CONTINUED`.
+    let mut frames = [2, 0, 0, 0b0010_0000, 7, 8, 1, 0, 0, 0, 9].repeat(3);
+    let fin = frames.len() - 2;
+    frames[fin] |= 0b1000_0000;
+    assert_eq!(data, frames);
+}
+
+#[tokio::test]
+async fn compressed() {
+    let addr = server().await;
+    let body = [&[1, 0][..], &[7; 256]].concat();
+    let req = http::Request::post("https://localhost/compressed")
+        .header(header::CONTENT_LENGTH, body.len())
+        .header(header::ACCEPT_ENCODING, "gzip")
+        .body(())
+        .unwrap();
+    let (res, data) = send(addr, req, body).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(
+        res.headers()[header::CONTENT_LENGTH],
+        data.len().to_string()
+    );
+    assert!(data.len() < 256);
+}
+
+#[derive(Debug)]
+struct SkipVerify;
+
+impl rustls::client::danger::ServerCertVerifier for SkipVerify {
+    fn verify_server_cert(
+        &self,
+        _: &rustls::pki_types::CertificateDer,
+        _: &[rustls::pki_types::CertificateDer],
+        _: &rustls::pki_types::ServerName,
+        _: &[u8],
+        _: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _: &[u8],
+        _: &rustls::pki_types::CertificateDer,
+        _: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _: &[u8],
+        _: &rustls::pki_types::CertificateDer,
+        _: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        rustls::crypto::ring::default_provider()
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
@@ -0,0 +1,174 @@
+//! Bearer token authentication.
+//!
+//! Token of `authorization: Bearer <token>` header is validated before the call is executed,
+//! And the caller is available to rpc via `Principal` extractor.
+//! Requests without a token are anonymous, Invalid tokens are rejected with `401 Unauthorized`.
+//!
+//! [`Jwt`] validator is enabled by `jwt` feature.
+use frpc_transport_core::Identity;
+
+/// Value of [`STATUS_HEADER`](crate::limit::STATUS_HEADER), When the token is invalid.
+pub const UNAUTHENTICATED: &str = "unauthenticated";
+
+pub trait TokenValidator: Send + Sync + 'static {
+    /// Returns `None` if the token isn't valid.
+    fn validate(&self, token: &str) -> Option<Identity>;
+}
+
+impl<F> TokenValidator for F
+where
+    F: Fn(&str) -> Option<Identity> + Send + Sync + 'static,
+{
+    fn validate(&self, token: &str) -> Option<Identity> {
+        self(token)
+    }
+}
+
+/// Token of `authorization` header value, Scheme is case-insensitive.
+pub fn bearer(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+#[cfg(feature = "jwt")]
+pub use jwt::Jwt;
+
+#[cfg(feature = "jwt")]
+mod jwt {
+    use super::*;
+    use ring::{hmac, signature};
+    use serde_json::Value;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    enum Key {
+        Hs256(hmac::Key),
+        Rs256(signature::UnparsedPublicKey<Vec<u8>>),
+    }
+
+    /// Validates JSON Web Token, Signed with `HS256` or `RS256`.
+    ///
+    /// `exp` and `nbf` claims are checked if present, `iss` and `aud` only if they're configured.
+    pub struct Jwt {
+        key: Key,
+        pub issuer: Option<String>,
+        pub audience: Option<String>,
+        /// Allowed clock skew, In seconds.
+        pub leeway: u64,
+    }
+
+    impl Jwt {
+        fn new(key: Key) -> Self {
+            Self {
+                key,
+                issuer: None,
+                audience: None,
+                leeway: 60,
+            }
+        }
+
+        pub fn hs256(secret: &[u8]) -> Self {
+            Self::new(Key::Hs256(hmac::Key::new(hmac::HMAC_SHA256, secret)))
+        }
+
+        /// `der` is the public key, Encoded as PKCS#1 `RSAPublicKey`.
+        pub fn rs256(der: impl Into<Vec<u8>>) -> Self {
+            Self::new(Key::Rs256(signature::UnparsedPublicKey::new(
+                &signature::RSA_PKCS1_2048_8192_SHA256,
+                der.into(),
+            )))
+        }
+
+        pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+            self.issuer = Some(issuer.into());
+            self
+        }
+
+        pub fn audience(mut self, audience: impl Into<String>) -> Self {
+            self.audience = Some(audience.into());
+            self
+        }
+
+        fn verify(&self, alg: &str, message: &[u8], signature: &[u8]) -> Option<()> {
+            match (&self.key, alg) {
+                (Key::Hs256(key), "HS256") => hmac::verify(key, message, signature).ok(),
+                (Key::Rs256(key), "RS256") => key.verify(message, signature).ok(),
+                _ => None,
+            }
+        }
+
+        fn check_claims(&self, claims: &Value) -> Option<()> {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+            if let Some(exp) = claims.get("exp") {
+                (now <= exp.as_u64()?.saturating_add(self.leeway)).then_some(())?;
+            }
+            if let Some(nbf) = claims.get("nbf") {
+                (now.saturating_add(self.leeway) >= nbf.as_u64()?).then_some(())?;
+            }
+            if let Some(issuer) = &self.issuer {
+                (claims.get("iss")?.as_str()? == issuer).then_some(())?;
+            }
+            if let Some(audience) = &self.audience {
+                let matched = match claims.get("aud")? {
+                    Value::String(aud) => aud == audience,
+                    Value::Array(aud) => aud.iter().any(|aud| aud.as_str() == Some(audience)),
+                    _ => false,
+                };
+                matched.then_some(())?;
+            }
+            Some(())
+        }
+    }
+
+    impl TokenValidator for Jwt {
+        fn validate(&self, token: &str) -> Option<Identity> {
+            let (message, signature) = token.rsplit_once('.')?;
+            let (header, payload) = message.split_once('.')?;
+            let header: Value = serde_json::from_slice(&base64url(header)?).ok()?;
+            self.verify(
+                header.get("alg")?.as_str()?,
+                message.as_bytes(),
+                &base64url(signature)?,
+            )?;
+            let claims: Value = serde_json::from_slice(&base64url(payload)?).ok()?;
+            self.check_claims(&claims)?;
+            Some(Identity {
+                subject: claims.get("sub").and_then(Value::as_str).map(Into::into),
+                scopes: claims
+                    .get("scope")
+                    .and_then(Value::as_str)
+                    .map(|scope| scope.split_whitespace().map(Into::into).collect())
+                    .unwrap_or_default(),
+                certificate: None,
+            })
+        }
+    }
+
+    /// Unpadded base64url, As used by JWT.
+    ///
+    /// Padding and non-canonical trailing bits are rejected.
+    fn base64url(input: &str) -> Option<Vec<u8>> {
+        if input.len() % 4 == 1 {
+            return None;
+        }
+        let mut out = Vec::with_capacity(input.len() * 3 / 4);
+        let mut acc = 0u32;
+        let mut bits = 0;
+        for byte in input.bytes() {
+            let value = match byte {
+                b'A'..=b'Z' => byte - b'A',
+                b'a'..=b'z' => byte - b'a' + 26,
+                b'0'..=b'9' => byte - b'0' + 52,
+                b'-' => 62,
+                b'_' => 63,
+                _ => return None,
+            };
+            acc = (acc << 6) | value as u32;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                out.push((acc >> bits) as u8);
+            }
+        }
+        (acc & ((1 << bits) - 1) == 0).then_some(out)
+    }
+}
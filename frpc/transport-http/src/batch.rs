@@ -0,0 +1,278 @@
+//! Many unary calls in a single request.
+//!
+//! Request body starts with [`BATCH_ID`] instead of an rpc id, Followed by the calls:
+//!
+//! ```text
+//! [rpc id: u16] [args length: u32] [args] ...
+//! ```
+//!
+//! Calls are executed concurrently, And every result is sent as soon as it's completed,
+//! Framed exactly like a server stream item:
+//!
+//! ```text
+//! [call index: u16] [status: u8] [output]
+//! ```
+//!
+//! Every call is checked against the rate limit and call limits on its own.
+//! Calls that take the authenticated caller are rejected, If the request is anonymous.
+//!
+//! Response ends with an empty frame, That has [`FIN`](crate::chunked::FIN) bit set.
+use crate::{
+    auth,
+    chunked::Chunked,
+    compression::Negotiated,
+    limit::{self, Limits},
+    metrics,
+    rate_limit::RateLimiter,
+    trace,
+};
+use bytes::Bytes;
+use frpc_transport_core::*;
+use std::{
+    collections::VecDeque,
+    future::{poll_fn, Future},
+    io::{self, Write},
+    mem,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc;
+
+pub use frpc_transport_core::BATCH_ID;
+
+/// Status of a call in the batch.
+pub mod status {
+    pub const OK: u8 = 0;
+    /// Unknown rpc id.
+    pub const NOT_FOUND: u8 = 1;
+    /// Invalid arguments, Or the output couldn't be encoded.
+    pub const ERROR: u8 = 2;
+    /// Server stream can't be batched.
+    pub const UNSUPPORTED: u8 = 3;
+    /// Rpc limit is reached, See [`limit`](crate::limit).
+    pub const RESOURCE_EXHAUSTED: u8 = 4;
+    /// Rpc takes the authenticated caller, But the request is anonymous.
+    pub const UNAUTHENTICATED: u8 = 5;
+}
+
+pub struct Call<'a> {
+    pub id: u16,
+    pub args: &'a [u8],
+}
+
+/// Returns `None` if the envelope is malformed.
+pub fn parse(mut data: &[u8]) -> Option<Vec<Call<'_>>> {
+    let mut calls = Vec::new();
+    while !data.is_empty() {
+        let (head, rest) = data.split_first_chunk::<6>()?;
+        let id = u16::from_le_bytes([head[0], head[1]]);
+        let len = u32::from_le_bytes([head[2], head[3], head[4], head[5]]) as usize;
+        if rest.len() < len {
+            return None;
+        }
+        let (args, rest) = rest.split_at(len);
+        calls.push(Call { id, args });
+        data = rest;
+    }
+    (calls.len() <= u16::MAX as usize).then_some(calls)
+}
+
+/// Shared by every call in the batch.
+pub struct Config<'a> {
+    /// Max number of calls, That are executed concurrently.
+    pub concurrency: usize,
+    pub limits: &'a Limits,
+    pub compression: Negotiated,
+    pub chunk_size: usize,
+    pub identity: Option<Arc<Identity>>,
+    /// Every call takes its own token, Keyed by its rpc id.
+    pub rate_limit: Option<&'a RateLimiter>,
+    pub peer_addr: Option<SocketAddr>,
+}
+
+/// Execute `calls` at most `concurrency` at a time, Result frames are sent to `tx`.
+///
+/// Calls that exceed a call limit or their rate limit are rejected, Instead of waiting.
+pub async fn execute<E>(
+    state: E::State,
+    calls: Vec<Call<'_>>,
+    config: Config<'_>,
+    tx: mpsc::Sender<Bytes>,
+) where
+    E: Service,
+    E::State: Clone,
+{
+    let Config {
+        concurrency,
+        limits,
+        compression,
+        chunk_size,
+        identity,
+        rate_limit,
+        peer_addr,
+    } = config;
+    let done = Mutex::new(VecDeque::new());
+    let mut cursors: Vec<&[u8]> = calls.iter().map(|call| call.args).collect();
+    let records: Vec<_> = calls.iter().map(|_| metrics::Call::new::<E>()).collect();
+    let mut responders: Vec<_> = records
+        .iter()
+        .enumerate()
+        .map(|(index, call)| BatchResponder {
+            index: index as u16,
+            done: &done,
+            compression,
+            chunk_size,
+            call,
+            identity: identity.clone(),
+        })
+        .collect();
+
+    let mut pending = calls
+        .iter()
+        .zip(cursors.iter_mut().zip(responders.iter_mut()))
+        .enumerate();
+    let mut running = Vec::with_capacity(concurrency);
+    loop {
+        while running.len() < concurrency.max(1) {
+            let Some((index, (call, (cursor, transport)))) = pending.next() else {
+                break;
+            };
+            let record = transport.call;
+            record.rpc::<E>(call.id);
+            let reject = |status| {
+                finish(
+                    &done,
+                    output(index as u16, status, chunk_size),
+                    &compression,
+                );
+                record.error();
+                record.finish();
+            };
+            if E::requires_identity(call.id) && identity.is_none() {
+                trace::record_error(auth::UNAUTHENTICATED);
+                reject(status::UNAUTHENTICATED);
+                continue;
+            }
+            let rate = rate_limit.map_or(Ok(()), |limiter| {
+                limiter.check(E::NAME, call.id, peer_addr, identity.as_deref())
+            });
+            let permit = rate.ok().and_then(|_| limits.try_call(E::NAME, call.id));
+            let Some(permit) = permit else {
+                trace::record_error(limit::error());
+                reject(status::RESOURCE_EXHAUSTED);
+                continue;
+            };
+            match E::execute(state.clone(), call.id, cursor, transport) {
+                Some(fut) => {
+                    let fut = trace::batch_call::<E, _>(call.id, call.args.len(), async move {
+                        fut.await;
+                        drop(permit)
+                    });
+                    running.push(Box::pin(fut))
+                }
+                None => reject(status::NOT_FOUND),
+            }
+        }
+        // Every call has been completed, If nothing is running after refilling.
+        let idle = running.is_empty();
+        if !idle {
+            poll_fn(|cx| {
+                running.retain_mut(|fut| fut.as_mut().poll(cx).is_pending());
+                if running.is_empty() || !done.lock().unwrap().is_empty() {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await;
+        }
+        let frames = mem::take(&mut *done.lock().unwrap());
+        for frame in frames {
+            if tx.send(frame).await.is_err() {
+                return;
+            }
+        }
+        if idle {
+            break;
+        }
+    }
+    let _ = tx.send(Bytes::from_static(&[0, 0, 0, 0b1000_0000])).await;
+}
+
+struct BatchResponder<'a> {
+    index: u16,
+    done: &'a Mutex<VecDeque<Bytes>>,
+    compression: Negotiated,
+    chunk_size: usize,
+    call: &'a metrics::Call,
+    identity: Option<Arc<Identity>>,
+}
+
+impl BatchResponder<'_> {
+    fn output(&self, status: u8) -> Chunked {
+        output(self.index, status, self.chunk_size)
+    }
+
+    fn finish(&self, buf: Chunked) {
+        trace::record_response(buf.len());
+        finish(self.done, buf, &self.compression);
+        self.call.finish();
+    }
+
+    fn fail(&self, status: u8) {
+        self.call.error();
+        self.finish(self.output(status))
+    }
+}
+
+impl Transport for BatchResponder<'_> {
+    async fn unary_sync(&mut self, cb: impl FnOnce(&mut dyn io::Write) -> io::Result<()> + Send) {
+        let mut buf = self.output(status::OK);
+        match cb(&mut buf) {
+            Ok(()) => self.finish(buf),
+            Err(err) => {
+                trace::record_error(err);
+                self.fail(status::ERROR)
+            }
+        }
+    }
+
+    async fn unary(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<()>> + Send,
+    ) {
+        let mut buf = self.output(status::OK);
+        match poll_fn(|cx| poll(cx, &mut buf)).await {
+            Ok(()) => self.finish(buf),
+            Err(err) => {
+                trace::record_error(err);
+                self.fail(status::ERROR)
+            }
+        }
+    }
+
+    async fn server_stream(
+        &mut self,
+        _: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<bool>> + Send,
+    ) {
+        trace::record_error("server stream can't be batched");
+        self.fail(status::UNSUPPORTED)
+    }
+
+    fn identity(&self) -> Option<Arc<Identity>> {
+        self.identity.clone()
+    }
+}
+
+fn output(index: u16, status: u8, chunk_size: usize) -> Chunked {
+    let mut buf = Chunked::new(chunk_size, 4);
+    let _ = buf.write_all(&index.to_le_bytes());
+    let _ = buf.write_all(&[status]);
+    buf
+}
+
+fn finish(done: &Mutex<VecDeque<Bytes>>, mut buf: Chunked, compression: &Negotiated) {
+    let frames = buf.take_frames(compression, false);
+    done.lock().unwrap().extend(frames);
+}
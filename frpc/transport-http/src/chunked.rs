@@ -0,0 +1,177 @@
+//! Output is encoded into bounded chunks, So that large results don't need a contiguous allocation.
+//!
+//! - Unary output is sent as a sequence of data frames.
+//! - Server stream item, that is larger than a chunk, is split into multiple frames.
+//!   Every frame except the last one is marked with [`CONTINUED`] bit, Client concatenates them before decoding.
+//!
+//! Buffers are pooled per thread, And the memory of sent chunks is reclaimed by the next write.
+use crate::compression::{Negotiated, COMPRESSED};
+use bytes::{Bytes, BytesMut};
+use std::{cell::RefCell, io, iter, mem};
+
+/// Bit of the last byte of frame header, Set on the last frame of server stream.
+pub const FIN: u8 = 0b1000_0000;
+
+/// Bit of the last byte of frame header, Set when the item continues in the next frame.
+pub const CONTINUED: u8 = 0b0010_0000;
+
+/// Frame length must be smaller than this, Upper 3 bits of the header are flags.
+pub const MAX_FRAME_SIZE: usize = 1 << 29;
+
+/// Max number of pooled buffers per thread.
+const POOL_SIZE: usize = 64;
+
+/// Larger buffers are freed, instead of being pooled.
+const MAX_POOLED_CAPACITY: usize = 64 * 1024;
+
+/// List of chunks is shrunk to this capacity, before being pooled.
+const MAX_POOLED_CHUNKS: usize = 16;
+
+/// Frame header of `len` bytes, `flags` are bits of the last byte.
+///
+/// # Panics
+///
+/// If `len` isn't smaller than [`MAX_FRAME_SIZE`].
+pub fn header(len: usize, flags: u8) -> [u8; 4] {
+    assert!(len < MAX_FRAME_SIZE, "frame is too large: {len}");
+    let mut header = (len as u32).to_le_bytes();
+    header[3] |= flags;
+    header
+}
+
+thread_local! {
+    static POOL: RefCell<Vec<(BytesMut, Vec<BytesMut>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// [`io::Write`] that splits the output into chunks of at most `chunk_size` bytes,
+/// Each chunk starts with `header` reserved bytes.
+#[derive(Debug)]
+pub struct Chunked {
+    /// Chunk that is being written.
+    buf: BytesMut,
+    /// Full chunks.
+    chunks: Vec<BytesMut>,
+    chunk_size: usize,
+    header: usize,
+}
+
+impl Chunked {
+    pub fn new(chunk_size: usize, header: usize) -> Self {
+        let (buf, chunks) = POOL
+            .with(|pool| pool.borrow_mut().pop())
+            .unwrap_or_default();
+        Self {
+            buf,
+            chunks,
+            chunk_size: chunk_size.clamp(1, MAX_FRAME_SIZE - 1),
+            header,
+        }
+    }
+
+    /// New writer with the same configuration.
+    pub fn empty(&self) -> Self {
+        Self::new(self.chunk_size, self.header)
+    }
+
+    /// Number of bytes written, Excluding reserved headers.
+    pub fn len(&self) -> usize {
+        self.chunks().map(<[u8]>::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty() && self.chunks.is_empty()
+    }
+
+    /// Written bytes, Excluding reserved headers.
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        self.chunks
+            .iter()
+            .chain(iter::once(&self.buf))
+            .filter(|chunk| !chunk.is_empty())
+            .map(|chunk| &chunk[self.header..])
+    }
+
+    /// Takes the written chunks, Including reserved headers.
+    pub fn take(&mut self) -> impl ExactSizeIterator<Item = Bytes> + '_ {
+        if !self.buf.is_empty() {
+            self.chunks.push(self.buf.split());
+        }
+        self.chunks.drain(..).map(BytesMut::freeze)
+    }
+
+    /// Takes the written item as server stream frames, `header` must be 4 bytes.
+    ///
+    /// Returns nothing, If the item is empty and it's not the last one.
+    pub fn take_frames(
+        &mut self,
+        compression: &Negotiated,
+        fin: bool,
+    ) -> impl ExactSizeIterator<Item = Bytes> + '_ {
+        debug_assert_eq!(self.header, 4);
+        if self.is_empty() && fin {
+            self.buf.resize(4, 0);
+        }
+        if !self.buf.is_empty() {
+            self.chunks.push(self.buf.split());
+        }
+        let last = self.chunks.len().saturating_sub(1);
+        for (i, frame) in self.chunks.iter_mut().enumerate() {
+            let mut flags = 0;
+            if compression.encode_frame(frame) {
+                flags |= COMPRESSED;
+            }
+            if i != last {
+                flags |= CONTINUED;
+            } else if fin {
+                flags |= FIN;
+            }
+            let len = frame.len() - 4;
+            frame[..4].copy_from_slice(&header(len, flags));
+        }
+        self.chunks.drain(..).map(BytesMut::freeze)
+    }
+}
+
+impl io::Write for Chunked {
+    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+        let amt = buf.len();
+        let limit = self.header + self.chunk_size;
+        while !buf.is_empty() {
+            if self.buf.len() == limit {
+                self.chunks.push(self.buf.split());
+            }
+            if self.buf.is_empty() {
+                self.buf
+                    .reserve(self.header + buf.len().min(self.chunk_size));
+                self.buf.resize(self.header, 0);
+            }
+            let len = buf.len().min(limit - self.buf.len());
+            self.buf.extend_from_slice(&buf[..len]);
+            buf = &buf[len..];
+        }
+        Ok(amt)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Chunked {
+    fn drop(&mut self) {
+        if self.buf.capacity() > MAX_POOLED_CAPACITY {
+            return;
+        }
+        let mut buf = mem::take(&mut self.buf);
+        let mut chunks = mem::take(&mut self.chunks);
+        buf.clear();
+        chunks.clear();
+        chunks.shrink_to(MAX_POOLED_CHUNKS);
+        let _ = POOL.try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < POOL_SIZE {
+                pool.push((buf, chunks));
+            }
+        });
+    }
+}
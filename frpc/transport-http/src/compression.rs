@@ -0,0 +1,214 @@
+//! Compression of request and response bodies, Each codec is enabled by a cargo feature of the same name.
+//!
+//! - Request body is decoded according to `content-encoding`.
+//! - Unary response is encoded with the preferred encoding of `accept-encoding`, if it's larger than the threshold.
+//! - Server stream frames are encoded individually with the preferred encoding of [`FRAME_ACCEPT_ENCODING`],
+//!   Chosen encoding is sent in [`FRAME_ENCODING`] response header,
+//!   And every encoded frame is marked with [`COMPRESSED`] bit.
+use crate::chunked::Chunked;
+use bytes::BytesMut;
+use std::io::{self, Write};
+
+/// Request header, Same syntax as `accept-encoding`.
+///
+/// Browsers don't allow to set `accept-encoding`, And would decode the whole response stream.
+pub const FRAME_ACCEPT_ENCODING: &str = "frpc-accept-encoding";
+
+/// Response header, Encoding of server stream frames.
+pub const FRAME_ENCODING: &str = "frpc-frame-encoding";
+
+/// Bit of the last byte of frame header, Set when the frame is encoded.
+pub const COMPRESSED: u8 = 0b0100_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    #[cfg(feature = "zstd")]
+    Zstd,
+    #[cfg(feature = "brotli")]
+    Brotli,
+    #[cfg(feature = "gzip")]
+    Gzip,
+}
+
+impl Encoding {
+    /// Supported encodings, In order of preference.
+    pub const ALL: &'static [Encoding] = &[
+        #[cfg(feature = "zstd")]
+        Encoding::Zstd,
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli,
+        #[cfg(feature = "gzip")]
+        Encoding::Gzip,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            #[cfg(feature = "zstd")]
+            Encoding::Zstd => "zstd",
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => "br",
+            #[cfg(feature = "gzip")]
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|encoding| encoding.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Pick the most preferred encoding, that is accepted by the client.
+    ///
+    /// `accept` is the value of `accept-encoding` header,
+    /// Encodings with `q=0` are rejected, Even if `*` is accepted.
+    pub fn negotiate(accept: &str) -> Option<Self> {
+        let mut accepted = Vec::new();
+        let mut rejected = Vec::new();
+        for item in accept.split(',') {
+            let mut params = item.split(';');
+            let name = params.next().unwrap_or_default().trim();
+            if name.is_empty() {
+                continue;
+            }
+            let q = params.find_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                key.trim().eq_ignore_ascii_case("q").then(|| value.trim())
+            });
+            match q.map(str::parse::<f32>) {
+                Some(Ok(0.0)) => rejected.push(name),
+                _ => accepted.push(name),
+            }
+        }
+        Self::ALL.iter().copied().find(|encoding| {
+            let named = |name: &&str| encoding.as_str().eq_ignore_ascii_case(name);
+            !rejected.iter().any(named) && (accepted.iter().any(named) || accepted.contains(&"*"))
+        })
+    }
+
+    pub fn encode(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        self.encode_into([data], Vec::new())
+    }
+
+    /// Encode `data` piece by piece into `writer`, Returns the `writer`.
+    #[cfg_attr(
+        not(any(feature = "zstd", feature = "brotli", feature = "gzip")),
+        allow(unused_variables)
+    )]
+    pub fn encode_into<'a, W: Write>(
+        self,
+        data: impl IntoIterator<Item = &'a [u8]>,
+        writer: W,
+    ) -> io::Result<W> {
+        match self {
+            #[cfg(feature = "zstd")]
+            Encoding::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(writer, 3)?;
+                for bytes in data {
+                    encoder.write_all(bytes)?;
+                }
+                encoder.finish()
+            }
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(writer, 4096, 5, 22);
+                for bytes in data {
+                    encoder.write_all(bytes)?;
+                }
+                Ok(encoder.into_inner())
+            }
+            #[cfg(feature = "gzip")]
+            Encoding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(writer, flate2::Compression::fast());
+                for bytes in data {
+                    encoder.write_all(bytes)?;
+                }
+                encoder.finish()
+            }
+        }
+    }
+
+    /// At most `limit + 1` bytes are decoded, So that caller can detect oversized payload.
+    #[cfg_attr(
+        not(any(feature = "zstd", feature = "brotli", feature = "gzip")),
+        allow(unused_variables)
+    )]
+    pub fn decode(self, data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "zstd")]
+            Encoding::Zstd => read_limited(zstd::Decoder::with_buffer(data)?, limit),
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => read_limited(brotli::Decompressor::new(data, 4096), limit),
+            #[cfg(feature = "gzip")]
+            Encoding::Gzip => read_limited(flate2::read::GzDecoder::new(data), limit),
+        }
+    }
+}
+
+#[cfg(any(feature = "zstd", feature = "brotli", feature = "gzip"))]
+fn read_limited(reader: impl io::Read, limit: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    io::Read::read_to_end(&mut reader.take(limit as u64 + 1), &mut buf)?;
+    Ok(buf)
+}
+
+/// Encodings chosen for a response.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Negotiated {
+    pub unary: Option<Encoding>,
+    pub frame: Option<Encoding>,
+    /// Smaller payloads are sent as it is.
+    pub threshold: usize,
+}
+
+impl Negotiated {
+    pub fn new(accept: Option<&str>, frame_accept: Option<&str>, threshold: usize) -> Self {
+        Self {
+            unary: accept.and_then(Encoding::negotiate),
+            frame: frame_accept.and_then(Encoding::negotiate),
+            threshold,
+        }
+    }
+
+    /// Returns the encoding, If `buf` was encoded.
+    pub fn encode_unary(&self, buf: &mut Chunked) -> Option<Encoding> {
+        let encoding = self.unary?;
+        let len = buf.len();
+        if len < self.threshold {
+            return None;
+        }
+        let encoded = encoding.encode_into(buf.chunks(), buf.empty()).ok()?;
+        if encoded.len() >= len {
+            return None;
+        }
+        *buf = encoded;
+        Some(encoding)
+    }
+
+    /// `buf` starts with 4 bytes reserved for the frame header, Returns `true` if the frame was encoded.
+    pub fn encode_frame(&self, buf: &mut BytesMut) -> bool {
+        match self.frame {
+            Some(encoding) => encode(encoding, self.threshold, buf, 4),
+            None => false,
+        }
+    }
+}
+
+fn encode(encoding: Encoding, threshold: usize, buf: &mut BytesMut, offset: usize) -> bool {
+    let data = &buf[offset..];
+    if data.len() < threshold {
+        return false;
+    }
+    let Ok(encoded) = encoding.encode(data) else {
+        return false;
+    };
+    if encoded.len() >= data.len() {
+        return false;
+    }
+    buf.truncate(offset);
+    buf.extend_from_slice(&encoded);
+    true
+}
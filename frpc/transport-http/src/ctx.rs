@@ -0,0 +1,618 @@
+//! Call handling, That is shared by HTTP/2 and [HTTP/1.1](crate::http1).
+//!
+//! A protocol only provides the request body and a way to respond, See [`RecvRequest`] and [`SendResponse`].
+use crate::http::{header, HeaderMap, HeaderValue, StatusCode};
+use crate::{
+    auth::{self, TokenValidator},
+    batch,
+    chunked::Chunked,
+    compression::{self, Negotiated},
+    heartbeat::{self, Heartbeat},
+    limit::{self, Limits},
+    metrics,
+    rate_limit::RateLimiter,
+    resume,
+    shutdown::{self, Shutdown},
+    trace,
+};
+use bytes::Bytes;
+use frpc_transport_core::*;
+use std::{
+    future::{poll_fn, Future},
+    io,
+    net::SocketAddr,
+    pin::pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+/// Request of a protocol.
+pub trait RecvRequest: Send {
+    fn headers(&self) -> &HeaderMap;
+
+    /// Next chunk of the body, `None` once the body has ended.
+    fn data(&mut self) -> impl Future<Output = Option<io::Result<Bytes>>> + Send;
+}
+
+/// Response of a protocol, Headers are sent along with the body.
+pub trait SendResponse: Send {
+    type Stream: SendStream;
+
+    fn headers_mut(&mut self) -> &mut HeaderMap;
+
+    /// Completes once the client has cancelled the request.
+    fn cancelled(&mut self) -> impl Future<Output = ()> + Send;
+
+    /// Sends the whole body at once.
+    fn send(&mut self, status: StatusCode, chunks: Vec<Bytes>) -> impl Future<Output = ()> + Send;
+
+    /// Starts a body of unknown length, `None` if the client is gone.
+    fn send_stream(&mut self) -> Option<Self::Stream>;
+}
+
+/// Body of a response, That is sent in frames.
+pub trait SendStream: Send {
+    /// Returns `false` if the client is gone, `end` is set on the last frame.
+    fn write(&mut self, bytes: Bytes, end: bool) -> impl Future<Output = bool> + Send;
+
+    /// Completes once the client has cancelled the request.
+    fn cancelled(&mut self) -> impl Future<Output = ()> + Send;
+}
+
+/// Request context, See [`crate::Ctx`] and [`crate::http1::Ctx`].
+pub struct Ctx<Req, Res> {
+    pub req: Req,
+    pub res: Res,
+    /// Remote address of the client, Recorded in the span of every call.
+    pub peer_addr: Option<SocketAddr>,
+    /// Server stream responses are ended, Once it's triggered.
+    pub shutdown: Option<Shutdown>,
+    /// Bounds on concurrent calls of the connection, Unlimited by default.
+    pub limits: Limits,
+    /// Calls are rejected, Once the rate of the client is exceeded.
+    pub rate_limit: Option<Arc<RateLimiter>>,
+    /// Caller verified by mTLS, Replaced by the caller of a valid bearer token.
+    pub identity: Option<Arc<Identity>>,
+    /// Validates bearer token of `authorization` header, See [`auth`](crate::auth).
+    pub token_validator: Option<Arc<dyn TokenValidator>>,
+
+    // config
+    pub max_unary_payload_size: u32,
+    pub compression_threshold: usize,
+    /// Large outputs are sent in chunks of this size, See [`chunked`](crate::chunked).
+    pub chunk_size: usize,
+    /// Max number of calls of a [`batch`](crate::batch) request, That are executed concurrently.
+    pub batch_concurrency: usize,
+    /// Quiet server streams send a [`heartbeat`](crate::heartbeat) frame at this interval.
+    pub heartbeat: Option<Duration>,
+}
+
+impl<Req, Res> std::ops::Deref for Ctx<Req, Res> {
+    type Target = Req;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.req
+    }
+}
+
+impl<Req, Res> Ctx<Req, Res>
+where
+    Req: RecvRequest,
+    Res: SendResponse,
+{
+    pub fn new(req: Req, res: Res) -> Self {
+        Self {
+            req,
+            res,
+            peer_addr: None,
+            shutdown: None,
+            limits: Limits::default(),
+            rate_limit: None,
+            identity: None,
+            token_validator: None,
+            max_unary_payload_size: 128 * 1024,
+            compression_threshold: 1024,
+            chunk_size: 1024 * 1024,
+            batch_concurrency: 16,
+            heartbeat: None,
+        }
+    }
+
+    /// `state` is cloned for every call of a [`batch`](crate::batch) request.
+    pub async fn serve<S, E>(&mut self, _: E, state: S) -> StatusCode
+    where
+        E: Service<State = S>,
+        S: Clone,
+    {
+        let traceparent = self.req.headers().get(trace::TRACEPARENT);
+        let span =
+            trace::CallSpan::new::<E>(self.peer_addr, traceparent.and_then(|v| v.to_str().ok()));
+        if let Some(traceparent) = span.traceparent() {
+            if let Ok(value) = HeaderValue::from_str(traceparent) {
+                self.res.headers_mut().insert(trace::TRACEPARENT, value);
+            }
+        }
+        let call = metrics::Call::new::<E>();
+        let status = span.instrument(self.dispatch::<S, E>(state, &call)).await;
+        span.record_status(status.as_u16());
+        call.status(status.as_u16());
+        status
+    }
+
+    async fn dispatch<S, E>(&mut self, state: S, call: &metrics::Call) -> StatusCode
+    where
+        E: Service<State = S>,
+        S: Clone,
+    {
+        if let Err(status) = self.authenticate() {
+            return status;
+        }
+        let Some(len) = self.req.headers().get(header::CONTENT_LENGTH) else {
+            // Uni-Stream, Bi-Stream
+            return StatusCode::NOT_IMPLEMENTED;
+        };
+        let Ok(Ok(len)) = len.to_str().map(str::parse::<u32>) else {
+            return StatusCode::BAD_REQUEST;
+        };
+        let mut buf = Vec::with_capacity(len.min(self.max_unary_payload_size) as usize);
+        if len >= 2 && !self.req.headers().contains_key(header::CONTENT_ENCODING) {
+            // Rpc id decides, whether the body is streamed.
+            while buf.len() < 2 {
+                match self.req.data().await {
+                    Some(Ok(bytes)) => buf.extend_from_slice(&bytes),
+                    _ => return StatusCode::PARTIAL_CONTENT,
+                }
+            }
+            if E::is_streaming(u16::from_le_bytes([buf[0], buf[1]])) {
+                return self.serve_streaming::<S, E>(state, call, len, buf).await;
+            }
+        }
+        if len > self.max_unary_payload_size {
+            return StatusCode::PAYLOAD_TOO_LARGE;
+        }
+        while let Some(bytes) = self.req.data().await {
+            let Ok(bytes) = bytes else {
+                return StatusCode::PARTIAL_CONTENT;
+            };
+            buf.extend_from_slice(&bytes);
+            if buf.len() > len as usize {
+                return StatusCode::PARTIAL_CONTENT;
+            }
+        }
+        if let Some(encoding) = self.req.headers().get(header::CONTENT_ENCODING) {
+            let Some(encoding) = encoding
+                .to_str()
+                .ok()
+                .and_then(compression::Encoding::from_name)
+            else {
+                return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+            };
+            let max = self.max_unary_payload_size as usize;
+            buf = match encoding.decode(&buf, max) {
+                Ok(buf) if buf.len() > max => return StatusCode::PAYLOAD_TOO_LARGE,
+                Ok(buf) => buf,
+                Err(_) => return StatusCode::BAD_REQUEST,
+            };
+        }
+        if buf.len() < 2 {
+            return StatusCode::BAD_REQUEST;
+        }
+        let id = u16::from_le_bytes([buf[0], buf[1]]);
+        if E::is_streaming(id) {
+            // Only reachable with `content-encoding`, Streamed body isn't decoded.
+            return StatusCode::UNSUPPORTED_MEDIA_TYPE;
+        }
+        let data = &buf[2..];
+        trace::record_request::<E>(id, buf.len());
+        call.rpc::<E>(id);
+        if E::requires_identity(id) && self.identity.is_none() {
+            trace::record_error(auth::UNAUTHENTICATED);
+            return self.unauthenticated();
+        }
+        let compression = self.negotiate();
+        // Calls of a batch are limited one by one.
+        if id == batch::BATCH_ID {
+            return self.serve_batch::<S, E>(state, data, compression).await;
+        }
+        if let Err(retry_after) = self.check_rate(E::NAME, id) {
+            trace::record_error(limit::error());
+            return self.resource_exhausted(Some(retry_after));
+        }
+        let Some(_permit) = self.limits.call(E::NAME, id).await else {
+            trace::record_error(limit::error());
+            return self.resource_exhausted(None);
+        };
+
+        let mut transport = RpcResponder {
+            resume_cursor: self.resume_cursor(),
+            res: &mut self.res,
+            compression,
+            chunk_size: self.chunk_size,
+            body: None,
+            call,
+            shutdown: self.shutdown.clone(),
+            limits: &self.limits,
+            identity: self.identity.clone(),
+            heartbeat: self.heartbeat,
+        };
+        let mut cursor = data;
+        let Some(fut) = E::execute(state, id, &mut cursor, &mut transport) else {
+            return StatusCode::NOT_FOUND;
+        };
+        fut.await;
+        StatusCode::OK
+    }
+
+    fn negotiate(&self) -> Negotiated {
+        let get = |name| self.req.headers().get(name).and_then(|v| v.to_str().ok());
+        Negotiated::new(
+            get(header::ACCEPT_ENCODING.as_str()),
+            get(compression::FRAME_ACCEPT_ENCODING),
+            self.compression_threshold,
+        )
+    }
+
+    fn resume_cursor(&self) -> Option<String> {
+        let cursor = self.req.headers().get(resume::RESUME_CURSOR)?;
+        cursor.to_str().ok().map(str::to_owned)
+    }
+
+    /// Validates bearer token with [`Ctx::token_validator`], Caller is stored in [`Ctx::identity`].
+    fn authenticate(&mut self) -> Result<(), StatusCode> {
+        let Some(validator) = &self.token_validator else {
+            return Ok(());
+        };
+        let token = self
+            .req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(auth::bearer);
+        let Some(token) = token else {
+            return Ok(());
+        };
+        match validator.validate(token) {
+            Some(mut identity) => {
+                let certificate = self.identity.as_ref().map(|id| id.certificate.clone());
+                identity.certificate = certificate.flatten();
+                self.identity = Some(Arc::new(identity));
+                Ok(())
+            }
+            None => Err(self.unauthenticated()),
+        }
+    }
+
+    fn unauthenticated(&mut self) -> StatusCode {
+        let headers = self.res.headers_mut();
+        headers.insert(
+            limit::STATUS_HEADER,
+            HeaderValue::from_static(auth::UNAUTHENTICATED),
+        );
+        headers.insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        StatusCode::UNAUTHORIZED
+    }
+
+    /// Take a token from [`Ctx::rate_limit`], Returns how long to wait if the call is rejected.
+    fn check_rate(&self, service: &'static str, id: u16) -> Result<(), Duration> {
+        let Some(limiter) = &self.rate_limit else {
+            return Ok(());
+        };
+        limiter.check(service, id, self.peer_addr, self.identity.as_deref())
+    }
+
+    fn resource_exhausted(&mut self, retry_after: Option<Duration>) -> StatusCode {
+        let headers = self.res.headers_mut();
+        headers.insert(
+            limit::STATUS_HEADER,
+            HeaderValue::from_static(limit::RESOURCE_EXHAUSTED),
+        );
+        if let Some(retry_after) = retry_after {
+            let secs = retry_after.as_secs_f64().ceil() as u64;
+            headers.insert(header::RETRY_AFTER, secs.into());
+        }
+        StatusCode::TOO_MANY_REQUESTS
+    }
+
+    /// Responds with [`metrics::render`] in Prometheus text format.
+    pub async fn serve_metrics(&mut self) -> StatusCode {
+        self.res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(metrics::CONTENT_TYPE),
+        );
+        let body = Bytes::from(metrics::render());
+        self.res.send(StatusCode::OK, vec![body]).await;
+        StatusCode::OK
+    }
+
+    /// Calls are executed concurrently, See [`batch`].
+    async fn serve_batch<S, E>(
+        &mut self,
+        state: S,
+        data: &[u8],
+        compression: Negotiated,
+    ) -> StatusCode
+    where
+        E: Service<State = S>,
+        S: Clone,
+    {
+        let Some(calls) = batch::parse(data) else {
+            return StatusCode::BAD_REQUEST;
+        };
+        if let Some(encoding) = compression.frame {
+            self.res.headers_mut().insert(
+                compression::FRAME_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            );
+        }
+        let Some(mut stream) = self.res.send_stream() else {
+            return StatusCode::OK;
+        };
+        let (tx, mut rx) = mpsc::channel(16);
+        let config = batch::Config {
+            concurrency: self.batch_concurrency,
+            limits: &self.limits,
+            compression,
+            chunk_size: self.chunk_size,
+            identity: self.identity.clone(),
+            rate_limit: self.rate_limit.as_deref(),
+            peer_addr: self.peer_addr,
+        };
+        let calls = batch::execute::<E>(state, calls, config, tx);
+        let write = async move {
+            // A frame is held back, So that the last one ends the stream.
+            let mut prev = None;
+            while let Some(frame) = rx.recv().await {
+                if let Some(prev) = prev.replace(frame) {
+                    if !stream.write(prev, false).await {
+                        return;
+                    }
+                }
+            }
+            if let Some(last) = prev {
+                stream.write(last, true).await;
+            }
+        };
+        tokio::join!(calls, write);
+        StatusCode::OK
+    }
+
+    /// Only the first `max_unary_payload_size` bytes are buffered, Rest of the body is received by the rpc.
+    ///
+    /// Compressed bodies are rejected with `415 Unsupported Media Type`.
+    async fn serve_streaming<S, E>(
+        &mut self,
+        state: S,
+        call: &metrics::Call,
+        len: u32,
+        mut buf: Vec<u8>,
+    ) -> StatusCode
+    where
+        E: Service<State = S>,
+    {
+        let head_len = len.min(self.max_unary_payload_size) as usize;
+        while buf.len() < head_len {
+            match self.req.data().await {
+                Some(Ok(bytes)) => buf.extend_from_slice(&bytes),
+                _ => return StatusCode::PARTIAL_CONTENT,
+            }
+        }
+        if buf.len() > len as usize {
+            return StatusCode::PARTIAL_CONTENT;
+        }
+        let id = u16::from_le_bytes([buf[0], buf[1]]);
+        trace::record_request::<E>(id, len as usize);
+        call.rpc::<E>(id);
+        if E::requires_identity(id) && self.identity.is_none() {
+            trace::record_error(auth::UNAUTHENTICATED);
+            return self.unauthenticated();
+        }
+        if let Err(retry_after) = self.check_rate(E::NAME, id) {
+            trace::record_error(limit::error());
+            return self.resource_exhausted(Some(retry_after));
+        }
+        let Some(_permit) = self.limits.call(E::NAME, id).await else {
+            trace::record_error(limit::error());
+            return self.resource_exhausted(None);
+        };
+
+        let compression = self.negotiate();
+        let (tx, rx) = mpsc::channel(4);
+        let mut transport = RpcResponder {
+            resume_cursor: self.resume_cursor(),
+            res: &mut self.res,
+            compression,
+            chunk_size: self.chunk_size,
+            body: Some(Box::new(BodyReceiver {
+                rx,
+                remaining: len as usize - buf.len(),
+            })),
+            call,
+            shutdown: self.shutdown.clone(),
+            limits: &self.limits,
+            identity: self.identity.clone(),
+            heartbeat: self.heartbeat,
+        };
+        let mut cursor = &buf[2..];
+        let Some(fut) = E::execute(state, id, &mut cursor, &mut transport) else {
+            return StatusCode::NOT_FOUND;
+        };
+        let req = &mut self.req;
+        let pump = async move {
+            while let Some(bytes) = req.data().await {
+                let bytes = bytes.map(|bytes| bytes.to_vec());
+                let failed = bytes.is_err();
+                if tx.send(bytes).await.is_err() || failed {
+                    break;
+                }
+            }
+        };
+        let mut fut = pin!(fut);
+        tokio::select! {
+            biased;
+            () = &mut fut => {}
+            () = pump => fut.await,
+        }
+        StatusCode::OK
+    }
+}
+
+/// Chunks of request body, Forwarded by [`Ctx`] while the rpc is running.
+///
+/// Channel is bounded, So that a slow rpc applies backpressure to the client.
+pub(crate) struct BodyReceiver {
+    pub(crate) rx: mpsc::Receiver<io::Result<Vec<u8>>>,
+    /// Rest of `content-length`.
+    pub(crate) remaining: usize,
+}
+
+impl Body for BodyReceiver {
+    fn poll_data(&mut self, cx: &mut Context) -> Poll<Option<io::Result<Vec<u8>>>> {
+        let data = ready!(self.rx.poll_recv(cx));
+        if let Some(Ok(bytes)) = &data {
+            self.remaining = self.remaining.saturating_sub(bytes.len());
+        }
+        Poll::Ready(data)
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+pub struct RpcResponder<'a, Res> {
+    res: &'a mut Res,
+    compression: Negotiated,
+    chunk_size: usize,
+    body: Option<Box<dyn Body>>,
+    call: &'a metrics::Call,
+    shutdown: Option<Shutdown>,
+    limits: &'a Limits,
+    identity: Option<Arc<Identity>>,
+    heartbeat: Option<Duration>,
+    resume_cursor: Option<String>,
+}
+
+impl<Res: SendResponse> Transport for RpcResponder<'_, Res> {
+    async fn unary_sync(&mut self, cb: impl FnOnce(&mut dyn io::Write) -> io::Result<()> + Send) {
+        let mut cb = Some(cb);
+        self.unary(move |_, buf| {
+            Poll::Ready(match cb.take() {
+                Some(cb) => cb(buf),
+                None => unreachable!(),
+            })
+        })
+        .await
+    }
+
+    async fn unary(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<()>> + Send,
+    ) {
+        let mut buf = Chunked::new(self.chunk_size, 0);
+        let output = tokio::select! {
+            biased;
+            () = self.res.cancelled() => None,
+            output = poll_fn(|cx| poll(cx, &mut buf)) => Some(output),
+        };
+        match output {
+            Some(Ok(())) => {
+                if let Some(encoding) = self.compression.encode_unary(&mut buf) {
+                    self.res.headers_mut().insert(
+                        header::CONTENT_ENCODING,
+                        HeaderValue::from_static(encoding.as_str()),
+                    );
+                }
+                trace::record_response(buf.len());
+                let chunks = buf.take().collect();
+                self.res.send(StatusCode::OK, chunks).await;
+            }
+            Some(Err(err)) => {
+                trace::record_error(err);
+                self.call.error();
+                self.res.send(StatusCode::NOT_ACCEPTABLE, Vec::new()).await;
+            }
+            None => trace::record_error("cancelled"),
+        }
+    }
+
+    async fn server_stream(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<bool>> + Send,
+    ) {
+        let Some(_permit) = self.limits.stream().await else {
+            trace::record_error(limit::error());
+            self.call.error();
+            self.res.headers_mut().insert(
+                limit::STATUS_HEADER,
+                HeaderValue::from_static(limit::RESOURCE_EXHAUSTED),
+            );
+            let status = StatusCode::TOO_MANY_REQUESTS;
+            return self.res.send(status, Vec::new()).await;
+        };
+        if let Some(encoding) = self.compression.frame {
+            self.res.headers_mut().insert(
+                compression::FRAME_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            );
+        }
+        let Some(mut stream) = self.res.send_stream() else {
+            return;
+        };
+        let mut buf = Chunked::new(self.chunk_size, 4);
+        let mut shutdown = pin!(shutdown::signal(self.shutdown.clone()));
+        let mut heartbeat = Heartbeat::new(self.heartbeat);
+        let mut sent = 0;
+        loop {
+            let done = tokio::select! {
+                biased;
+                () = stream.cancelled() => return trace::record_error("cancelled"),
+                () = &mut shutdown => {
+                    trace::record_error(shutdown::error());
+                    return self.call.error();
+                }
+                result = poll_fn(|cx| poll(cx, &mut buf)) => match result {
+                    Ok(done) => done,
+                    Err(err) => {
+                        trace::record_error(err);
+                        return self.call.error();
+                    }
+                },
+                () = poll_fn(|cx| heartbeat.poll(cx)) => {
+                    let frame = Bytes::from_static(heartbeat::FRAME);
+                    if !stream.write(frame, false).await {
+                        return;
+                    }
+                    continue;
+                }
+            };
+            let frames = buf.take_frames(&self.compression, done);
+            let last = frames.len().saturating_sub(1);
+            for (i, frame) in frames.enumerate() {
+                sent += frame.len();
+                if !stream.write(frame, done && i == last).await {
+                    return;
+                }
+            }
+            trace::record_response(sent);
+            self.call.item();
+            heartbeat.reset();
+            if done {
+                break;
+            }
+        }
+    }
+
+    fn take_body(&mut self) -> Option<Box<dyn Body>> {
+        self.body.take()
+    }
+
+    fn identity(&self) -> Option<Arc<Identity>> {
+        self.identity.clone()
+    }
+
+    fn resume_cursor(&self) -> Option<String> {
+        self.resume_cursor.clone()
+    }
+}
@@ -0,0 +1,48 @@
+//! Heartbeat of server streams, So that intermediaries don't close a quiet stream.
+//!
+//! Heartbeat is a zero-length frame without any flag, Clients skip it.
+//! Empty items are never sent as frames, So it can't be confused with an item.
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::time::{sleep, Instant, Sleep};
+
+/// Length is zero, And `FIN`, `COMPRESSED` and `CONTINUED` flags are unset.
+pub const FRAME: &[u8] = &[0; 4];
+
+/// Ready once the stream is quiet for the interval.
+pub struct Heartbeat {
+    interval: Option<Duration>,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Heartbeat {
+    /// Never ready, If `interval` is `None`.
+    pub fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            sleep: interval.map(|interval| Box::pin(sleep(interval))),
+        }
+    }
+
+    pub fn poll(&mut self, cx: &mut Context) -> Poll<()> {
+        match &mut self.sleep {
+            Some(sleep) => {
+                ready!(sleep.as_mut().poll(cx));
+                self.reset();
+                Poll::Ready(())
+            }
+            None => Poll::Pending,
+        }
+    }
+
+    /// Restart the interval, Called whenever an item is sent.
+    pub fn reset(&mut self) {
+        if let (Some(sleep), Some(interval)) = (&mut self.sleep, self.interval) {
+            sleep.as_mut().reset(Instant::now() + interval);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! HTTP/1.1 support, For clients behind proxies or load balancers that doesn't speak HTTP/2.
+//!
+//! Unary responses are sent with `content-length`, Server stream frames are sent with chunked transfer encoding.
+use crate::{
+    ctx::{self, RecvRequest, SendResponse, SendStream},
+    shutdown::{self, Shutdown},
+};
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Body, Bytes, Frame, Incoming, SizeHint},
+    service::service_fn,
+};
+pub use hyper::{
+    header::{self, HeaderValue},
+    HeaderMap, Method, StatusCode, Uri,
+};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    future::{poll_fn, Future},
+    io, mem,
+    pin::{pin, Pin},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::{mpsc, oneshot},
+};
+
+/// Serve HTTP/1.1 requests from `io`, Each request is handled by `handler` in a separate task.
+pub async fn serve_connection<IO, Fut>(
+    io: IO,
+    handler: impl Fn(Ctx) -> Fut + Send + 'static,
+) -> hyper::Result<()>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    serve_connection_with_shutdown(io, None, handler).await
+}
+
+/// Same as [`serve_connection`], But the connection is closed gracefully once `shutdown` is triggered.
+///
+/// In-flight requests are completed, And the connection is closed without accepting a new one.
+pub async fn serve_connection_with_shutdown<IO, Fut>(
+    io: IO,
+    shutdown: Option<Shutdown>,
+    handler: impl Fn(Ctx) -> Fut + Send + 'static,
+) -> hyper::Result<()>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let config = Config {
+        shutdown,
+        ..Default::default()
+    };
+    serve_connection_with(io, config, handler).await
+}
+
+/// Options of [`serve_connection_with`].
+#[derive(Clone, Default)]
+pub struct Config {
+    /// See [`serve_connection_with_shutdown`].
+    pub shutdown: Option<Shutdown>,
+    /// The connection is closed, If headers of a request aren't received within this duration.
+    pub header_read_timeout: Option<Duration>,
+}
+
+pub async fn serve_connection_with<IO, Fut>(
+    io: IO,
+    config: Config,
+    handler: impl Fn(Ctx) -> Fut + Send + 'static,
+) -> hyper::Result<()>
+where
+    IO: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let Config {
+        shutdown,
+        header_read_timeout,
+    } = config;
+    let signal = shutdown::signal(shutdown.clone());
+    let service = service_fn(move |req: hyper::Request<Incoming>| {
+        let (sender, receiver) = oneshot::channel();
+        let (head, body) = req.into_parts();
+        let mut ctx = Ctx::new(
+            Request {
+                method: head.method,
+                uri: head.uri,
+                headers: head.headers,
+                body,
+            },
+            Response {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                headers: HeaderMap::new(),
+                sender: Some(sender),
+            },
+        );
+        ctx.shutdown = shutdown.clone();
+        tokio::spawn(handler(ctx));
+        async {
+            Ok::<_, Infallible>(receiver.await.unwrap_or_else(|_| {
+                let mut res = hyper::Response::new(ResponseBody::Empty);
+                *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                res
+            }))
+        }
+    });
+    let mut builder = hyper::server::conn::http1::Builder::new();
+    if let Some(timeout) = header_read_timeout {
+        builder
+            .timer(TokioTimer::new())
+            .header_read_timeout(timeout);
+    }
+    let mut conn = pin!(builder.serve_connection(TokioIo::new(io), service));
+    tokio::select! {
+        result = conn.as_mut() => return result,
+        _ = signal => conn.as_mut().graceful_shutdown(),
+    }
+    conn.await
+}
+
+#[derive(Debug)]
+pub struct Request {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Incoming,
+}
+
+/// Response is sent on drop, if it wasn't sent already.
+///
+/// Status defaults to `500 Internal Server Error`, So a handler that never responds isn't mistaken for a success.
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    sender: Option<oneshot::Sender<hyper::Response<ResponseBody>>>,
+}
+
+impl Response {
+    fn send_body(&mut self, body: ResponseBody) -> bool {
+        let Some(sender) = self.sender.take() else {
+            return false;
+        };
+        let mut res = hyper::Response::new(body);
+        *res.status_mut() = self.status;
+        *res.headers_mut() = mem::take(&mut self.headers);
+        sender.send(res).is_ok()
+    }
+}
+
+impl Drop for Response {
+    fn drop(&mut self) {
+        self.send_body(ResponseBody::Empty);
+    }
+}
+
+pub enum ResponseBody {
+    Empty,
+    Full(VecDeque<Bytes>),
+    Stream(mpsc::Receiver<Bytes>),
+}
+
+impl Body for ResponseBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        Poll::Ready(match self.get_mut() {
+            ResponseBody::Empty => None,
+            ResponseBody::Full(chunks) => chunks.pop_front().map(|bytes| Ok(Frame::data(bytes))),
+            ResponseBody::Stream(rx) => {
+                return rx
+                    .poll_recv(cx)
+                    .map(|bytes| bytes.map(|b| Ok(Frame::data(b))))
+            }
+        })
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match self {
+            ResponseBody::Empty => true,
+            ResponseBody::Full(chunks) => chunks.is_empty(),
+            ResponseBody::Stream(_) => false,
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match self {
+            ResponseBody::Empty => SizeHint::with_exact(0),
+            ResponseBody::Full(chunks) => {
+                SizeHint::with_exact(chunks.iter().map(|b| b.len() as u64).sum())
+            }
+            ResponseBody::Stream(_) => SizeHint::default(),
+        }
+    }
+}
+
+/// HTTP/1.1 request context.
+pub type Ctx = ctx::Ctx<Request, Response>;
+
+/// Response to a HTTP/1.1 request.
+pub type RpcResponder<'a> = ctx::RpcResponder<'a, Response>;
+
+impl RecvRequest for Request {
+    fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    async fn data(&mut self) -> Option<io::Result<Bytes>> {
+        loop {
+            match self.body.frame().await? {
+                Ok(frame) => match frame.into_data() {
+                    Ok(bytes) => return Some(Ok(bytes)),
+                    Err(_trailers) => continue,
+                },
+                Err(err) => return Some(Err(io::Error::other(err))),
+            }
+        }
+    }
+}
+
+impl SendResponse for Response {
+    type Stream = mpsc::Sender<Bytes>;
+
+    fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    /// Client has disconnected, If the response receiver is dropped.
+    async fn cancelled(&mut self) {
+        if let Some(sender) = self.sender.as_mut() {
+            poll_fn(|cx| sender.poll_closed(cx)).await
+        }
+    }
+
+    async fn send(&mut self, status: StatusCode, chunks: Vec<Bytes>) {
+        let len: usize = chunks.iter().map(Bytes::len).sum();
+        self.status = status;
+        self.headers.insert(header::CONTENT_LENGTH, len.into());
+        self.send_body(ResponseBody::Full(chunks.into()));
+    }
+
+    fn send_stream(&mut self) -> Option<Self::Stream> {
+        let (tx, rx) = mpsc::channel(16);
+        self.status = StatusCode::OK;
+        self.send_body(ResponseBody::Stream(rx)).then_some(tx)
+    }
+}
+
+/// Frames are sent with chunked transfer encoding, The body ends once the sender is dropped.
+impl SendStream for mpsc::Sender<Bytes> {
+    async fn write(&mut self, bytes: Bytes, _: bool) -> bool {
+        self.send(bytes).await.is_ok()
+    }
+
+    async fn cancelled(&mut self) {
+        self.closed().await
+    }
+}
@@ -1,179 +1,90 @@
-use frpc_transport_core::*;
-use h2x::http::StatusCode;
+pub mod auth;
+pub mod batch;
+pub mod chunked;
+pub mod compression;
+pub mod ctx;
+pub mod heartbeat;
+pub mod http1;
+pub mod limit;
+pub mod metrics;
+pub mod rate_limit;
+pub mod resume;
+pub mod shutdown;
+pub mod trace;
+
+use bytes::Bytes;
+use ctx::{RecvRequest, SendResponse, SendStream};
+use h2x::http::{HeaderMap, StatusCode};
 pub use h2x::*;
-use std::{
-    future::poll_fn,
-    io, mem, ptr,
-    task::{Context, Poll},
-};
+use std::{future::poll_fn, io, mem};
 
-pub struct Ctx {
-    pub req: Request,
-    pub res: Response,
+/// HTTP/2 request context.
+pub type Ctx = ctx::Ctx<Request, Response>;
 
-    // config
-    pub max_unary_payload_size: u32,
-}
+/// Response to a HTTP/2 request.
+pub type RpcResponder<'a> = ctx::RpcResponder<'a, Response>;
 
-impl std::ops::Deref for Ctx {
-    type Target = Request;
-    #[inline]
-    fn deref(&self) -> &Self::Target {
-        &self.req
+impl RecvRequest for Request {
+    fn headers(&self) -> &HeaderMap {
+        &self.headers
     }
-}
 
-impl Ctx {
-    pub fn new(req: Request, res: Response) -> Self {
-        Self {
-            req,
-            res,
-            max_unary_payload_size: 128 * 1024,
+    /// Received bytes are released to the flow control window, So the client can keep sending.
+    async fn data(&mut self) -> Option<io::Result<Bytes>> {
+        let bytes = self.body.data().await?.map_err(io::Error::other);
+        if let Ok(bytes) = &bytes {
+            let _ = self.body.flow_control().release_capacity(bytes.len());
         }
+        Some(bytes)
     }
+}
 
-    pub async fn serve<S, E>(&mut self, _: E, state: S) -> StatusCode
-    where
-        E: Service<State = S>,
-    {
-        match self.req.headers.get("content-length") {
-            Some(len) => {
-                let Ok(Ok(len)) = len.to_str().map(str::parse::<u32>) else {
-                    return StatusCode::BAD_REQUEST;
-                };
-                if len > self.max_unary_payload_size {
-                    return StatusCode::PAYLOAD_TOO_LARGE;
-                }
-                let mut buf = Vec::with_capacity(len as usize);
-                while let Some(bytes) = self.req.body.data().await {
-                    let Ok(bytes) = bytes else {
-                        return StatusCode::PARTIAL_CONTENT;
-                    };
-                    buf.extend_from_slice(&bytes);
-                    if buf.len() > len as usize {
-                        return StatusCode::PARTIAL_CONTENT;
-                    }
-                }
-                if buf.len() < 2 {
-                    return StatusCode::BAD_REQUEST;
-                }
-                let id = u16::from_le_bytes([buf[0], buf[1]]);
-                let data = &buf[2..];
-
-                let mut transport = RpcResponder(&mut self.res);
-                let mut cursor = data;
-                let Some(fut) = E::execute(state, id, &mut cursor, &mut transport) else {
-                    return StatusCode::NOT_FOUND;
-                };
-                fut.await;
-                StatusCode::OK
-            }
-            None => {
-                // Uni-Stream, Bi-Stream
-                StatusCode::NOT_IMPLEMENTED
-            }
-        }
-    }
+/// Headers of `res` are moved into the response.
+fn head(res: &mut Response, status: StatusCode) -> http::Response<()> {
+    let mut response = http::Response::new(());
+    *response.status_mut() = status;
+    *response.headers_mut() = mem::take(&mut res.headers);
+    response
 }
 
-pub struct RpcResponder<'a>(&'a mut Response);
+impl SendResponse for Response {
+    type Stream = h2x::Responder;
 
-impl Transport for RpcResponder<'_> {
-    async fn unary_sync(&mut self, cb: impl FnOnce(&mut dyn io::Write) -> io::Result<()> + Send) {
-        let mut cb = Some(cb);
-        self.unary(move |_, buf| {
-            Poll::Ready(match cb.take() {
-                Some(cb) => cb(buf),
-                None => unreachable!(),
-            })
-        })
-        .await
+    fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
     }
 
-    async fn unary(
-        &mut self,
-        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<()>> + Send,
-    ) {
-        let mut response = http::Response::new(());
-        *response.headers_mut() = mem::take(&mut self.0.headers);
-
-        let mut buf = vec![];
-
-        if let Some(output) = poll_fn(|cx| match self.0.sender.poll_reset(cx) {
-            Poll::Ready(_) => Poll::Ready(None),
-            Poll::Pending => poll(cx, &mut buf).map(Some),
-        })
-        .await
-        {
-            match output {
-                Ok(()) => {
-                    let is_empty = buf.is_empty();
-                    if let Ok(stream) = self.0.sender.send_response(response, is_empty) {
-                        if !is_empty {
-                            let _ = h2x::Responder { inner: stream }
-                                .write_bytes(buf.into(), true)
-                                .await;
-                        }
-                    }
-                }
-                Err(_parse_err) => {
-                    // dbg!(_parse_err);
-                    *response.status_mut() = StatusCode::NOT_ACCEPTABLE;
-                    let _ = self.0.sender.send_response(response, true);
-                }
-            }
-        }
+    async fn cancelled(&mut self) {
+        let _ = poll_fn(|cx| self.sender.poll_reset(cx)).await;
     }
 
-    async fn server_stream(
-        &mut self,
-        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<bool>> + Send,
-    ) {
-        let mut response = http::Response::new(());
-        *response.headers_mut() = mem::take(&mut self.0.headers);
-
-        let Ok(inner) = self.0.sender.send_response(response, false) else {
+    async fn send(&mut self, status: StatusCode, chunks: Vec<Bytes>) {
+        let response = head(self, status);
+        let Ok(inner) = self.sender.send_response(response, chunks.is_empty()) else {
             return;
         };
-
         let mut stream = h2x::Responder { inner };
-        let mut buf = vec![0; 4];
-
-        while let Ok(done) = poll_fn(|cx| match stream.inner.poll_reset(cx) {
-            Poll::Pending => match poll(cx, &mut buf) {
-                Poll::Pending => Poll::Pending,
-                Poll::Ready(result) => Poll::Ready(result.map_err(|_| ())),
-            },
-            Poll::Ready(_) => Poll::Ready(Err(())),
-        })
-        .await
-        {
-            let len = buf.len() - 4;
-            if len >= (1 << 31) {
-                stream.inner.send_reset(h2::Reason::INTERNAL_ERROR);
+        let last = chunks.len().saturating_sub(1);
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            if !stream.write(chunk, i == last).await {
                 break;
             }
-            unsafe {
-                let len = (len as u32).to_le_bytes();
-                // SAFETY: `buf` is valid for `4` bytes.
-                ptr::copy_nonoverlapping(len.as_ptr(), buf.as_mut_ptr(), 4);
-            }
-            match done {
-                false => {
-                    if len == 0 {
-                        continue;
-                    }
-                    let bytes = std::mem::replace(&mut buf, vec![0; 4]);
-                    if stream.write_bytes(bytes.into(), false).await.is_err() {
-                        break;
-                    }
-                }
-                true => {
-                    buf[3] |= 0b1000_0000;
-                    let _ = stream.write_bytes(buf.into(), true).await;
-                    break;
-                }
-            }
         }
     }
+
+    fn send_stream(&mut self) -> Option<Self::Stream> {
+        let response = head(self, StatusCode::OK);
+        let inner = self.sender.send_response(response, false).ok()?;
+        Some(h2x::Responder { inner })
+    }
+}
+
+impl SendStream for h2x::Responder {
+    async fn write(&mut self, bytes: Bytes, end: bool) -> bool {
+        self.write_bytes(bytes, end).await.is_ok()
+    }
+
+    async fn cancelled(&mut self) {
+        let _ = poll_fn(|cx| self.inner.poll_reset(cx)).await;
+    }
 }
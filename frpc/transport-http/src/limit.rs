@@ -0,0 +1,151 @@
+//! Bounds on concurrent calls, So that a burst of requests can't exhaust memory.
+//!
+//! A call must acquire a permit from every configured limit, Before [`Service::execute`](frpc_transport_core::Service::execute).
+//! Server streams are counted by both call and stream limits.
+//!
+//! Rejected calls are answered with `429 Too Many Requests`, And [`STATUS_HEADER`] set to [`RESOURCE_EXHAUSTED`].
+//! Every call of a [`batch`](crate::batch) request acquires its own permit, Without waiting.
+use std::{collections::HashMap, io, sync::Arc};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Response header, Typed status of a rejected call.
+pub const STATUS_HEADER: &str = "frpc-status";
+/// Value of [`STATUS_HEADER`], When a limit is reached.
+pub const RESOURCE_EXHAUSTED: &str = "resource-exhausted";
+
+/// Service name and rpc id.
+pub type RpcKey = (&'static str, u16);
+
+/// What to do when a limit is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wait until a running call is completed.
+    #[default]
+    Queue,
+    /// Reject the call immediately.
+    Reject,
+}
+
+/// `None` means unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct LimitConfig {
+    /// Max in-flight calls of the server.
+    pub max_calls: Option<usize>,
+    /// Max open server streams of the server.
+    pub max_streams: Option<usize>,
+    pub max_calls_per_connection: Option<usize>,
+    pub max_streams_per_connection: Option<usize>,
+    /// Max in-flight calls of an rpc.
+    pub rpc: HashMap<RpcKey, usize>,
+    pub overflow: Overflow,
+}
+
+/// Semaphores of [`LimitConfig`], Cheap to clone.
+///
+/// Default is unlimited.
+#[derive(Clone, Default)]
+pub struct Limits {
+    overflow: Overflow,
+    calls: Option<Arc<Semaphore>>,
+    streams: Option<Arc<Semaphore>>,
+    rpc: Option<Arc<HashMap<RpcKey, Arc<Semaphore>>>>,
+    per_connection: (Option<usize>, Option<usize>),
+    connection_calls: Option<Arc<Semaphore>>,
+    connection_streams: Option<Arc<Semaphore>>,
+}
+
+/// Held for as long as the call is running.
+pub struct Permit {
+    _permits: Vec<OwnedSemaphorePermit>,
+}
+
+fn semaphore(permits: Option<usize>) -> Option<Arc<Semaphore>> {
+    permits.map(|permits| Arc::new(Semaphore::new(permits)))
+}
+
+impl Limits {
+    pub fn new(config: &LimitConfig) -> Self {
+        let rpc = config
+            .rpc
+            .iter()
+            .map(|(&key, &permits)| (key, Arc::new(Semaphore::new(permits))));
+        Self {
+            overflow: config.overflow,
+            calls: semaphore(config.max_calls),
+            streams: semaphore(config.max_streams),
+            rpc: (!config.rpc.is_empty()).then(|| Arc::new(rpc.collect())),
+            per_connection: (
+                config.max_calls_per_connection,
+                config.max_streams_per_connection,
+            ),
+            connection_calls: None,
+            connection_streams: None,
+        }
+    }
+
+    /// Limits of a newly accepted connection, Server limits are shared.
+    pub fn connection(&self) -> Self {
+        Self {
+            connection_calls: semaphore(self.per_connection.0),
+            connection_streams: semaphore(self.per_connection.1),
+            ..self.clone()
+        }
+    }
+
+    fn rpc(&self, service: &'static str, id: u16) -> Option<&Arc<Semaphore>> {
+        self.rpc.as_ref()?.get(&(service, id))
+    }
+
+    /// Returns `None` if the call is rejected.
+    pub async fn call(&self, service: &'static str, id: u16) -> Option<Permit> {
+        self.acquire([
+            self.rpc(service, id),
+            self.connection_calls.as_ref(),
+            self.calls.as_ref(),
+        ])
+        .await
+    }
+
+    /// Returns `None` if the server stream is rejected.
+    pub async fn stream(&self) -> Option<Permit> {
+        self.acquire([self.connection_streams.as_ref(), self.streams.as_ref()])
+            .await
+    }
+
+    /// Same as [`Limits::call`], But never waits.
+    pub fn try_call(&self, service: &'static str, id: u16) -> Option<Permit> {
+        let semaphores = [
+            self.rpc(service, id),
+            self.connection_calls.as_ref(),
+            self.calls.as_ref(),
+        ];
+        let permits = semaphores
+            .into_iter()
+            .flatten()
+            .map(|semaphore| semaphore.clone().try_acquire_owned().ok())
+            .collect::<Option<_>>()?;
+        Some(Permit { _permits: permits })
+    }
+
+    /// Semaphores are always acquired in the same order, So queued calls can't deadlock.
+    async fn acquire<const N: usize>(
+        &self,
+        semaphores: [Option<&Arc<Semaphore>>; N],
+    ) -> Option<Permit> {
+        let mut permits = Vec::new();
+        for semaphore in semaphores.into_iter().flatten() {
+            let semaphore = semaphore.clone();
+            let permit = match self.overflow {
+                Overflow::Queue => semaphore.acquire_owned().await.ok()?,
+                Overflow::Reject => semaphore.try_acquire_owned().ok()?,
+            };
+            permits.push(permit);
+        }
+        Some(Permit { _permits: permits })
+    }
+}
+
+/// Reason of a rejected call.
+pub fn error() -> io::Error {
+    io::Error::new(io::ErrorKind::WouldBlock, "resource exhausted")
+}
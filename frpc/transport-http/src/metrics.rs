@@ -0,0 +1,229 @@
+//! Per rpc metrics, Enabled by `metrics` feature.
+//!
+//! Every call is counted by `service` and `rpc` labels, `rpc` is the ident given to `declare!`.
+//! Ids without a name are counted as `rpc="unknown"`, So that clients can't create a series per id.
+//! Calls that failed before the rpc id is known have an empty `rpc` label.
+//!
+//! Call [`render`] to get them in Prometheus text format.
+//! Without the feature, Nothing is recorded.
+use frpc_transport_core::Service;
+
+#[cfg(feature = "metrics")]
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering::Relaxed},
+        Arc, Mutex, OnceLock,
+    },
+    time::Instant,
+};
+
+/// Content type of [`render`] output.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds of latency histogram buckets, In seconds.
+pub const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0,
+];
+
+/// `rpc` label of ids, That the service doesn't have a name for.
+pub const UNKNOWN_RPC: &str = "unknown";
+
+/// `service` and `rpc` labels.
+#[cfg(feature = "metrics")]
+type Key = (&'static str, &'static str);
+
+#[cfg(feature = "metrics")]
+static REGISTRY: Mutex<BTreeMap<Key, Arc<RpcMetrics>>> = Mutex::new(BTreeMap::new());
+
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct RpcMetrics {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    in_flight: AtomicU64,
+    items: AtomicU64,
+    /// Non-cumulative, Last one is `+Inf`.
+    latency: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    latency_sum_us: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+fn get(service: &'static str, rpc: &'static str) -> Arc<RpcMetrics> {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.entry((service, rpc)).or_default().clone()
+}
+
+/// Metrics of a single call, Recorded when it's finished or dropped.
+pub struct Call {
+    #[cfg(feature = "metrics")]
+    service: &'static str,
+    #[cfg(feature = "metrics")]
+    start: Instant,
+    #[cfg(feature = "metrics")]
+    rpc: OnceLock<Arc<RpcMetrics>>,
+    #[cfg(feature = "metrics")]
+    failed: AtomicBool,
+    #[cfg(feature = "metrics")]
+    finished: AtomicBool,
+}
+
+#[cfg(feature = "metrics")]
+impl Call {
+    pub fn new<E: Service>() -> Self {
+        Self {
+            service: E::NAME,
+            start: Instant::now(),
+            rpc: OnceLock::new(),
+            failed: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+        }
+    }
+
+    /// Rpc id is known.
+    pub fn rpc<E: Service>(&self, id: u16) {
+        let metrics = get(self.service, E::rpc_name(id).unwrap_or(UNKNOWN_RPC));
+        metrics.in_flight.fetch_add(1, Relaxed);
+        let _ = self.rpc.set(metrics);
+    }
+
+    /// Server stream has yielded an item.
+    pub fn item(&self) {
+        if let Some(metrics) = self.rpc.get() {
+            metrics.items.fetch_add(1, Relaxed);
+        }
+    }
+
+    pub fn error(&self) {
+        self.failed.store(true, Relaxed);
+    }
+
+    /// Response status of the request, Anything other than `200 OK` is an error.
+    pub fn status(&self, status: u16) {
+        if status != 200 {
+            self.error();
+        }
+    }
+
+    /// Records the call, Only the first call has any effect.
+    pub fn finish(&self) {
+        if self.finished.swap(true, Relaxed) {
+            return;
+        }
+        let metrics = match self.rpc.get() {
+            Some(metrics) => {
+                metrics.in_flight.fetch_sub(1, Relaxed);
+                metrics.clone()
+            }
+            None => get(self.service, ""),
+        };
+        metrics.requests.fetch_add(1, Relaxed);
+        if self.failed.load(Relaxed) {
+            metrics.errors.fetch_add(1, Relaxed);
+        }
+        let elapsed = self.start.elapsed();
+        let secs = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&le| secs <= le)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        metrics.latency[bucket].fetch_add(1, Relaxed);
+        metrics
+            .latency_sum_us
+            .fetch_add(elapsed.as_micros() as u64, Relaxed);
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for Call {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+impl Call {
+    pub fn new<E: Service>() -> Self {
+        Self {}
+    }
+
+    pub fn rpc<E: Service>(&self, _: u16) {}
+
+    pub fn item(&self) {}
+
+    pub fn error(&self) {}
+
+    pub fn status(&self, _: u16) {}
+
+    pub fn finish(&self) {}
+}
+
+/// All recorded metrics in Prometheus text format.
+#[cfg(feature = "metrics")]
+pub fn render() -> String {
+    let registry: Vec<_> = REGISTRY
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(&(service, rpc), metrics)| {
+            (
+                format!("service=\"{service}\",rpc=\"{rpc}\""),
+                metrics.clone(),
+            )
+        })
+        .collect();
+
+    let mut out = String::new();
+    let mut counter = |name: &str, kind: &str, help: &str, get: fn(&RpcMetrics) -> u64| {
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+        for (labels, metrics) in &registry {
+            let _ = writeln!(out, "{name}{{{labels}}} {}", get(metrics));
+        }
+    };
+    counter(
+        "frpc_requests_total",
+        "counter",
+        "Number of completed calls.",
+        |m| m.requests.load(Relaxed),
+    );
+    counter(
+        "frpc_errors_total",
+        "counter",
+        "Number of failed calls.",
+        |m| m.errors.load(Relaxed),
+    );
+    counter("frpc_in_flight", "gauge", "Number of running calls.", |m| {
+        m.in_flight.load(Relaxed)
+    });
+    counter(
+        "frpc_stream_items_total",
+        "counter",
+        "Number of items yielded by server streams.",
+        |m| m.items.load(Relaxed),
+    );
+
+    let name = "frpc_request_duration_seconds";
+    let _ = writeln!(
+        out,
+        "# HELP {name} Latency of calls.\n# TYPE {name} histogram"
+    );
+    for (labels, metrics) in &registry {
+        let mut count = 0;
+        let les = LATENCY_BUCKETS.iter().map(f64::to_string);
+        for (le, bucket) in les.chain(["+Inf".into()]).zip(&metrics.latency) {
+            count += bucket.load(Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{le}\"}} {count}");
+        }
+        let sum = metrics.latency_sum_us.load(Relaxed) as f64 / 1e6;
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {sum}");
+        let _ = writeln!(out, "{name}_count{{{labels}}} {count}");
+    }
+    out
+}
+
+/// Nothing is recorded without `metrics` feature.
+#[cfg(not(feature = "metrics"))]
+pub fn render() -> String {
+    String::new()
+}
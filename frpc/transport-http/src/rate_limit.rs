@@ -0,0 +1,171 @@
+//! Token bucket rate limiting, Keyed by peer address or the authenticated caller.
+//!
+//! Every key has its own bucket per rule, And a call takes a token from it.
+//! Peers are keyed by [`client_ip`], So a client can't get more buckets by changing its IPv6 address.
+//!
+//! Least recently used buckets are evicted, Once there are more than [`MAX_BUCKETS`].
+//! Buckets created while the table is full start empty, So evicting a bucket doesn't refill it.
+//! Rule of an rpc takes precedence over the rule of its service, Calls without a rule aren't limited.
+//!
+//! Rejected calls are answered with [`RESOURCE_EXHAUSTED`](crate::limit::RESOURCE_EXHAUSTED) status,
+//! And `retry-after` header.
+use frpc_transport_core::{Identity, Service};
+use lru::LruCache;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Max number of buckets, That are kept at once.
+pub const MAX_BUCKETS: usize = 4096;
+
+/// Which bucket a call takes a token from.
+///
+/// Request headers aren't used, As they are set by the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyBy {
+    /// IP address of the client, See [`client_ip`].
+    Peer,
+    /// Caller verified by a bearer token or mTLS, Anonymous calls are keyed by the peer address.
+    Identity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    /// Max number of calls in a burst.
+    pub burst: u32,
+    /// Tokens added to the bucket every second.
+    pub per_second: f64,
+}
+
+impl Rate {
+    pub fn per_second(calls: u32) -> Self {
+        Self {
+            burst: calls,
+            per_second: calls as f64,
+        }
+    }
+
+    pub fn per_minute(calls: u32) -> Self {
+        Self {
+            burst: calls,
+            per_second: calls as f64 / 60.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Peer(IpAddr),
+    Subject(Box<str>),
+    Certificate(Arc<[u8]>),
+}
+
+impl Key {
+    fn new(key_by: KeyBy, peer: Option<SocketAddr>, identity: Option<&Identity>) -> Option<Self> {
+        if let (KeyBy::Identity, Some(identity)) = (key_by, identity) {
+            if let Some(subject) = &identity.subject {
+                return Some(Key::Subject(subject.as_str().into()));
+            }
+            if let Some(certificate) = &identity.certificate {
+                return Some(Key::Certificate(certificate.clone()));
+            }
+        }
+        peer.map(|peer| Key::Peer(client_ip(peer.ip())))
+    }
+}
+
+/// Address that identifies a client.
+///
+/// IPv4-mapped address is converted to IPv4, And IPv6 address is truncated to its `/64` network,
+/// As a single host is usually assigned a whole `/64`.
+pub fn client_ip(ip: IpAddr) -> IpAddr {
+    match ip.to_canonical() {
+        IpAddr::V6(v6) => Ipv6Addr::from(u128::from(v6) & !(u64::MAX as u128)).into(),
+        v4 => v4,
+    }
+}
+
+/// Service name and rpc id, `None` is the rule of the whole service.
+type Rule = (&'static str, Option<u16>);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, rate: &Rate, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.per_second).min(rate.burst as f64);
+        self.updated = now;
+    }
+}
+
+pub struct RateLimiter {
+    key_by: KeyBy,
+    rules: HashMap<Rule, Rate>,
+    buckets: Mutex<LruCache<(Key, Rule), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(key_by: KeyBy) -> Self {
+        Self {
+            key_by,
+            rules: HashMap::new(),
+            buckets: Mutex::new(LruCache::new(NonZeroUsize::new(MAX_BUCKETS).unwrap())),
+        }
+    }
+
+    /// Limit every rpc of service `E`, Each rpc has its own bucket.
+    pub fn service<E: Service>(mut self, rate: Rate) -> Self {
+        self.rules.insert((E::NAME, None), rate);
+        self
+    }
+
+    /// Limit rpc `id` of service `E`.
+    pub fn rpc<E: Service>(mut self, id: u16, rate: Rate) -> Self {
+        self.rules.insert((E::NAME, Some(id)), rate);
+        self
+    }
+
+    /// Take a token, Returns how long to wait if the call is rejected.
+    pub fn check(
+        &self,
+        service: &'static str,
+        id: u16,
+        peer: Option<SocketAddr>,
+        identity: Option<&Identity>,
+    ) -> Result<(), Duration> {
+        let rule = (service, Some(id));
+        let Some(rate) = self.rate(&rule) else {
+            return Ok(());
+        };
+        let Some(key) = Key::new(self.key_by, peer, identity) else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let full = buckets.len() == buckets.cap().get();
+        let bucket = buckets.get_or_insert_mut((key, rule), || Bucket {
+            tokens: if full { 0.0 } else { rate.burst as f64 },
+            updated: now,
+        });
+        bucket.refill(rate, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let wait = (1.0 - bucket.tokens) / rate.per_second;
+        Err(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+    }
+
+    fn rate(&self, &(service, id): &Rule) -> Option<&Rate> {
+        self.rules
+            .get(&(service, id))
+            .or_else(|| self.rules.get(&(service, None)))
+    }
+}
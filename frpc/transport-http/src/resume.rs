@@ -0,0 +1,6 @@
+//! Resumable server streams, Every item carries a cursor defined by the server.
+//!
+//! When the client reconnects, It sends the cursor of the last received item in [`RESUME_CURSOR`] header.
+
+/// Request header, Cursor of the last item received by the client.
+pub const RESUME_CURSOR: &str = "frpc-resume-cursor";
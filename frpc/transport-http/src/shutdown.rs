@@ -0,0 +1,53 @@
+//! Graceful shutdown signal, Shared by the server and every call it's serving.
+//!
+//! Once triggered, New connections aren't accepted, In-flight unary calls are allowed to complete,
+//! And server streams are ended, Which drops their generator.
+use std::{future::pending, io, sync::Arc};
+use tokio::sync::watch;
+
+/// Cheap to clone, Every clone triggers the same signal.
+#[derive(Clone)]
+pub struct Shutdown {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            tx: Arc::new(watch::Sender::new(false)),
+        }
+    }
+
+    /// Start shutting down, Calling it more than once has no effect.
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Completes once the signal is triggered.
+    pub async fn triggered(&self) {
+        let _ = self.tx.subscribe().wait_for(|triggered| *triggered).await;
+    }
+}
+
+/// Same as [`Shutdown::triggered`], But never completes without a signal.
+pub async fn signal(shutdown: Option<Shutdown>) {
+    match shutdown {
+        Some(shutdown) => shutdown.triggered().await,
+        None => pending().await,
+    }
+}
+
+/// Reason of a server stream that was ended by shutdown.
+pub fn error() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "server is shutting down")
+}
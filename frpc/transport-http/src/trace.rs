@@ -0,0 +1,192 @@
+//! Every call is executed inside a span, When `tracing` feature is enabled.
+//!
+//! Span fields: `service`, `rpc`, `rpc.id`, `peer`, `request.size`, `response.size`, `status` and `error`.
+//!
+//! W3C [`TRACEPARENT`] request header is continued: `trace_id` and `parent_id` are taken from it,
+//! And the response carries a `traceparent` header of the server span.
+//! Without the feature, Everything here is a no-op.
+#![cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+use frpc_transport_core::Service;
+use std::{fmt, future::Future, net::SocketAddr};
+
+/// Request and response header, See <https://www.w3.org/TR/trace-context/#traceparent-header>
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Span of a request, Created by `Ctx::serve`.
+pub struct CallSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(feature = "tracing")]
+    traceparent: String,
+}
+
+#[cfg(feature = "tracing")]
+impl CallSpan {
+    pub fn new<E: Service>(peer: Option<SocketAddr>, traceparent: Option<&str>) -> Self {
+        let parent = traceparent.and_then(TraceParent::parse);
+        let trace_id = match parent {
+            Some(ref parent) => parent.trace_id.clone(),
+            None => format!("{:016x}{:016x}", random_u64(), random_u64()),
+        };
+        let span_id = format!("{:016x}", random_u64());
+        let flags = parent.as_ref().map_or("01", |parent| parent.flags.as_str());
+
+        let span = tracing::info_span!(
+            "rpc",
+            service = E::NAME,
+            rpc = tracing::field::Empty,
+            rpc.id = tracing::field::Empty,
+            peer = tracing::field::Empty,
+            request.size = tracing::field::Empty,
+            response.size = tracing::field::Empty,
+            status = tracing::field::Empty,
+            error = tracing::field::Empty,
+            trace_id = trace_id.as_str(),
+            span_id = span_id.as_str(),
+            parent_id = tracing::field::Empty,
+        );
+        if let Some(peer) = peer {
+            span.record("peer", tracing::field::display(peer));
+        }
+        if let Some(ref parent) = parent {
+            span.record("parent_id", parent.parent_id.as_str());
+        }
+        Self {
+            traceparent: format!("00-{trace_id}-{span_id}-{flags}"),
+            span,
+        }
+    }
+
+    /// Value of the response [`TRACEPARENT`] header.
+    pub fn traceparent(&self) -> Option<&str> {
+        Some(&self.traceparent)
+    }
+
+    pub fn instrument<F: Future>(&self, fut: F) -> impl Future<Output = F::Output> {
+        tracing::Instrument::instrument(fut, self.span.clone())
+    }
+
+    pub fn record_status(&self, status: u16) {
+        self.span.record("status", status);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl CallSpan {
+    pub fn new<E: Service>(_: Option<SocketAddr>, _: Option<&str>) -> Self {
+        Self {}
+    }
+
+    pub fn traceparent(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn instrument<F: Future>(&self, fut: F) -> impl Future<Output = F::Output> {
+        fut
+    }
+
+    pub fn record_status(&self, _: u16) {}
+}
+
+/// Records the called rpc on the current span.
+pub fn record_request<E: Service>(id: u16, size: usize) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        span.record("rpc.id", id);
+        span.record("request.size", size);
+        if let Some(name) = E::rpc_name(id) {
+            span.record("rpc", name);
+        }
+    }
+}
+
+/// Total number of bytes sent so far.
+pub fn record_response(size: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("response.size", size);
+}
+
+/// Call has failed, Or it was cancelled by the client.
+pub fn record_error(error: impl fmt::Display) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("error", tracing::field::display(error));
+}
+
+/// Every call of a [`batch`](crate::batch) request has its own span, Nested in the span of the request.
+#[cfg(feature = "tracing")]
+pub fn batch_call<E: Service, F: Future>(
+    id: u16,
+    size: usize,
+    fut: F,
+) -> impl Future<Output = F::Output> {
+    let span = tracing::info_span!(
+        "call",
+        rpc = E::rpc_name(id),
+        rpc.id = id,
+        request.size = size,
+        response.size = tracing::field::Empty,
+        error = tracing::field::Empty,
+    );
+    tracing::Instrument::instrument(fut, span)
+}
+
+#[cfg(not(feature = "tracing"))]
+pub fn batch_call<E: Service, F: Future>(
+    _: u16,
+    _: usize,
+    fut: F,
+) -> impl Future<Output = F::Output> {
+    fut
+}
+
+#[cfg(feature = "tracing")]
+struct TraceParent {
+    trace_id: String,
+    parent_id: String,
+    flags: String,
+}
+
+#[cfg(feature = "tracing")]
+impl TraceParent {
+    /// `{version}-{trace-id}-{parent-id}-{trace-flags}`
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let parent_id = parts.next()?;
+        let flags = parts.next()?;
+        let is_hex = |s: &str, len| {
+            s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        };
+        let is_zero = |s: &str| s.bytes().all(|b| b == b'0');
+        if !is_hex(version, 2) || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        if !is_hex(trace_id, 32) || !is_hex(parent_id, 16) || !is_hex(flags, 2) {
+            return None;
+        }
+        if is_zero(trace_id) || is_zero(parent_id) {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.into(),
+            parent_id: parent_id.into(),
+            flags: flags.into(),
+        })
+    }
+}
+
+/// Ids don't need to be cryptographically secure, Only unique.
+#[cfg(feature = "tracing")]
+fn random_u64() -> u64 {
+    use std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+        sync::atomic::{AtomicU64, Ordering},
+    };
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish().max(1)
+}
@@ -0,0 +1,190 @@
+#![cfg(feature = "jwt")]
+use frpc_transport_http::auth::{bearer, Jwt, TokenValidator};
+use ring::{hmac, rand::SystemRandom, signature};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECRET: &[u8] = b"secret";
+const RSA_PRIVATE_KEY: &[u8] = include_bytes!("fixtures/rsa.pk8");
+const RSA_PUBLIC_KEY: &[u8] = include_bytes!("fixtures/rsa.pub");
+
+fn base64url(input: &[u8]) -> String {
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut out = String::new();
+    for chunk in input.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - i * 8));
+        for i in 0..=chunk.len() {
+            out.push(CHARS[(n >> (18 - i * 6)) as usize & 63] as char);
+        }
+    }
+    out
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+fn message(alg: &str, claims: &Value) -> String {
+    let header = json!({ "alg": alg, "typ": "JWT" });
+    format!(
+        "{}.{}",
+        base64url(header.to_string().as_bytes()),
+        base64url(claims.to_string().as_bytes())
+    )
+}
+
+fn hs256(claims: Value) -> String {
+    let message = message("HS256", &claims);
+    let key = hmac::Key::new(hmac::HMAC_SHA256, SECRET);
+    let tag = hmac::sign(&key, message.as_bytes());
+    format!("{message}.{}", base64url(tag.as_ref()))
+}
+
+fn rs256(claims: Value) -> String {
+    let message = message("RS256", &claims);
+    let key = signature::RsaKeyPair::from_pkcs8(RSA_PRIVATE_KEY).unwrap();
+    let mut sig = vec![0; key.public().modulus_len()];
+    key.sign(
+        &signature::RSA_PKCS1_SHA256,
+        &SystemRandom::new(),
+        message.as_bytes(),
+        &mut sig,
+    )
+    .unwrap();
+    format!("{message}.{}", base64url(&sig))
+}
+
+#[test]
+fn bearer_scheme() {
+    assert_eq!(bearer("Bearer abc"), Some("abc"));
+    assert_eq!(bearer("bearer  abc "), Some("abc"));
+    assert_eq!(bearer("Basic abc"), None);
+    assert_eq!(bearer("Bearer"), None);
+}
+
+#[test]
+fn valid_tokens() {
+    let claims = json!({ "sub": "alice", "scope": "read write", "exp": now() + 60 });
+
+    let identity = Jwt::hs256(SECRET).validate(&hs256(claims.clone())).unwrap();
+    assert_eq!(identity.subject.as_deref(), Some("alice"));
+    assert_eq!(identity.scopes, ["read", "write"]);
+    assert!(identity.certificate.is_none());
+
+    let identity = Jwt::rs256(RSA_PUBLIC_KEY).validate(&rs256(claims)).unwrap();
+    assert_eq!(identity.subject.as_deref(), Some("alice"));
+}
+
+#[test]
+fn alg_none() {
+    let token = format!("{}.", message("none", &json!({ "sub": "alice" })));
+    assert!(Jwt::hs256(SECRET).validate(&token).is_none());
+    assert!(Jwt::rs256(RSA_PUBLIC_KEY).validate(&token).is_none());
+}
+
+#[test]
+fn algorithm_confusion() {
+    // HS256 token, That is signed with the RSA public key as the secret.
+    let message = message("HS256", &json!({ "sub": "alice" }));
+    let key = hmac::Key::new(hmac::HMAC_SHA256, RSA_PUBLIC_KEY);
+    let tag = hmac::sign(&key, message.as_bytes());
+    let token = format!("{message}.{}", base64url(tag.as_ref()));
+    assert!(Jwt::rs256(RSA_PUBLIC_KEY).validate(&token).is_none());
+
+    let token = rs256(json!({ "sub": "alice" }));
+    assert!(Jwt::hs256(SECRET).validate(&token).is_none());
+}
+
+#[test]
+fn bad_signature() {
+    let token = hs256(json!({ "sub": "alice" }));
+    assert!(Jwt::hs256(b"other secret").validate(&token).is_none());
+
+    // Payload is replaced, But the signature is kept.
+    let (_, signature) = token.rsplit_once('.').unwrap();
+    let forged = format!(
+        "{}.{signature}",
+        message("HS256", &json!({ "sub": "mallory" }))
+    );
+    assert!(Jwt::hs256(SECRET).validate(&forged).is_none());
+
+    let mut token = rs256(json!({ "sub": "alice" })).into_bytes();
+    let last = token.len() - 2;
+    token[last] = if token[last] == b'A' { b'B' } else { b'A' };
+    let token = String::from_utf8(token).unwrap();
+    assert!(Jwt::rs256(RSA_PUBLIC_KEY).validate(&token).is_none());
+}
+
+#[test]
+fn exp_and_nbf_with_leeway() {
+    let jwt = Jwt::hs256(SECRET);
+    assert_eq!(jwt.leeway, 60);
+    let valid = |claims| jwt.validate(&hs256(claims)).is_some();
+
+    assert!(valid(json!({ "exp": now() - 30 })));
+    assert!(!valid(json!({ "exp": now() - 120 })));
+    assert!(valid(json!({ "nbf": now() + 30 })));
+    assert!(!valid(json!({ "nbf": now() + 120 })));
+    assert!(!valid(json!({ "exp": "tomorrow" })));
+
+    let mut jwt = Jwt::hs256(SECRET);
+    jwt.leeway = 0;
+    assert!(jwt.validate(&hs256(json!({ "exp": now() - 30 }))).is_none());
+    assert!(jwt.validate(&hs256(json!({ "nbf": now() + 30 }))).is_none());
+    assert!(jwt.validate(&hs256(json!({ "exp": now() + 30 }))).is_some());
+}
+
+#[test]
+fn issuer_and_audience() {
+    let jwt = Jwt::hs256(SECRET).issuer("frpc").audience("api");
+    let valid = |claims| jwt.validate(&hs256(claims)).is_some();
+
+    assert!(valid(json!({ "iss": "frpc", "aud": "api" })));
+    assert!(valid(json!({ "iss": "frpc", "aud": ["web", "api"] })));
+    assert!(!valid(json!({ "iss": "other", "aud": "api" })));
+    assert!(!valid(json!({ "iss": "frpc", "aud": "web" })));
+    assert!(!valid(json!({ "iss": "frpc", "aud": ["web"] })));
+    assert!(!valid(json!({ "aud": "api" })));
+    assert!(!valid(json!({ "iss": "frpc" })));
+
+    // Unchecked, Unless configured.
+    let token = hs256(json!({ "iss": "other", "aud": "web" }));
+    assert!(Jwt::hs256(SECRET).validate(&token).is_some());
+}
+
+#[test]
+fn malformed_base64() {
+    let jwt = Jwt::hs256(SECRET);
+    let token = hs256(json!({ "sub": "alice" }));
+    assert!(jwt.validate(&token).is_some());
+
+    // Padding isn't allowed.
+    assert!(jwt.validate(&format!("{token}=")).is_none());
+    // Standard base64 alphabet.
+    let (message, signature) = token.rsplit_once('.').unwrap();
+    let standard = signature.replace('-', "+").replace('_', "/");
+    if standard != signature {
+        assert!(jwt.validate(&format!("{message}.{standard}")).is_none());
+    }
+    assert!(jwt.validate(&format!("{message}.{signature}!")).is_none());
+    // A single trailing character can't encode a byte.
+    assert!(jwt.validate(&format!("{message}.{signature}A")).is_none());
+    // Trailing bits must be zero, 32 bytes signature leaves 2 bits of the last character.
+    // Its value is a multiple of 4, So the next character is in the same range.
+    let (signature, last) = signature.split_at(signature.len() - 1);
+    let last = (last.as_bytes()[0] + 1) as char;
+    assert!(jwt
+        .validate(&format!("{message}.{signature}{last}"))
+        .is_none());
+
+    assert!(jwt.validate("").is_none());
+    assert!(jwt.validate("abc").is_none());
+    assert!(jwt.validate("abc.def").is_none());
+    assert!(jwt.validate(&format!("e30.{}", signature)).is_none());
+}
@@ -0,0 +1,186 @@
+use bytes::Bytes;
+use frpc_transport_core::{BoxFuture, Identity, Service, Transport};
+use frpc_transport_http::{
+    batch::{self, status, Call, Config, BATCH_ID},
+    compression::Negotiated,
+    limit::Limits,
+    rate_limit::{KeyBy, Rate, RateLimiter},
+};
+use std::{future::Future, net::SocketAddr, sync::Arc, task::Poll, time::Duration};
+use tokio::sync::mpsc;
+
+struct Svc;
+
+impl Service for Svc {
+    type State = ();
+    const NAME: &'static str = "Svc";
+
+    /// `1`: Sleeps for `args[0]` milliseconds, Then echoes it. `2`: Server stream.
+    /// `3`: Echoes the caller, Requires an identity.
+    fn execute<'fut, TR>(
+        _: Self::State,
+        id: u16,
+        cursor: &'fut mut &[u8],
+        transport: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        let delay = cursor.first().copied().unwrap_or(0);
+        let fut: BoxFuture<'fut, ()> = match id {
+            1 => Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(delay.into())).await;
+                transport.unary_sync(|w| w.write_all(&[delay])).await
+            }),
+            2 => Box::pin(transport.server_stream(|_, _| Poll::Ready(Ok(false)))),
+            3 => Box::pin(async move {
+                let subject = transport.identity().unwrap().subject.clone().unwrap();
+                transport
+                    .unary_sync(move |w| w.write_all(subject.as_bytes()))
+                    .await
+            }),
+            _ => return None,
+        };
+        Some(fut)
+    }
+
+    fn requires_identity(id: u16) -> bool {
+        id == 3
+    }
+}
+
+/// Calls of a batch body, `BATCH_ID` is already stripped.
+fn envelope(calls: &[(u16, &[u8])]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for (id, args) in calls {
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(&(args.len() as u32).to_le_bytes());
+        buf.extend_from_slice(args);
+    }
+    buf
+}
+
+/// `(index, status, output)` of every frame, In the order they were sent.
+async fn run(
+    body: &[u8],
+    rate_limit: Option<&RateLimiter>,
+    identity: Option<Identity>,
+) -> Vec<(u16, u8, Vec<u8>)> {
+    let calls = batch::parse(body).unwrap();
+    let limits = Limits::default();
+    let config = Config {
+        concurrency: 16,
+        limits: &limits,
+        compression: Negotiated::default(),
+        chunk_size: 1024,
+        identity: identity.map(Arc::new),
+        rate_limit,
+        peer_addr: Some(SocketAddr::from(([127, 0, 0, 1], 443))),
+    };
+    let (tx, mut rx) = mpsc::channel(16);
+    let execute = batch::execute::<Svc>((), calls, config, tx);
+    let recv = async {
+        let mut frames: Vec<Bytes> = Vec::new();
+        while let Some(frame) = rx.recv().await {
+            frames.push(frame);
+        }
+        frames
+    };
+    let (_, frames) = tokio::join!(execute, recv);
+
+    let (last, frames) = frames.split_last().unwrap();
+    assert_eq!(&last[..], [0, 0, 0, 0b1000_0000]);
+    frames
+        .iter()
+        .map(|frame| {
+            let len = u32::from_le_bytes([frame[0], frame[1], frame[2], 0]) as usize;
+            assert_eq!(frame[3], 0, "no flags are set");
+            assert_eq!(frame.len(), 4 + len);
+            let index = u16::from_le_bytes([frame[4], frame[5]]);
+            (index, frame[6], frame[7..].to_vec())
+        })
+        .collect()
+}
+
+#[test]
+fn malformed_envelope() {
+    assert!(batch::parse(&[]).unwrap().is_empty());
+    // Truncated header.
+    assert!(batch::parse(&[1, 0, 1, 0]).is_none());
+    // Args are shorter than their length.
+    assert!(batch::parse(&[1, 0, 2, 0, 0, 0, 7]).is_none());
+
+    let body = envelope(&[(1, &[7]), (2, &[])]);
+    let calls = batch::parse(&body).unwrap();
+    let calls: Vec<_> = calls.iter().map(|Call { id, args }| (*id, *args)).collect();
+    assert_eq!(calls, [(1, &[7][..]), (2, &[][..])]);
+}
+
+#[tokio::test]
+async fn unknown_id() {
+    let frames = run(&envelope(&[(9, &[]), (BATCH_ID, &[])]), None, None).await;
+    assert_eq!(
+        frames,
+        [
+            (0, status::NOT_FOUND, vec![]),
+            (1, status::NOT_FOUND, vec![])
+        ]
+    );
+}
+
+#[tokio::test]
+async fn server_stream_is_unsupported() {
+    let frames = run(&envelope(&[(2, &[])]), None, None).await;
+    assert_eq!(frames, [(0, status::UNSUPPORTED, vec![])]);
+}
+
+#[tokio::test]
+async fn sent_in_order_of_completion() {
+    let frames = run(&envelope(&[(1, &[60]), (1, &[0]), (1, &[30])]), None, None).await;
+    assert_eq!(
+        frames,
+        [
+            (1, status::OK, vec![0]),
+            (2, status::OK, vec![30]),
+            (0, status::OK, vec![60])
+        ]
+    );
+}
+
+#[tokio::test]
+async fn rate_limited_per_call() {
+    let limiter = RateLimiter::new(KeyBy::Peer).rpc::<Svc>(1, Rate::per_minute(2));
+    let body = envelope(&[(1, &[0]), (1, &[0]), (1, &[0]), (2, &[])]);
+    let mut frames = run(&body, Some(&limiter), None).await;
+    frames.sort();
+    assert_eq!(
+        frames,
+        [
+            (0, status::OK, vec![0]),
+            (1, status::OK, vec![0]),
+            (2, status::RESOURCE_EXHAUSTED, vec![]),
+            (3, status::UNSUPPORTED, vec![])
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unauthenticated_per_call() {
+    let body = envelope(&[(3, &[]), (1, &[0])]);
+    let mut frames = run(&body, None, None).await;
+    frames.sort();
+    assert_eq!(
+        frames,
+        [
+            (0, status::UNAUTHENTICATED, vec![]),
+            (1, status::OK, vec![0])
+        ]
+    );
+
+    let identity = Identity {
+        subject: Some("alice".into()),
+        ..Default::default()
+    };
+    let frames = run(&envelope(&[(3, &[])]), None, Some(identity)).await;
+    assert_eq!(frames, [(0, status::OK, b"alice".to_vec())]);
+}
@@ -0,0 +1,109 @@
+use frpc_transport_http::{
+    chunked::{self, Chunked, CONTINUED, FIN, MAX_FRAME_SIZE},
+    compression::Negotiated,
+};
+use std::io::Write;
+
+/// `(len, flags, payload)` of every frame.
+fn frames(buf: &mut Chunked, compression: &Negotiated, fin: bool) -> Vec<(usize, u8, Vec<u8>)> {
+    buf.take_frames(compression, fin)
+        .map(|frame| {
+            let len = u32::from_le_bytes([frame[0], frame[1], frame[2], frame[3] & 0b1_1111]);
+            assert_eq!(frame.len(), 4 + len as usize);
+            (len as usize, frame[3] & !0b1_1111, frame[4..].to_vec())
+        })
+        .collect()
+}
+
+#[test]
+fn split_at_chunk_size() {
+    let mut buf = Chunked::new(4, 2);
+    assert!(buf.is_empty());
+    buf.write_all(&[1, 2, 3]).unwrap();
+    buf.write_all(&[4]).unwrap();
+    // Exactly full chunk, Next write starts a new one.
+    assert_eq!(buf.chunks().collect::<Vec<_>>(), [&[1, 2, 3, 4][..]]);
+    buf.write_all(&[5, 6, 7, 8, 9, 10]).unwrap();
+    assert_eq!(buf.len(), 10);
+    assert_eq!(
+        buf.chunks().collect::<Vec<_>>(),
+        [&[1, 2, 3, 4][..], &[5, 6, 7, 8], &[9, 10]]
+    );
+
+    let chunks: Vec<_> = buf.take().collect();
+    assert_eq!(
+        chunks.iter().map(|c| c.len()).collect::<Vec<_>>(),
+        [6, 6, 4]
+    );
+    assert!(chunks.iter().all(|chunk| chunk[..2] == [0, 0]));
+    assert!(buf.is_empty());
+    assert_eq!(buf.take().len(), 0);
+}
+
+#[test]
+fn chunk_size_is_clamped() {
+    let mut buf = Chunked::new(0, 0);
+    buf.write_all(&[1, 2]).unwrap();
+    assert_eq!(buf.chunks().collect::<Vec<_>>(), [&[1][..], &[2]]);
+}
+
+#[test]
+fn continued_and_fin() {
+    let none = Negotiated::default();
+    let mut buf = Chunked::new(4, 4);
+    buf.write_all(&[1; 9]).unwrap();
+    assert_eq!(
+        frames(&mut buf, &none, false),
+        [
+            (4, CONTINUED, vec![1; 4]),
+            (4, CONTINUED, vec![1; 4]),
+            (1, 0, vec![1])
+        ]
+    );
+    buf.write_all(&[2; 4]).unwrap();
+    assert_eq!(frames(&mut buf, &none, true), [(4, FIN, vec![2; 4])]);
+
+    // Empty item is skipped, Unless it's the last one.
+    assert!(frames(&mut buf, &none, false).is_empty());
+    assert_eq!(frames(&mut buf, &none, true), [(0, FIN, vec![])]);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn compressed_with_continued_and_fin() {
+    use frpc_transport_http::compression::{Encoding, COMPRESSED};
+
+    let gzip = Negotiated::new(None, Some("gzip"), 0);
+    let mut buf = Chunked::new(256, 4);
+    // Last frame is too small to shrink, So it's sent as it is.
+    buf.write_all(&[0; 513]).unwrap();
+    let split = frames(&mut buf, &gzip, true);
+    let flags: Vec<_> = split.iter().map(|(_, flags, _)| *flags).collect();
+    assert_eq!(flags, [COMPRESSED | CONTINUED, COMPRESSED | CONTINUED, FIN]);
+    for (len, _, payload) in &split[..2] {
+        assert!(*len < 256);
+        assert_eq!(Encoding::Gzip.decode(payload, 256).unwrap(), [0; 256]);
+    }
+    assert_eq!(split[2].2, [0]);
+
+    let mut buf = Chunked::new(256, 4);
+    buf.write_all(&[0; 256]).unwrap();
+    let single = frames(&mut buf, &gzip, true);
+    assert_eq!(single.len(), 1);
+    assert_eq!(single[0].1, COMPRESSED | FIN);
+}
+
+#[test]
+fn header_at_max_frame_size() {
+    let len = MAX_FRAME_SIZE - 1;
+    let header = chunked::header(len, FIN | CONTINUED);
+    assert_eq!(header, [0xff, 0xff, 0xff, 0b1011_1111]);
+    assert_eq!(u32::from_le_bytes(header) & 0x1fff_ffff, len as u32);
+    assert_eq!(chunked::header(0, 0), [0; 4]);
+}
+
+#[test]
+#[should_panic = "frame is too large"]
+fn header_larger_than_max_frame_size() {
+    chunked::header(MAX_FRAME_SIZE, 0);
+}
@@ -0,0 +1,141 @@
+use frpc_transport_http::compression::Encoding;
+
+#[test]
+fn unsupported_encodings() {
+    assert_eq!(Encoding::from_name("compress"), None);
+    assert_eq!(Encoding::from_name("identity"), None);
+    assert_eq!(Encoding::from_name(""), None);
+    assert_eq!(Encoding::negotiate("compress, identity;q=0.5"), None);
+    assert_eq!(Encoding::negotiate(""), None);
+    assert_eq!(Encoding::negotiate(" , ;q=1"), None);
+}
+
+#[test]
+fn wildcard_accepts_the_preferred_encoding() {
+    assert_eq!(Encoding::negotiate("*").as_ref(), Encoding::ALL.first());
+    assert_eq!(Encoding::negotiate("*;q=0"), None);
+}
+
+#[cfg(feature = "gzip")]
+mod gzip {
+    use super::*;
+    use bytes::BytesMut;
+    use frpc_transport_http::{chunked::Chunked, compression::Negotiated};
+    use std::io::Write;
+
+    #[test]
+    fn names() {
+        assert_eq!(Encoding::from_name(" GZip "), Some(Encoding::Gzip));
+        assert_eq!(Encoding::Gzip.as_str(), "gzip");
+    }
+
+    #[test]
+    fn q_values() {
+        let negotiate = Encoding::negotiate;
+        assert_eq!(negotiate("gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("GZIP;q=0.5"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("compress, gzip ; q=1.0"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("gzip;q=0"), None);
+        assert_eq!(negotiate("gzip; Q=0.000"), None);
+        assert_eq!(negotiate("gzip;level=1;q=0"), None);
+        // Explicit rejection wins over the wildcard.
+        assert_eq!(
+            negotiate("*, gzip;q=0").filter(|e| *e == Encoding::Gzip),
+            None
+        );
+        // Malformed q-value isn't a rejection.
+        assert_eq!(negotiate("gzip;q=high"), Some(Encoding::Gzip));
+    }
+
+    fn chunked(data: &[u8]) -> Chunked {
+        let mut buf = Chunked::new(1024, 0);
+        buf.write_all(data).unwrap();
+        buf
+    }
+
+    #[test]
+    fn threshold() {
+        let negotiated = Negotiated::new(Some("gzip"), Some("gzip"), 100);
+
+        let mut buf = chunked(&[0; 99]);
+        assert_eq!(negotiated.encode_unary(&mut buf), None);
+        assert_eq!(buf.len(), 99);
+
+        let mut buf = chunked(&[0; 100]);
+        assert_eq!(negotiated.encode_unary(&mut buf), Some(Encoding::Gzip));
+        assert!(buf.len() < 100);
+        let encoded: Vec<u8> = buf.chunks().flatten().copied().collect();
+        assert_eq!(Encoding::Gzip.decode(&encoded, 100).unwrap(), [0; 100]);
+
+        let mut frame = BytesMut::from(&[0; 4 + 99][..]);
+        assert!(!negotiated.encode_frame(&mut frame));
+        assert_eq!(frame.len(), 4 + 99);
+        let mut frame = BytesMut::from(&[0; 4 + 100][..]);
+        assert!(negotiated.encode_frame(&mut frame));
+        assert_eq!(Encoding::Gzip.decode(&frame[4..], 100).unwrap(), [0; 100]);
+    }
+
+    #[test]
+    fn incompressible_output_is_sent_as_it_is() {
+        // Xorshift, Output of a random generator doesn't shrink.
+        let mut state = 0x2545_f491_u32;
+        let data: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let negotiated = Negotiated::new(Some("gzip"), None, 0);
+        let mut buf = chunked(&data);
+        assert_eq!(negotiated.encode_unary(&mut buf), None);
+        assert_eq!(buf.chunks().flatten().copied().collect::<Vec<_>>(), data);
+        // Not negotiated.
+        let mut frame = BytesMut::from(&[0; 4 + 1024][..]);
+        assert!(!negotiated.encode_frame(&mut frame));
+    }
+
+    #[test]
+    fn decode_is_limited() {
+        let bomb = Encoding::Gzip.encode(&vec![0; 1024 * 1024]).unwrap();
+        assert!(bomb.len() < 16 * 1024);
+
+        // One byte more than the limit, So that caller can detect it.
+        assert_eq!(Encoding::Gzip.decode(&bomb, 1000).unwrap().len(), 1001);
+        assert_eq!(Encoding::Gzip.decode(&bomb, 0).unwrap().len(), 1);
+        let decoded = Encoding::Gzip.decode(&bomb, 1024 * 1024).unwrap();
+        assert_eq!(decoded.len(), 1024 * 1024);
+
+        assert!(Encoding::Gzip.decode(b"not gzip", 1024).is_err());
+        assert!(Encoding::Gzip
+            .decode(&bomb[..bomb.len() / 2], 1024 * 1024)
+            .is_err());
+    }
+}
+
+#[cfg(all(feature = "zstd", feature = "brotli", feature = "gzip"))]
+#[test]
+fn preference_order() {
+    assert_eq!(
+        Encoding::ALL,
+        [Encoding::Zstd, Encoding::Brotli, Encoding::Gzip]
+    );
+    assert_eq!(Encoding::negotiate("gzip, br, zstd"), Some(Encoding::Zstd));
+    assert_eq!(Encoding::negotiate("gzip, br"), Some(Encoding::Brotli));
+    assert_eq!(Encoding::negotiate("*, zstd;q=0"), Some(Encoding::Brotli));
+    assert_eq!(
+        Encoding::negotiate("zstd;q=0, br;q=0, *"),
+        Some(Encoding::Gzip)
+    );
+    assert_eq!(Encoding::from_name("br"), Some(Encoding::Brotli));
+
+    for encoding in Encoding::ALL {
+        let bomb = encoding.encode(&vec![0; 64 * 1024]).unwrap();
+        assert_eq!(
+            encoding.decode(&bomb, 10).unwrap().len(),
+            11,
+            "{encoding:?}"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use frpc_transport_http::limit::{LimitConfig, Limits, Overflow};
+use std::{collections::HashMap, time::Duration};
+use tokio::time::timeout;
+
+const WAIT: Duration = Duration::from_millis(50);
+
+#[tokio::test]
+async fn reject() {
+    let limits = Limits::new(&LimitConfig {
+        max_calls: Some(1),
+        overflow: Overflow::Reject,
+        ..Default::default()
+    });
+    let permit = limits.call("Svc", 1).await.unwrap();
+    assert!(limits.call("Svc", 1).await.is_none());
+    assert!(limits.call("Other", 2).await.is_none());
+    drop(permit);
+    assert!(limits.call("Svc", 1).await.is_some());
+}
+
+#[tokio::test]
+async fn queue() {
+    let limits = Limits::new(&LimitConfig {
+        max_calls: Some(1),
+        ..Default::default()
+    });
+    let permit = limits.call("Svc", 1).await.unwrap();
+    // Never waits, Even if the overflow is queued.
+    assert!(limits.try_call("Svc", 1).is_none());
+    let queued = tokio::spawn({
+        let limits = limits.clone();
+        async move { limits.call("Svc", 1).await.is_some() }
+    });
+    tokio::time::sleep(WAIT).await;
+    assert!(!queued.is_finished());
+    drop(permit);
+    assert!(timeout(Duration::from_secs(5), queued)
+        .await
+        .unwrap()
+        .unwrap());
+}
+
+#[tokio::test]
+async fn per_connection() {
+    let limits = Limits::new(&LimitConfig {
+        max_calls: Some(3),
+        max_calls_per_connection: Some(2),
+        max_streams_per_connection: Some(1),
+        overflow: Overflow::Reject,
+        ..Default::default()
+    });
+    let (a, b) = (limits.connection(), limits.connection());
+    let _a1 = a.call("Svc", 1).await.unwrap();
+    let _a2 = a.call("Svc", 1).await.unwrap();
+    assert!(a.call("Svc", 1).await.is_none());
+
+    // Other connection has its own limit, But the server limit is shared.
+    let _b1 = b.call("Svc", 1).await.unwrap();
+    assert!(b.call("Svc", 1).await.is_none());
+
+    let _stream = a.stream().await.unwrap();
+    assert!(a.stream().await.is_none());
+    assert!(b.stream().await.is_some());
+}
+
+#[tokio::test]
+async fn per_rpc() {
+    let limits = Limits::new(&LimitConfig {
+        rpc: HashMap::from([(("Svc", 1), 1)]),
+        overflow: Overflow::Reject,
+        ..Default::default()
+    });
+    let permit = limits.call("Svc", 1).await.unwrap();
+    assert!(limits.call("Svc", 1).await.is_none());
+    assert!(limits.try_call("Svc", 1).is_none());
+    // Other rpc, And the same id of another service, Are unlimited.
+    assert!(limits.call("Svc", 2).await.is_some());
+    assert!(limits.call("Other", 1).await.is_some());
+    assert!(limits.try_call("Svc", 2).is_some());
+    drop(permit);
+    assert!(limits.try_call("Svc", 1).is_some());
+}
+
+#[tokio::test]
+async fn unlimited_by_default() {
+    let limits = Limits::default().connection();
+    let mut permits = Vec::new();
+    for _ in 0..100 {
+        permits.push(limits.call("Svc", 1).await.unwrap());
+        permits.push(limits.stream().await.unwrap());
+    }
+}
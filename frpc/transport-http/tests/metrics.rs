@@ -0,0 +1,103 @@
+#![cfg(feature = "metrics")]
+use frpc_transport_core::{Service, Transport};
+use frpc_transport_http::metrics::{self, Call, CONTENT_TYPE, LATENCY_BUCKETS};
+use std::future::{Future, Ready};
+
+struct Svc;
+
+impl Service for Svc {
+    type State = ();
+    const NAME: &'static str = "MetricsSvc";
+
+    fn execute<'fut, TR>(
+        _: Self::State,
+        _: u16,
+        _: &'fut mut &[u8],
+        _: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        None::<Ready<()>>
+    }
+
+    fn rpc_name(id: u16) -> Option<&'static str> {
+        (id == 1).then_some("events")
+    }
+}
+
+/// Lines of `render` output, That belong to [`Svc`].
+fn lines(out: &str) -> Vec<&str> {
+    out.lines()
+        .filter(|line| line.contains("service=\"MetricsSvc\""))
+        .collect()
+}
+
+#[test]
+fn render() {
+    let call = Call::new::<Svc>();
+    call.rpc::<Svc>(1);
+    call.item();
+    call.item();
+    call.finish();
+    // Recorded once.
+    drop(call);
+
+    for id in [7, 8, 9] {
+        let call = Call::new::<Svc>();
+        call.rpc::<Svc>(id);
+        call.status(404);
+    }
+    // Failed before the rpc id is known.
+    Call::new::<Svc>().status(501);
+
+    let out = metrics::render();
+    assert!(CONTENT_TYPE.starts_with("text/plain; version=0.0.4"));
+    for (name, kind) in [
+        ("frpc_requests_total", "counter"),
+        ("frpc_errors_total", "counter"),
+        ("frpc_in_flight", "gauge"),
+        ("frpc_stream_items_total", "counter"),
+        ("frpc_request_duration_seconds", "histogram"),
+    ] {
+        assert!(out.contains(&format!("\n# TYPE {name} {kind}\n")), "{name}");
+        assert!(out.contains(&format!("# HELP {name} ")), "{name}");
+    }
+
+    let lines = lines(&out);
+    // Every unknown id is folded into a single series.
+    let labels = [
+        r#"service="MetricsSvc",rpc="""#,
+        r#"service="MetricsSvc",rpc="events""#,
+        r#"service="MetricsSvc",rpc="unknown""#,
+    ];
+    let counters = [
+        ("frpc_requests_total", [1, 1, 3]),
+        ("frpc_errors_total", [1, 0, 3]),
+        ("frpc_in_flight", [0, 0, 0]),
+        ("frpc_stream_items_total", [0, 2, 0]),
+    ];
+    for (name, values) in counters {
+        for (labels, value) in labels.iter().zip(values) {
+            let line = format!("{name}{{{labels}}} {value}");
+            assert!(lines.contains(&line.as_str()), "{line}");
+        }
+    }
+
+    let name = "frpc_request_duration_seconds";
+    for (labels, count) in labels.iter().zip([1, 1, 3]) {
+        let buckets: Vec<_> = lines
+            .iter()
+            .filter(|line| line.starts_with(&format!("{name}_bucket{{{labels},le=")))
+            .collect();
+        assert_eq!(buckets.len(), LATENCY_BUCKETS.len() + 1);
+        assert!(buckets[0].starts_with(&format!("{name}_bucket{{{labels},le=\"0.001\"}} ")));
+        let inf = format!("{name}_bucket{{{labels},le=\"+Inf\"}} {count}");
+        assert_eq!(*buckets[LATENCY_BUCKETS.len()], inf);
+        let total = format!("{name}_count{{{labels}}} {count}");
+        assert!(lines.contains(&total.as_str()), "{total}");
+        let sum = format!("{name}_sum{{{labels}}} ");
+        assert!(lines.iter().any(|line| line.starts_with(&sum)), "{sum}");
+    }
+    assert!(!out.contains("rpc=\"7\""));
+}
@@ -0,0 +1,144 @@
+use frpc_transport_core::{Identity, Service, Transport};
+use frpc_transport_http::rate_limit::{client_ip, KeyBy, Rate, RateLimiter, MAX_BUCKETS};
+use std::{
+    future::{Future, Ready},
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+struct Svc;
+
+impl Service for Svc {
+    type State = ();
+    const NAME: &'static str = "Svc";
+
+    fn execute<'fut, TR>(
+        _: Self::State,
+        _: u16,
+        _: &'fut mut &[u8],
+        _: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        None::<Ready<()>>
+    }
+}
+
+fn peer(n: u32) -> Option<SocketAddr> {
+    Some(SocketAddr::from((n.to_be_bytes(), 443)))
+}
+
+#[test]
+fn burst() {
+    let limiter = RateLimiter::new(KeyBy::Peer).service::<Svc>(Rate::per_minute(3));
+    for _ in 0..3 {
+        assert!(limiter.check(Svc::NAME, 1, peer(1), None).is_ok());
+    }
+    let wait = limiter.check(Svc::NAME, 1, peer(1), None).unwrap_err();
+    assert!(wait > Duration::from_secs(19) && wait <= Duration::from_secs(20));
+
+    // Every rpc and every peer has its own bucket.
+    assert!(limiter.check(Svc::NAME, 2, peer(1), None).is_ok());
+    assert!(limiter.check(Svc::NAME, 1, peer(2), None).is_ok());
+    // Rpc without a rule isn't limited.
+    assert!(limiter.check("Other", 1, peer(1), None).is_ok());
+}
+
+#[test]
+fn rpc_rule_takes_precedence() {
+    let limiter = RateLimiter::new(KeyBy::Peer)
+        .service::<Svc>(Rate::per_minute(1))
+        .rpc::<Svc>(2, Rate::per_minute(2));
+    assert!(limiter.check(Svc::NAME, 2, peer(1), None).is_ok());
+    assert!(limiter.check(Svc::NAME, 2, peer(1), None).is_ok());
+    assert!(limiter.check(Svc::NAME, 2, peer(1), None).is_err());
+}
+
+#[test]
+fn refill() {
+    let rate = Rate {
+        burst: 1,
+        per_second: 20.0,
+    };
+    let limiter = RateLimiter::new(KeyBy::Peer).service::<Svc>(rate);
+    assert!(limiter.check(Svc::NAME, 1, peer(1), None).is_ok());
+    let wait = limiter.check(Svc::NAME, 1, peer(1), None).unwrap_err();
+    assert!(wait <= Duration::from_millis(50));
+
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(limiter.check(Svc::NAME, 1, peer(1), None).is_ok());
+    // Tokens don't exceed the burst, However long it's idle.
+    std::thread::sleep(Duration::from_millis(120));
+    assert!(limiter.check(Svc::NAME, 1, peer(1), None).is_ok());
+    assert!(limiter.check(Svc::NAME, 1, peer(1), None).is_err());
+}
+
+#[test]
+fn keyed_by_identity() {
+    let limiter = RateLimiter::new(KeyBy::Identity).service::<Svc>(Rate::per_minute(1));
+    let alice = Identity {
+        subject: Some("alice".into()),
+        ..Default::default()
+    };
+    let bob = Identity {
+        subject: Some("bob".into()),
+        ..Default::default()
+    };
+    assert!(limiter.check(Svc::NAME, 1, peer(1), Some(&alice)).is_ok());
+    // Same caller from another address.
+    assert!(limiter.check(Svc::NAME, 1, peer(2), Some(&alice)).is_err());
+    assert!(limiter.check(Svc::NAME, 1, peer(1), Some(&bob)).is_ok());
+
+    // Anonymous calls are keyed by the peer address.
+    assert!(limiter.check(Svc::NAME, 1, peer(3), None).is_ok());
+    assert!(limiter.check(Svc::NAME, 1, peer(3), None).is_err());
+}
+
+#[test]
+fn least_recently_used_bucket_is_evicted() {
+    let limiter = RateLimiter::new(KeyBy::Peer).service::<Svc>(Rate::per_minute(1));
+    assert!(limiter.check(Svc::NAME, 1, peer(0), None).is_ok());
+    assert!(limiter.check(Svc::NAME, 1, peer(1), None).is_ok());
+    for n in 2..MAX_BUCKETS as u32 {
+        assert!(limiter.check(Svc::NAME, 1, peer(n), None).is_ok());
+    }
+    // Used recently, So `peer(1)` is evicted first.
+    assert!(limiter.check(Svc::NAME, 1, peer(0), None).is_err());
+    // Table is full, New bucket starts empty.
+    assert!(limiter.check(Svc::NAME, 1, peer(u32::MAX), None).is_err());
+    assert!(limiter.check(Svc::NAME, 1, peer(0), None).is_err());
+
+    // Evicted bucket isn't refilled.
+    assert!(limiter.check(Svc::NAME, 1, peer(1), None).is_err());
+}
+
+#[test]
+fn ipv6_is_keyed_by_prefix() {
+    let limiter = RateLimiter::new(KeyBy::Peer).service::<Svc>(Rate::per_minute(1));
+    let ip = |s: &str| Some(SocketAddr::new(s.parse().unwrap(), 443));
+    assert!(limiter.check(Svc::NAME, 1, ip("2001:db8::1"), None).is_ok());
+    // Same `/64` network.
+    assert!(limiter
+        .check(Svc::NAME, 1, ip("2001:db8::ffff:2"), None)
+        .is_err());
+    assert!(limiter
+        .check(Svc::NAME, 1, ip("2001:db8:0:1::1"), None)
+        .is_ok());
+
+    // Mapped address is the same client.
+    assert!(limiter.check(Svc::NAME, 1, ip("10.0.0.1"), None).is_ok());
+    assert!(limiter
+        .check(Svc::NAME, 1, ip("::ffff:10.0.0.1"), None)
+        .is_err());
+    assert!(limiter.check(Svc::NAME, 1, ip("10.0.0.2"), None).is_ok());
+
+    assert_eq!(
+        client_ip("::ffff:10.0.0.1".parse().unwrap()),
+        IpAddr::from([10, 0, 0, 1])
+    );
+    assert_eq!(
+        client_ip("2001:db8::1".parse().unwrap()),
+        "2001:db8::".parse::<IpAddr>().unwrap()
+    );
+}
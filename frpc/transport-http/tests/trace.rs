@@ -0,0 +1,113 @@
+#![cfg(feature = "tracing")]
+use frpc_transport_core::{Service, Transport};
+use frpc_transport_http::trace::CallSpan;
+use std::future::{Future, Ready};
+
+struct Svc;
+
+impl Service for Svc {
+    type State = ();
+
+    fn execute<'fut, TR>(
+        _: Self::State,
+        _: u16,
+        _: &'fut mut &[u8],
+        _: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        None::<Ready<()>>
+    }
+}
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_ID: &str = "00f067aa0ba902b7";
+
+/// `[version, trace_id, span_id, flags]` of the response header.
+fn response(traceparent: Option<&str>) -> Vec<String> {
+    let span = CallSpan::new::<Svc>(None, traceparent);
+    let parts: Vec<String> = span
+        .traceparent()
+        .unwrap()
+        .split('-')
+        .map(str::to_owned)
+        .collect();
+    assert_eq!(parts.len(), 4);
+    assert_eq!(parts[0], "00");
+    assert_eq!(parts[1].len(), 32);
+    assert_eq!(parts[2].len(), 16);
+    assert_ne!(parts[2], "0".repeat(16));
+    parts
+}
+
+/// Returns `true`, If the trace of `traceparent` is continued.
+fn continued(traceparent: &str) -> bool {
+    let parts = response(Some(traceparent));
+    if parts[1] != TRACE_ID {
+        assert_eq!(parts[3], "01", "new trace is sampled");
+        return false;
+    }
+    true
+}
+
+#[test]
+fn valid() {
+    let parts = response(Some(&format!("00-{TRACE_ID}-{PARENT_ID}-01")));
+    assert_eq!(parts[1], TRACE_ID);
+    assert_ne!(parts[2], PARENT_ID);
+    assert_eq!(parts[3], "01");
+
+    // Flags are propagated.
+    let parts = response(Some(&format!(" 00-{TRACE_ID}-{PARENT_ID}-00 ")));
+    assert_eq!(parts[1], TRACE_ID);
+    assert_eq!(parts[3], "00");
+}
+
+#[test]
+fn new_trace() {
+    let a = response(None);
+    let b = response(None);
+    assert_ne!(a[1], b[1]);
+    assert_ne!(a[2], b[2]);
+    assert_eq!(a[3], "01");
+}
+
+#[test]
+fn version() {
+    // Later versions may append fields.
+    assert!(continued(&format!("01-{TRACE_ID}-{PARENT_ID}-01")));
+    assert!(continued(&format!("cc-{TRACE_ID}-{PARENT_ID}-01-extra")));
+
+    assert!(!continued(&format!("00-{TRACE_ID}-{PARENT_ID}-01-extra")));
+    assert!(!continued(&format!("ff-{TRACE_ID}-{PARENT_ID}-01")));
+    assert!(!continued(&format!("0-{TRACE_ID}-{PARENT_ID}-01")));
+    assert!(!continued(&format!("zz-{TRACE_ID}-{PARENT_ID}-01")));
+}
+
+#[test]
+fn all_zero_ids() {
+    let zero_trace = "0".repeat(32);
+    let zero_parent = "0".repeat(16);
+    let parts = response(Some(&format!("00-{zero_trace}-{PARENT_ID}-01")));
+    assert_ne!(parts[1], zero_trace);
+    assert!(!continued(&format!("00-{TRACE_ID}-{zero_parent}-01")));
+}
+
+#[test]
+fn wrong_length() {
+    let (short_trace, short_parent) = (&TRACE_ID[1..], &PARENT_ID[1..]);
+    assert!(!continued(&format!("00-{short_trace}-{PARENT_ID}-01")));
+    assert!(!continued(&format!("00-{TRACE_ID}0-{PARENT_ID}-01")));
+    assert!(!continued(&format!("00-{TRACE_ID}-{short_parent}-01")));
+    assert!(!continued(&format!("00-{TRACE_ID}-{PARENT_ID}0-01")));
+    assert!(!continued(&format!("00-{TRACE_ID}-{PARENT_ID}-1")));
+    assert!(!continued(&format!("00-{TRACE_ID}-{PARENT_ID}")));
+    assert!(!continued(""));
+}
+
+#[test]
+fn uppercase_hex() {
+    let upper = TRACE_ID.to_uppercase();
+    assert!(!continued(&format!("00-{upper}-{PARENT_ID}-01")));
+}
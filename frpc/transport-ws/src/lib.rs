@@ -0,0 +1,211 @@
+//! WebSocket transport, Many concurrent calls share one socket.
+//!
+//! Every binary message starts with a `u32` (little-endian) call id, chosen by the client.
+//!
+//! - Request: `[call id][rpc id: u16][arguments]`
+//! - Cancel: `[call id]`
+//! - Response: `[call id][kind: u8][payload]`, where `kind` is one of [`frame`]
+//!
+//! A request with the id of an unfinished call, Cancels that call first.
+//! At most [`MAX_CALLS`] calls run at once, Further calls fail with `429` status.
+//! Calls are anonymous, So rpc that take the authenticated caller fail with `401` status.
+//!
+//! [`accept`] performs the HTTP upgrade on a raw stream. If the upgrade is handled by an HTTP server instead,
+//! Wrap the upgraded connection with [`WebSocketStream::from_raw_socket`] and pass it to [`serve`].
+use frpc_transport_core::*;
+use futures_util::{SinkExt, StreamExt};
+use std::{
+    collections::HashMap,
+    future::poll_fn,
+    io,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::{mpsc, Semaphore},
+    task::AbortHandle,
+};
+pub use tokio_tungstenite::{
+    self,
+    tungstenite::{self, Message},
+    WebSocketStream,
+};
+
+/// Max concurrent calls of a connection.
+pub const MAX_CALLS: usize = 128;
+
+/// Kind of response frame.
+pub mod frame {
+    /// Result of unary call.
+    pub const UNARY: u8 = 0;
+    /// Item yielded by server stream.
+    pub const YIELD: u8 = 1;
+    /// Return value of server stream, It's the last frame of a call.
+    pub const RETURN: u8 = 2;
+    /// Call failed, payload is a `u16` (little-endian) http status code.
+    pub const ERROR: u8 = 3;
+}
+
+/// Accept the WebSocket handshake of a client on `io`, Then [`serve`] it.
+///
+/// ```ignore
+/// # async fn run() -> std::io::Result<()> {
+/// let listener = tokio::net::TcpListener::bind("127.0.0.1:4000").await?;
+/// loop {
+///     let (stream, _) = listener.accept().await?;
+///     tokio::spawn(frpc_transport_ws::accept::<Greeter, _>(stream, ()));
+/// }
+/// # }
+/// ```
+pub async fn accept<E, IO>(io: IO, state: E::State) -> Result<(), tungstenite::Error>
+where
+    E: Service + 'static,
+    E::State: Clone + Send + 'static,
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    let ws = tokio_tungstenite::accept_async(io).await?;
+    serve::<E, IO>(ws, state).await;
+    Ok(())
+}
+
+/// Serve rpc calls of service `E` from `ws` until the connection is closed.
+///
+/// Unfinished calls are cancelled when the connection is closed.
+pub async fn serve<E, IO>(ws: WebSocketStream<IO>, state: E::State)
+where
+    E: Service + 'static,
+    E::State: Clone + Send + 'static,
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut sink, mut stream) = ws.split();
+    let (tx, mut rx) = mpsc::channel::<Vec<u8>>(64);
+
+    let writer = async {
+        while let Some(bytes) = rx.recv().await {
+            if sink.send(Message::Binary(bytes)).await.is_err() {
+                break;
+            }
+        }
+    };
+    let reader = async move {
+        let mut calls = HashMap::<u32, AbortHandle>::new();
+        let permits = Arc::new(Semaphore::new(MAX_CALLS));
+        while let Some(Ok(msg)) = stream.next().await {
+            let data = match msg {
+                Message::Binary(data) => data,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let Some(id) = data.get(..4) else {
+                continue;
+            };
+            let id = u32::from_le_bytes([id[0], id[1], id[2], id[3]]);
+            calls.retain(|_, call| !call.is_finished());
+
+            // Cancel, Or the id is reused by a new call.
+            if let Some(call) = calls.remove(&id) {
+                call.abort();
+            }
+            if data.len() == 4 {
+                continue;
+            }
+            let mut transport = WsResponder { id, tx: tx.clone() };
+            let Ok(permit) = permits.clone().try_acquire_owned() else {
+                transport.error(429).await;
+                continue;
+            };
+            let state = state.clone();
+            let task = tokio::spawn(async move {
+                let _permit = permit;
+                if data.len() < 6 {
+                    return transport.error(400).await;
+                }
+                let rpc_id = u16::from_le_bytes([data[4], data[5]]);
+                if E::requires_identity(rpc_id) {
+                    return transport.error(401).await;
+                }
+                let responder = transport.clone();
+                let mut cursor = &data[6..];
+                match E::execute(state, rpc_id, &mut cursor, &mut transport) {
+                    Some(fut) => fut.await,
+                    None => responder.error(404).await,
+                };
+            });
+            calls.insert(id, task.abort_handle());
+        }
+        for call in calls.into_values() {
+            call.abort();
+        }
+    };
+    tokio::select! {
+        _ = writer => {}
+        _ = reader => {}
+    }
+}
+
+#[derive(Clone)]
+pub struct WsResponder {
+    id: u32,
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl WsResponder {
+    fn frame(&self, kind: u8) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(64);
+        buf.extend_from_slice(&self.id.to_le_bytes());
+        buf.push(kind);
+        buf
+    }
+
+    async fn error(&self, status: u16) {
+        let mut buf = self.frame(frame::ERROR);
+        buf.extend_from_slice(&status.to_le_bytes());
+        let _ = self.tx.send(buf).await;
+    }
+}
+
+impl Transport for WsResponder {
+    async fn unary_sync(&mut self, cb: impl FnOnce(&mut dyn io::Write) -> io::Result<()> + Send) {
+        let mut buf = self.frame(frame::UNARY);
+        match cb(&mut buf) {
+            Ok(()) => {
+                let _ = self.tx.send(buf).await;
+            }
+            Err(_) => self.error(406).await,
+        }
+    }
+
+    async fn unary(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<()>> + Send,
+    ) {
+        let mut buf = self.frame(frame::UNARY);
+        match poll_fn(|cx| poll(cx, &mut buf)).await {
+            Ok(()) => {
+                let _ = self.tx.send(buf).await;
+            }
+            Err(_) => self.error(406).await,
+        }
+    }
+
+    async fn server_stream(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<bool>> + Send,
+    ) {
+        loop {
+            let mut buf = self.frame(frame::YIELD);
+            match poll_fn(|cx| poll(cx, &mut buf)).await {
+                Ok(done) => {
+                    if done {
+                        buf[4] = frame::RETURN;
+                    }
+                    if self.tx.send(buf).await.is_err() || done {
+                        break;
+                    }
+                }
+                Err(_) => break self.error(406).await,
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use frpc_transport_core::{BoxFuture, Service, Transport};
+use frpc_transport_ws::{accept, frame, tokio_tungstenite, Message, WebSocketStream, MAX_CALLS};
+use futures_util::{SinkExt, StreamExt};
+use std::{future::Future, task::Poll, time::Duration};
+use tokio::{io::DuplexStream, sync::mpsc, time::timeout};
+
+struct Echo;
+
+/// `(call, running)` is sent when the call of rpc `3` is started and dropped.
+type Events = mpsc::UnboundedSender<(u8, bool)>;
+
+struct Guard(Events, u8);
+
+impl Guard {
+    fn new(events: Events, call: u8) -> Self {
+        let _ = events.send((call, true));
+        Self(events, call)
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let _ = self.0.send((self.1, false));
+    }
+}
+
+impl Service for Echo {
+    type State = Events;
+    const NAME: &'static str = "Echo";
+
+    /// `1`: Echo, `2`: Yields the arguments 3 times, `3`: Never completes, `4`: Requires an identity.
+    fn execute<'fut, TR>(
+        events: Self::State,
+        id: u16,
+        cursor: &'fut mut &[u8],
+        transport: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        let data = cursor.to_vec();
+        let fut: BoxFuture<'fut, ()> = match id {
+            1 => Box::pin(transport.unary_sync(move |w| w.write_all(&data))),
+            2 => {
+                let mut count = 0;
+                Box::pin(transport.server_stream(move |_, w| {
+                    count += 1;
+                    w.write_all(&data)?;
+                    Poll::Ready(Ok(count == 3))
+                }))
+            }
+            3 => {
+                let guard = Guard::new(events, data[0]);
+                Box::pin(transport.unary(move |_, _| {
+                    let _ = &guard;
+                    Poll::Pending
+                }))
+            }
+            4 => Box::pin(transport.unary_sync(|w| w.write_all(&[4]))),
+            _ => return None,
+        };
+        Some(fut)
+    }
+
+    fn requires_identity(id: u16) -> bool {
+        id == 4
+    }
+}
+
+type Client = WebSocketStream<DuplexStream>;
+
+async fn connect() -> (Client, mpsc::UnboundedReceiver<(u8, bool)>) {
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(accept::<Echo, _>(server, tx));
+    let (ws, _) = tokio_tungstenite::client_async("ws://localhost/", client)
+        .await
+        .unwrap();
+    (ws, rx)
+}
+
+async fn send(ws: &mut Client, id: u32, rpc_id: u16, args: &[u8]) {
+    let msg = [&id.to_le_bytes()[..], &rpc_id.to_le_bytes(), args].concat();
+    ws.send(Message::Binary(msg)).await.unwrap();
+}
+
+async fn cancel(ws: &mut Client, id: u32) {
+    ws.send(Message::Binary(id.to_le_bytes().to_vec()))
+        .await
+        .unwrap();
+}
+
+/// `(call id, kind, payload)`
+async fn recv(ws: &mut Client) -> (u32, u8, Vec<u8>) {
+    let msg = timeout(Duration::from_secs(5), ws.next()).await.unwrap();
+    let Some(Ok(Message::Binary(data))) = msg else {
+        panic!("expected a binary message: {msg:?}");
+    };
+    let id = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    (id, data[4], data[5..].to_vec())
+}
+
+#[tokio::test]
+async fn unary() {
+    let (mut ws, _) = connect().await;
+    send(&mut ws, 1, 1, b"hello").await;
+    assert_eq!(recv(&mut ws).await, (1, frame::UNARY, b"hello".to_vec()));
+
+    send(&mut ws, 2, 9, b"").await;
+    let status = 404u16.to_le_bytes().to_vec();
+    assert_eq!(recv(&mut ws).await, (2, frame::ERROR, status));
+
+    // Calls are anonymous.
+    send(&mut ws, 3, 4, b"").await;
+    let status = 401u16.to_le_bytes().to_vec();
+    assert_eq!(recv(&mut ws).await, (3, frame::ERROR, status));
+}
+
+#[tokio::test]
+async fn server_stream() {
+    let (mut ws, _) = connect().await;
+    send(&mut ws, 7, 2, b"item").await;
+    for kind in [frame::YIELD, frame::YIELD, frame::RETURN] {
+        assert_eq!(recv(&mut ws).await, (7, kind, b"item".to_vec()));
+    }
+}
+
+#[tokio::test]
+async fn cancel_call() {
+    let (mut ws, mut events) = connect().await;
+    send(&mut ws, 1, 3, &[1]).await;
+    assert_eq!(events.recv().await, Some((1, true)));
+    send(&mut ws, 2, 3, &[2]).await;
+    assert_eq!(events.recv().await, Some((2, true)));
+    cancel(&mut ws, 1).await;
+    assert_eq!(events.recv().await, Some((1, false)));
+
+    // Other calls keep running.
+    send(&mut ws, 3, 1, b"ok").await;
+    assert_eq!(recv(&mut ws).await, (3, frame::UNARY, b"ok".to_vec()));
+    assert!(events.try_recv().is_err());
+
+    // Every call is cancelled, When the connection is closed.
+    ws.close(None).await.unwrap();
+    assert_eq!(events.recv().await, Some((2, false)));
+}
+
+#[tokio::test]
+async fn reused_id_cancels_the_previous_call() {
+    let (mut ws, mut events) = connect().await;
+    send(&mut ws, 1, 3, &[1]).await;
+    assert_eq!(events.recv().await, Some((1, true)));
+    send(&mut ws, 1, 1, b"new").await;
+    assert_eq!(events.recv().await, Some((1, false)));
+    assert_eq!(recv(&mut ws).await, (1, frame::UNARY, b"new".to_vec()));
+}
+
+#[tokio::test]
+async fn max_calls() {
+    let (mut ws, mut events) = connect().await;
+    for id in 0..MAX_CALLS as u32 {
+        send(&mut ws, id, 3, &[0]).await;
+    }
+    for _ in 0..MAX_CALLS {
+        assert_eq!(events.recv().await, Some((0, true)));
+    }
+    let id = MAX_CALLS as u32;
+    send(&mut ws, id, 1, b"over").await;
+    let status = 429u16.to_le_bytes().to_vec();
+    assert_eq!(recv(&mut ws).await, (id, frame::ERROR, status));
+
+    // Finished call frees its permit.
+    cancel(&mut ws, 0).await;
+    assert_eq!(events.recv().await, Some((0, false)));
+    send(&mut ws, id, 1, b"ok").await;
+    assert_eq!(recv(&mut ws).await, (id, frame::UNARY, b"ok".to_vec()));
+}
@@ -0,0 +1,153 @@
+//! Connection admission, Checked right after a connection is accepted, Before the TLS handshake.
+//!
+//! Rejected connections are closed immediately, [`Application`](crate::Application) never sees them.
+use frpc_transport_http::rate_limit::client_ip;
+use std::{
+    collections::HashMap,
+    io,
+    net::IpAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+/// IP network, Such as `10.0.0.0/8` or `::1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    pub addr: IpAddr,
+    pub prefix: u8,
+}
+
+impl IpNet {
+    /// `prefix` is clamped to the length of the address.
+    ///
+    /// IPv4-mapped network, Such as `::ffff:10.0.0.0/104`, is converted to IPv4.
+    pub fn new(addr: IpAddr, prefix: u8) -> Self {
+        if let (IpAddr::V6(v6), 96..) = (addr, prefix) {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return Self::new(v4.into(), prefix - 96);
+            }
+        }
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        Self {
+            addr,
+            prefix: prefix.min(max),
+        }
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let (net, ip, len) = match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => (u32::from(net).into(), u32::from(ip).into(), 32),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => (u128::from(net), u128::from(ip), 128),
+            _ => return false,
+        };
+        let shift = len - self.prefix as u32;
+        net.checked_shr(shift).unwrap_or(0) == ip.checked_shr(shift).unwrap_or(0)
+    }
+}
+
+impl From<IpAddr> for IpNet {
+    fn from(addr: IpAddr) -> Self {
+        Self::new(addr, 128)
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn invalid<E>(_: E) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidInput, "invalid ip network")
+        }
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.parse().map_err(invalid)?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().map_err(invalid)?,
+            None => max,
+        };
+        if prefix > max {
+            return Err(invalid(prefix));
+        }
+        Ok(IpNet::new(addr, prefix))
+    }
+}
+
+/// IP allow and deny lists, And a limit on connections of an IP.
+#[derive(Debug, Default)]
+pub struct Admission {
+    /// If it isn't empty, Only these networks are admitted.
+    pub allow: Vec<IpNet>,
+    /// Takes precedence over `allow`.
+    pub deny: Vec<IpNet>,
+    /// Max open connections of an IP, IPv6 addresses of the same `/64` network are counted together.
+    pub max_per_ip: Option<usize>,
+    connections: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl Admission {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allow(mut self, net: impl Into<IpNet>) -> Self {
+        self.allow.push(net.into());
+        self
+    }
+
+    pub fn deny(mut self, net: impl Into<IpNet>) -> Self {
+        self.deny.push(net.into());
+        self
+    }
+
+    pub fn max_per_ip(mut self, max: usize) -> Self {
+        self.max_per_ip = Some(max);
+        self
+    }
+
+    /// Returns `None` if the connection is rejected, Otherwise it's counted until [`Admitted`] is dropped.
+    pub fn admit(self: &Arc<Self>, ip: IpAddr) -> Option<Admitted> {
+        let ip = ip.to_canonical();
+        if self.deny.iter().any(|net| net.contains(ip))
+            || !self.allow.is_empty() && !self.allow.iter().any(|net| net.contains(ip))
+        {
+            return None;
+        }
+        if let Some(max) = self.max_per_ip {
+            let mut connections = self.connections.lock().unwrap();
+            let count = connections.entry(client_ip(ip)).or_default();
+            if *count >= max {
+                return None;
+            }
+            *count += 1;
+        }
+        Some(Admitted {
+            admission: self.clone(),
+            ip,
+        })
+    }
+}
+
+/// Held for as long as the connection is open.
+pub struct Admitted {
+    admission: Arc<Admission>,
+    ip: IpAddr,
+}
+
+impl Drop for Admitted {
+    fn drop(&mut self) {
+        if self.admission.max_per_ip.is_none() {
+            return;
+        }
+        let ip = client_ip(self.ip);
+        let mut connections = self.admission.connections.lock().unwrap();
+        if let Some(count) = connections.get_mut(&ip) {
+            *count -= 1;
+            if *count == 0 {
+                connections.remove(&ip);
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{cert::ReloadableCert, client_verifier, ClientAuth, Server};
+use frpc_transport_http::tokio_tls_listener::rustls;
+use std::{
+    io,
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+/// HTTP/2 settings of a connection, `None` is the default of `h2`.
+#[derive(Debug, Clone)]
+pub struct H2Config {
+    pub max_concurrent_streams: Option<u32>,
+    /// Flow control window of a stream.
+    pub initial_window_size: Option<u32>,
+    pub initial_connection_window_size: Option<u32>,
+    pub max_frame_size: Option<u32>,
+    /// Send `PING` frame at this interval, The connection is closed if it isn't answered within `keepalive_timeout`.
+    pub keepalive_interval: Option<Duration>,
+    pub keepalive_timeout: Duration,
+}
+
+impl Default for H2Config {
+    fn default() -> Self {
+        Self {
+            max_concurrent_streams: None,
+            initial_window_size: None,
+            initial_connection_window_size: None,
+            max_frame_size: None,
+            keepalive_interval: None,
+            keepalive_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+impl H2Config {
+    pub(crate) fn builder(&self) -> h2::server::Builder {
+        let mut builder = h2::server::Builder::new();
+        if let Some(max) = self.max_concurrent_streams {
+            builder.max_concurrent_streams(max);
+        }
+        if let Some(size) = self.initial_window_size {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = self.initial_connection_window_size {
+            builder.initial_connection_window_size(size);
+        }
+        if let Some(size) = self.max_frame_size {
+            builder.max_frame_size(size);
+        }
+        builder
+    }
+}
+
+enum Tls {
+    Files(PathBuf, PathBuf),
+    Pem(Vec<u8>, Vec<u8>),
+    Config(rustls::ServerConfig),
+}
+
+/// Builds a [`Server`], Without TLS it's served in cleartext.
+///
+/// ```no_run
+/// # use frpc_transport::*;
+/// # fn main() -> std::io::Result<()> {
+/// let server = ServerBuilder::new()
+///     .tls_files("key.pem", "cert.pem")
+///     .reload_every(std::time::Duration::from_secs(60))
+///     .h2(H2Config {
+///         max_concurrent_streams: Some(256),
+///         ..Default::default()
+///     })
+///     .bind("0.0.0.0:443".parse().unwrap())
+///     .bind("[::]:443".parse().unwrap())
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ServerBuilder {
+    tls: Option<Tls>,
+    client_auth: Option<(Vec<u8>, ClientAuth)>,
+    alpn: Vec<Vec<u8>>,
+    reload_interval: Option<Duration>,
+    h2: H2Config,
+    idle_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    addrs: Vec<SocketAddr>,
+    listeners: Vec<TcpListener>,
+}
+
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self {
+            tls: None,
+            client_auth: None,
+            alpn: vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            reload_interval: None,
+            h2: H2Config::default(),
+            idle_timeout: None,
+            read_timeout: Some(crate::DEFAULT_READ_TIMEOUT),
+            addrs: Vec::new(),
+            listeners: Vec::new(),
+        }
+    }
+}
+
+impl ServerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// PEM encoded key and certificate chain files.
+    pub fn tls_files(mut self, key: impl Into<PathBuf>, cert: impl Into<PathBuf>) -> Self {
+        self.tls = Some(Tls::Files(key.into(), cert.into()));
+        self
+    }
+
+    /// PEM encoded key and certificate chain.
+    pub fn tls_pem(mut self, key: impl Into<Vec<u8>>, cert: impl Into<Vec<u8>>) -> Self {
+        self.tls = Some(Tls::Pem(key.into(), cert.into()));
+        self
+    }
+
+    /// Prebuilt config, Only ALPN protocols are replaced.
+    pub fn tls_config(mut self, config: rustls::ServerConfig) -> Self {
+        self.tls = Some(Tls::Config(config));
+        self
+    }
+
+    /// Authenticate clients by certificates, Signed by one of the PEM encoded `client_ca` certificates.
+    ///
+    /// Same as [`Server::with_client_auth`], Requires [`ServerBuilder::tls_files`] or [`ServerBuilder::tls_pem`].
+    pub fn client_auth(mut self, client_ca: impl Into<Vec<u8>>, auth: ClientAuth) -> Self {
+        self.client_auth = Some((client_ca.into(), auth));
+        self
+    }
+
+    /// Defaults to `h2` and `http/1.1`, A connection is served by HTTP/1.1 only if it's negotiated.
+    ///
+    /// `http/1.1` is left out, Unless [`Server::http1`] is enabled.
+    pub fn alpn<I>(mut self, protocols: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Vec<u8>>,
+    {
+        self.alpn = protocols.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Check [`ServerBuilder::tls_files`] for changes at this interval, And reload the certificate.
+    ///
+    /// Certificate can also be replaced through [`Server::cert`], Requires [`ServerBuilder::tls_files`].
+    pub fn reload_every(mut self, interval: Duration) -> Self {
+        self.reload_interval = Some(interval);
+        self
+    }
+
+    pub fn h2(mut self, config: H2Config) -> Self {
+        self.h2 = config;
+        self
+    }
+
+    /// See [`Server::idle_timeout`].
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Server::read_timeout`], `None` disables it.
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Listen on `addr`, Can be called more than once.
+    pub fn bind(mut self, addr: SocketAddr) -> Self {
+        self.addrs.push(addr);
+        self
+    }
+
+    /// Listen on an already bound listener, Such as from socket activation.
+    pub fn listener(mut self, listener: TcpListener) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Loads the certificate, And binds every address.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`], If [`ServerBuilder::reload_every`] is set without [`ServerBuilder::tls_files`],
+    /// Or [`ServerBuilder::client_auth`] is set without [`ServerBuilder::tls_files`] or [`ServerBuilder::tls_pem`].
+    pub fn build(self) -> io::Result<Server> {
+        if self.reload_interval.is_some() && !matches!(self.tls, Some(Tls::Files(..))) {
+            return Err(invalid_input("`reload_every` requires `tls_files`"));
+        }
+        if self.client_auth.is_some() && !matches!(self.tls, Some(Tls::Files(..) | Tls::Pem(..))) {
+            return Err(invalid_input(
+                "`client_auth` requires `tls_files` or `tls_pem`",
+            ));
+        }
+        let verifier = match &self.client_auth {
+            Some((client_ca, auth)) => Some(client_verifier(client_ca, *auth)?),
+            None => None,
+        };
+        let cert = match &self.tls {
+            Some(Tls::Files(key, cert)) => Some(ReloadableCert::from_files(key, cert)?),
+            Some(Tls::Pem(key, cert)) => Some(ReloadableCert::from_pem(key, cert)?),
+            _ => None,
+        }
+        .map(Arc::new);
+
+        let config = match self.tls {
+            Some(Tls::Config(config)) => Some(config),
+            _ => cert.clone().map(|cert| {
+                let builder = rustls::ServerConfig::builder();
+                match verifier {
+                    Some(verifier) => builder.with_client_cert_verifier(verifier),
+                    None => builder.with_no_client_auth(),
+                }
+                .with_cert_resolver(cert)
+            }),
+        };
+
+        let mut listeners = self.listeners;
+        for addr in self.addrs {
+            listeners.push(TcpListener::bind(addr)?);
+        }
+
+        let mut server = Server::plaintext();
+        server.config = config.map(|mut config| {
+            config.alpn_protocols = self.alpn;
+            Arc::new(config)
+        });
+        server.cert = cert;
+        server.reload_interval = self.reload_interval;
+        server.h2 = self.h2;
+        server.idle_timeout = self.idle_timeout;
+        server.read_timeout = self.read_timeout;
+        server.listeners = listeners.into_iter().map(Arc::new).collect();
+        Ok(server)
+    }
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
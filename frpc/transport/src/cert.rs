@@ -0,0 +1,90 @@
+use crate::invalid_data;
+use frpc_transport_http::tokio_tls_listener::rustls::{
+    self,
+    crypto::CryptoProvider,
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+/// Certificate of the server, That can be replaced without a restart.
+///
+/// New TLS handshakes use the current certificate, Established connections aren't affected.
+#[derive(Debug)]
+pub struct ReloadableCert {
+    provider: Arc<CryptoProvider>,
+    key: RwLock<Arc<CertifiedKey>>,
+    files: Option<(PathBuf, PathBuf)>,
+}
+
+impl ReloadableCert {
+    pub fn from_pem(key: &[u8], cert: &[u8]) -> io::Result<Self> {
+        let provider = rustls::ServerConfig::builder().crypto_provider().clone();
+        let key = certified_key(&provider, key, cert)?;
+        Ok(Self {
+            provider,
+            key: RwLock::new(Arc::new(key)),
+            files: None,
+        })
+    }
+
+    pub fn from_files(key: impl AsRef<Path>, cert: impl AsRef<Path>) -> io::Result<Self> {
+        let (key, cert) = (key.as_ref().to_owned(), cert.as_ref().to_owned());
+        Ok(Self {
+            files: Some((key.clone(), cert.clone())),
+            ..Self::from_pem(&fs::read(key)?, &fs::read(cert)?)?
+        })
+    }
+
+    /// Replace the certificate, The old one is kept if the new one isn't valid.
+    pub fn set_pem(&self, key: &[u8], cert: &[u8]) -> io::Result<()> {
+        let key = certified_key(&self.provider, key, cert)?;
+        *self.key.write().unwrap() = Arc::new(key);
+        Ok(())
+    }
+
+    /// Read the files again, Has no effect if it isn't loaded from files.
+    pub fn reload(&self) -> io::Result<()> {
+        match &self.files {
+            Some((key, cert)) => self.set_pem(&fs::read(key)?, &fs::read(cert)?),
+            None => Ok(()),
+        }
+    }
+
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        let (key, cert) = self.files.as_ref()?;
+        let modified = |path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        Some((modified(key)?, modified(cert)?))
+    }
+
+    /// Reload the files, Whenever they're modified.
+    pub(crate) async fn watch(self: Arc<Self>, interval: Duration) {
+        let mut modified = self.modified();
+        loop {
+            tokio::time::sleep(interval).await;
+            let now = self.modified();
+            // Files may be partially written, So it's retried until the new certificate is valid.
+            if now != modified && self.reload().is_ok() {
+                modified = now;
+            }
+        }
+    }
+}
+
+impl ResolvesServerCert for ReloadableCert {
+    fn resolve(&self, _: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.key.read().unwrap().clone())
+    }
+}
+
+fn certified_key(provider: &CryptoProvider, key: &[u8], cert: &[u8]) -> io::Result<CertifiedKey> {
+    let certs = rustls_pemfile::certs(&mut &*cert).collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut &*key)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no private key found"))?;
+    CertifiedKey::from_der(certs, key, provider).map_err(invalid_data)
+}
@@ -0,0 +1,165 @@
+//! Cross-origin resource sharing, So that browsers can call services from other origins.
+//!
+//! Preflight requests are answered before they reach the application,
+//! And responses of every other request are decorated with `access-control-*` headers.
+use frpc_transport_http::{
+    compression::{FRAME_ACCEPT_ENCODING, FRAME_ENCODING},
+    http1::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    limit::STATUS_HEADER,
+    resume::RESUME_CURSOR,
+    trace::TRACEPARENT,
+};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct Cors {
+    /// `None` allows any origin.
+    pub origins: Option<Vec<String>>,
+    /// Request headers, That the client is allowed to send.
+    pub headers: Vec<String>,
+    pub methods: Vec<Method>,
+    /// How long the browser may cache a preflight response.
+    pub max_age: Option<Duration>,
+    /// Allow cookies and `authorization` header, The origin is echoed instead of `*`.
+    ///
+    /// Only `origins` are allowed, Every origin is rejected if it's `None`.
+    pub credentials: bool,
+    /// Response headers, That are readable by the client.
+    pub expose_headers: Vec<String>,
+}
+
+impl Default for Cors {
+    /// Allows any origin, And every header used by the client.
+    fn default() -> Self {
+        Self {
+            origins: None,
+            headers: [
+                "content-type",
+                "content-encoding",
+                "authorization",
+                FRAME_ACCEPT_ENCODING,
+                TRACEPARENT,
+                RESUME_CURSOR,
+            ]
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+            methods: vec![Method::POST],
+            max_age: Some(Duration::from_secs(24 * 60 * 60)),
+            credentials: false,
+            expose_headers: [STATUS_HEADER, "retry-after", FRAME_ENCODING, TRACEPARENT]
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl Cors {
+    /// Only allow these origins, Such as `https://example.com`.
+    pub fn origins<I>(mut self, origins: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.origins = Some(origins.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>) -> Self {
+        self.headers.push(name.into());
+        self
+    }
+
+    pub fn expose_header(mut self, name: impl Into<String>) -> Self {
+        self.expose_headers.push(name.into());
+        self
+    }
+
+    pub fn method(mut self, method: Method) -> Self {
+        self.methods.push(method);
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Allow credentials, Only from these origins.
+    pub fn credentials<I>(mut self, origins: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.credentials = true;
+        self.origins(origins)
+    }
+
+    fn allow_origin(&self, origin: &HeaderValue) -> Option<HeaderValue> {
+        match &self.origins {
+            Some(origins) => {
+                let origin_str = origin.to_str().ok()?;
+                origins
+                    .iter()
+                    .any(|allowed| allowed == origin_str)
+                    .then(|| origin.clone())
+            }
+            // Echoing any origin with credentials, Would let every site act as the user.
+            None if self.credentials => None,
+            None => Some(HeaderValue::from_static("*")),
+        }
+    }
+
+    /// Adds CORS headers to `res`, Returns the status of the response if it's a preflight request.
+    ///
+    /// Preflight of a disallowed origin is answered with `403 Forbidden`.
+    pub fn apply(
+        &self,
+        method: &Method,
+        req: &HeaderMap,
+        res: &mut HeaderMap,
+    ) -> Option<StatusCode> {
+        // Response depends on the origin, Even if the request doesn't have one.
+        res.append(header::VARY, HeaderValue::from_static("origin"));
+        let origin = req.get(header::ORIGIN)?;
+        let preflight =
+            method == Method::OPTIONS && req.contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+        let Some(origin) = self.allow_origin(origin) else {
+            return preflight.then_some(StatusCode::FORBIDDEN);
+        };
+        res.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        if self.credentials {
+            res.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+        if !preflight {
+            if let Some(value) = join(&self.expose_headers) {
+                res.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, value);
+            }
+            return None;
+        }
+        let methods: Vec<_> = self.methods.iter().map(Method::as_str).collect();
+        if let Some(value) = join(&methods) {
+            res.insert(header::ACCESS_CONTROL_ALLOW_METHODS, value);
+        }
+        if let Some(value) = join(&self.headers) {
+            res.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, value);
+        }
+        if let Some(max_age) = self.max_age {
+            res.insert(header::ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+        }
+        Some(StatusCode::NO_CONTENT)
+    }
+}
+
+fn join(values: &[impl AsRef<str>]) -> Option<HeaderValue> {
+    if values.is_empty() {
+        return None;
+    }
+    let values: Vec<_> = values.iter().map(AsRef::as_ref).collect();
+    HeaderValue::try_from(values.join(", ")).ok()
+}
@@ -1,56 +1,755 @@
+pub mod admission;
+mod builder;
+mod cert;
+pub mod cors;
+mod lifecycle;
+mod stream;
+
+use admission::Admission;
+pub use builder::{H2Config, ServerBuilder};
+use bytes::Bytes;
+pub use cert::ReloadableCert;
+pub use cors::Cors;
+use frpc_transport_core::{Identity, Service};
 pub use frpc_transport_http;
-pub use frpc_transport_http::Ctx;
 use frpc_transport_http::{
     self as http,
-    tokio_tls_listener::{rustls, tokio_rustls::server::TlsStream},
+    auth::TokenValidator,
+    limit::{LimitConfig, Limits},
+    rate_limit::RateLimiter,
+    shutdown,
+    tokio_tls_listener::{rustls, tokio_rustls::TlsAcceptor},
     Conn,
 };
-use std::{future::Future, io, net::SocketAddr, path::Path, sync::Arc};
-use tokio::net::{TcpStream, ToSocketAddrs};
+pub use frpc_transport_http::{http1, shutdown::Shutdown, Ctx};
+use h2::{Ping, PingPong};
+use lifecycle::Activity;
+pub use lifecycle::{CloseReason, ConnectionStats};
+use std::{
+    future::{pending, poll_fn, Future},
+    io,
+    net::SocketAddr,
+    path::Path,
+    pin::{pin, Pin},
+    sync::Arc,
+    task::Poll,
+    time::Duration,
+};
+pub use stream::{Rewind, Stream};
+use tokio::{
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    task::JoinSet,
+};
+
+/// Default of [`Server::read_timeout`].
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Clone)]
 pub struct Server {
-    pub config: Arc<rustls::ServerConfig>,
+    /// `None` is cleartext, See [`Server::plaintext`].
+    pub config: Option<Arc<rustls::ServerConfig>>,
+    /// Requests to this path are answered with [`metrics`](http::metrics), Instead of the application.
+    pub metrics_path: Option<&'static str>,
+    /// [`Server::bind`] returns, Once it's triggered and every connection is closed.
+    pub shutdown: Option<Shutdown>,
+    /// Time given to in-flight calls after shutdown, Connections are closed forcefully when it's elapsed.
+    pub drain_timeout: Duration,
+    /// Bounds on concurrent calls, Set by [`Server::limits`].
+    pub limits: Limits,
+    /// Shared by every connection, Set by [`Server::rate_limit`].
+    pub rate_limit: Option<Arc<RateLimiter>>,
+    /// Validates bearer tokens, Set by [`Server::bearer`].
+    pub token_validator: Option<Arc<dyn TokenValidator>>,
+    /// Applied to every request, Set by [`Server::cors`].
+    pub cors: Option<Arc<Cors>>,
+    /// Certificate loaded by [`ServerBuilder`], Can be replaced while the server is running.
+    pub cert: Option<Arc<ReloadableCert>>,
+    /// Reload [`Server::cert`] from its files, When they're modified.
+    pub reload_interval: Option<Duration>,
+    pub h2: H2Config,
+    /// Connections are closed gracefully, When there is no request for this long.
+    pub idle_timeout: Option<Duration>,
+    /// Time allowed for TLS handshake, HTTP/2 preface and HTTP/1.1 request headers.
+    ///
+    /// Defaults to [`DEFAULT_READ_TIMEOUT`], `None` lets a client hold its connection without sending anything.
+    pub read_timeout: Option<Duration>,
+    /// Accepted by [`Server::serve`] and [`Server::bind`].
+    pub listeners: Vec<Arc<std::net::TcpListener>>,
+    /// IP allow and deny lists, Set by [`Server::admission`].
+    pub admission: Option<Arc<Admission>>,
+    /// Set by [`Server::heartbeat`].
+    pub heartbeat: Option<Duration>,
+    /// Serve HTTP/1.1 requests with [`Application::stream_http1`], Set by [`Server::http1`].
+    pub http1: bool,
+    /// Set by [`Server::route`].
+    routes: Vec<(&'static str, Arc<dyn Route>)>,
+}
+
+/// Whether a client has to present a certificate, Signed by the trusted CA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientAuth {
+    Required,
+    /// Clients without a certificate are anonymous.
+    Optional,
 }
 
 impl Server {
+    /// HTTP/2 over TLS, HTTP/1.1 is served from the same listener once [`Server::http1`] is enabled.
     #[inline]
     pub fn new(key: impl AsRef<Path>, cert: impl AsRef<Path>) -> io::Result<Self> {
-        Ok(Self {
-            config: Arc::new(http::Server::config(key, cert)?),
-        })
+        http::Server::config(key, cert).map(Self::from_config)
+    }
+
+    /// Same as [`Server::new`], But clients are authenticated by certificates, Signed by `client_ca`.
+    ///
+    /// The certificate of a client is available to rpc as [`Identity`], Through the transport.
+    pub fn with_client_auth(
+        key: impl AsRef<Path>,
+        cert: impl AsRef<Path>,
+        client_ca: impl AsRef<Path>,
+        auth: ClientAuth,
+    ) -> io::Result<Self> {
+        let config = http::Server::config(key, cert)?;
+        let verifier = client_verifier(&std::fs::read(client_ca)?, auth)?;
+        Ok(Self::from_config(
+            rustls::ServerConfig::builder()
+                .with_client_cert_verifier(verifier)
+                .with_cert_resolver(config.cert_resolver),
+        ))
+    }
+
+    fn from_config(config: rustls::ServerConfig) -> Self {
+        Self::plaintext().tls(config)
+    }
+
+    /// Cleartext HTTP/2 with prior knowledge (h2c), For deployment behind a TLS-terminating proxy.
+    ///
+    /// Protocol of a connection is detected by the HTTP/2 connection preface, See [`Server::http1`].
+    pub fn plaintext() -> Self {
+        Self {
+            config: None,
+            metrics_path: None,
+            shutdown: None,
+            drain_timeout: Duration::from_secs(30),
+            limits: Limits::default(),
+            rate_limit: None,
+            token_validator: None,
+            cors: None,
+            cert: None,
+            reload_interval: None,
+            h2: H2Config::default(),
+            idle_timeout: None,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            listeners: Vec::new(),
+            admission: None,
+            heartbeat: None,
+            http1: false,
+            routes: Vec::new(),
+        }
+    }
+
+    /// PEM bytes, Prebuilt TLS config, HTTP/2 settings and multiple addresses.
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder::new()
+    }
+
+    /// Serve Prometheus metrics at `path`, Requires `metrics` feature to record anything.
+    pub fn metrics(mut self, path: &'static str) -> Self {
+        self.metrics_path = Some(path);
+        self
+    }
+
+    /// Serve `service` at `path`, Instead of the application, Such as a health check service.
+    pub fn route<E, S>(mut self, path: &'static str, service: E, state: S) -> Self
+    where
+        E: Service<State = S> + Clone + Send + Sync + 'static,
+        S: Clone + Send + Sync + 'static,
+    {
+        self.routes.push((path, Arc::new((service, state))));
+        self
+    }
+
+    /// Shutdown gracefully when `signal` is triggered.
+    ///
+    /// New connections aren't accepted, HTTP/2 clients receive `GOAWAY`, Server streams are ended,
+    /// And in-flight unary calls are given [`Server::drain_timeout`] to complete.
+    /// [`Application::on_close`] is called for every connection before [`Server::bind`] returns.
+    pub fn shutdown(mut self, signal: Shutdown) -> Self {
+        self.shutdown = Some(signal);
+        self
+    }
+
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// See [`Server::read_timeout`].
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Bound concurrent calls and server streams, See [`limit`](http::limit).
+    pub fn limits(mut self, config: LimitConfig) -> Self {
+        self.limits = Limits::new(&config);
+        self
+    }
+
+    /// Reject calls of a client, That exceeds the rate, See [`rate_limit`](http::rate_limit).
+    pub fn rate_limit(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limit = Some(Arc::new(limiter));
+        self
+    }
+
+    /// Authenticate callers by `authorization: Bearer <token>` header, See [`auth`](http::auth).
+    pub fn bearer(mut self, validator: impl TokenValidator) -> Self {
+        self.token_validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Serve over TLS, ALPN protocols of `config` are replaced.
+    ///
+    /// `http/1.1` is only advertised, Once [`Server::http1`] is enabled.
+    pub fn tls(mut self, mut config: rustls::ServerConfig) -> Self {
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        self.config = Some(Arc::new(config));
+        self
+    }
+
+    /// Answer preflight requests, And allow browsers of other origins to read responses.
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.cors = Some(Arc::new(cors));
+        self
+    }
+
+    /// Reject connections by IP, See [`admission`].
+    pub fn admission(mut self, admission: Admission) -> Self {
+        self.admission = Some(Arc::new(admission));
+        self
+    }
+
+    /// Serve HTTP/1.1 too, Requests are handled by [`Application::stream_http1`].
+    ///
+    /// Otherwise `http/1.1` isn't advertised by ALPN, And requests of a cleartext HTTP/1.1 connection
+    /// are answered with `505 HTTP Version Not Supported`, Except the builtin ones such as [`Server::route`].
+    pub fn http1(mut self) -> Self {
+        self.http1 = true;
+        self
+    }
+
+    /// Quiet server streams send a [`heartbeat`](http::heartbeat) frame at this `interval`,
+    /// So that they aren't closed by proxies.
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat = Some(interval);
+        self
     }
 
+    /// `app` is called for every connection, Once its handshake is completed.
+    ///
+    /// Connections are handshaked concurrently, So `app` is shared by their tasks.
     pub async fn bind<Fut, App>(
         self,
         addr: impl ToSocketAddrs,
-        mut app: impl FnMut(SocketAddr, &mut Conn<TlsStream<TcpStream>>) -> Fut,
+        app: impl Fn(SocketAddr, Connection) -> Fut + Send + Sync + 'static,
     ) -> io::Result<()>
     where
-        Fut: Future<Output = App>,
+        Fut: Future<Output = App> + Send + 'static,
+        App: Application,
+    {
+        let mut listeners = self.listen()?;
+        listeners.push(TcpListener::bind(addr).await?);
+        self.run(listeners, app).await
+    }
+
+    /// Same as [`Server::bind`], But only [`Server::listeners`] are accepted.
+    pub async fn serve<Fut, App>(
+        self,
+        app: impl Fn(SocketAddr, Connection) -> Fut + Send + Sync + 'static,
+    ) -> io::Result<()>
+    where
+        Fut: Future<Output = App> + Send + 'static,
         App: Application,
     {
-        let server = http::Server::bind(addr, self.config).await?;
+        if self.listeners.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no address to listen on",
+            ));
+        }
+        let listeners = self.listen()?;
+        self.run(listeners, app).await
+    }
+
+    fn listen(&self) -> io::Result<Vec<TcpListener>> {
+        self.listeners
+            .iter()
+            .map(|listener| {
+                let listener = listener.try_clone()?;
+                listener.set_nonblocking(true)?;
+                TcpListener::from_std(listener)
+            })
+            .collect()
+    }
+
+    async fn run<Fut, App>(
+        self,
+        listeners: Vec<TcpListener>,
+        app: impl Fn(SocketAddr, Connection) -> Fut + Send + Sync + 'static,
+    ) -> io::Result<()>
+    where
+        Fut: Future<Output = App> + Send + 'static,
+        App: Application,
+    {
+        let acceptor = self.config.clone().map(|config| alpn(config, self.http1));
+        let acceptor = acceptor.map(TlsAcceptor::from);
+        let builtin = Builtin {
+            metrics_path: self.metrics_path,
+            routes: self.routes.clone().into(),
+            cors: self.cors.clone(),
+        };
+        let app = Arc::new(app);
+        // Aborted on drop.
+        let mut background = JoinSet::new();
+        if let (Some(cert), Some(interval)) = (self.cert.clone(), self.reload_interval) {
+            background.spawn(cert.watch(interval));
+        }
+        let mut signal = pin!(shutdown::signal(self.shutdown.clone()));
+        let mut connections = JoinSet::new();
+        let server = Arc::new(self);
         loop {
-            if let Ok((mut conn, addr)) = server.accept().await {
-                let app = app(addr, &mut conn).await;
-                tokio::spawn(async move {
-                    while let Some(Ok((req, res))) = conn.accept().await {
-                        let app = app.clone();
-                        tokio::spawn(async move { app.stream(Ctx::new(req, res)).await });
+            let (stream, addr) = tokio::select! {
+                accepted = accept(&listeners) => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(_) => continue,
+                },
+                // Reap closed connections.
+                Some(_) = connections.join_next() => continue,
+                _ = &mut signal => break,
+            };
+            let admitted = match &server.admission {
+                Some(admission) => match admission.admit(addr.ip()) {
+                    Some(admitted) => Some(admitted),
+                    None => continue,
+                },
+                None => None,
+            };
+            // Handshake is done by the task, So that a slow client doesn't hold up the accept loop.
+            let connection = server.clone().connection(
+                stream,
+                addr,
+                acceptor.clone(),
+                builtin.clone(),
+                app.clone(),
+            );
+            connections.spawn(async move {
+                connection.await;
+                drop(admitted);
+            });
+        }
+        while connections.join_next().await.is_some() {}
+        Ok(())
+    }
+
+    /// TLS handshake, Protocol detection and HTTP/2 handshake are bounded by [`Server::read_timeout`].
+    async fn connection<Fut, App>(
+        self: Arc<Self>,
+        stream: TcpStream,
+        addr: SocketAddr,
+        acceptor: Option<TlsAcceptor>,
+        builtin: Builtin,
+        app: Arc<impl Fn(SocketAddr, Connection) -> Fut>,
+    ) where
+        Fut: Future<Output = App>,
+        App: Application,
+    {
+        let shutdown = self.shutdown.clone();
+        let (drain_timeout, read_timeout, idle_timeout) =
+            (self.drain_timeout, self.read_timeout, self.idle_timeout);
+        let serve_http1 = self.http1;
+        let handshake = async {
+            let mut stream = match &acceptor {
+                Some(acceptor) => Stream::Tls(Box::new(acceptor.accept(stream).await.ok()?)),
+                None => Stream::Plain(Rewind::new(stream)),
+            };
+            let identity = peer_identity(&stream);
+            if stream.is_http1().await {
+                return Some((Handshake::Http1(stream), identity));
+            }
+            let conn = self.h2.builder().handshake::<_, Bytes>(stream).await.ok()?;
+            Some((Handshake::H2(Box::new(conn)), identity))
+        };
+        let handshake = tokio::select! {
+            handshake = within(read_timeout, handshake) => handshake.flatten(),
+            _ = shutdown::signal(shutdown.clone()) => None,
+        };
+        let Some((handshake, identity)) = handshake else {
+            return;
+        };
+        let settings = Settings {
+            addr,
+            shutdown: shutdown.clone(),
+            limits: self.limits.connection(),
+            rate_limit: self.rate_limit.clone(),
+            identity,
+            token_validator: self.token_validator.clone(),
+            heartbeat: self.heartbeat,
+        };
+        match handshake {
+            Handshake::Http1(mut stream) => {
+                let app = app(addr, Connection::Http1(&mut stream)).await;
+                let activity = Activity::new();
+                if !app.clone().on_connect(addr).await {
+                    return app.on_close(CloseReason::Rejected, activity.stats()).await;
+                }
+                let close = Shutdown::new();
+                let config = http1::Config {
+                    shutdown: Some(close.clone()),
+                    header_read_timeout: read_timeout,
+                };
+                let handler = app.clone();
+                let requests = activity.clone();
+                let serve = http1::serve_connection_with(stream, config, move |mut ctx| {
+                    settings.http1(&mut ctx);
+                    let request = requests.request();
+                    let handler = handler.clone();
+                    let builtin = builtin.clone();
+                    async move {
+                        if !builtin.http1(&mut ctx).await {
+                            if serve_http1 {
+                                handler.stream_http1(ctx).await
+                            } else {
+                                ctx.res.status = http1::StatusCode::HTTP_VERSION_NOT_SUPPORTED;
+                                // Response is sent on drop.
+                                drop(ctx);
+                            }
+                        }
+                        drop(request);
                     }
-                    app.close().await;
                 });
+                let serve = async {
+                    let mut serve = pin!(serve);
+                    let closing = tokio::select! {
+                        result = serve.as_mut() => return close_reason(result, CloseReason::Client),
+                        _ = shutdown::signal(shutdown.clone()) => CloseReason::Shutdown,
+                        _ = activity.idle(idle_timeout) => CloseReason::Idle,
+                    };
+                    close.trigger();
+                    close_reason(serve.await, closing)
+                };
+                let reason = drain(serve, shutdown.clone(), drain_timeout).await;
+                app.on_close(reason, activity.stats()).await;
+            }
+            Handshake::H2(mut conn) => {
+                let ping_pong = conn.ping_pong();
+                let mut conn = Conn::from(*conn);
+                let app = app(addr, Connection::H2(&mut conn)).await;
+                let keepalive = (self.h2.keepalive_interval, self.h2.keepalive_timeout);
+                let activity = Activity::new();
+                if !app.clone().on_connect(addr).await {
+                    return app.on_close(CloseReason::Rejected, activity.stats()).await;
+                }
+                let serve = {
+                    let (app, activity, shutdown) =
+                        (app.clone(), activity.clone(), shutdown.clone());
+                    async move {
+                        let mut signal = pin!(shutdown::signal(shutdown.clone()));
+                        let mut keepalive = pin!(keepalive_h2(ping_pong, keepalive));
+                        let mut closing = None;
+                        // Aborted on drop, Such as when the drain timeout is elapsed.
+                        let mut requests = JoinSet::new();
+                        let reason = loop {
+                            let accepted = tokio::select! {
+                                accepted = conn.accept() => accepted,
+                                Some(_) = requests.join_next() => continue,
+                                _ = &mut signal, if closing.is_none() => {
+                                    // Sends `GOAWAY`, Accepted streams are still served.
+                                    conn.graceful_shutdown();
+                                    closing = Some(CloseReason::Shutdown);
+                                    continue;
+                                }
+                                _ = activity.idle(idle_timeout), if closing.is_none() => {
+                                    conn.graceful_shutdown();
+                                    closing = Some(CloseReason::Idle);
+                                    continue;
+                                }
+                                _ = &mut keepalive => return CloseReason::KeepaliveTimeout,
+                            };
+                            let (req, res) = match accepted {
+                                Some(Ok(accepted)) => accepted,
+                                Some(Err(err)) => return CloseReason::Error(err.to_string()),
+                                None => break closing.unwrap_or(CloseReason::Client),
+                            };
+                            let request = activity.request();
+                            let app = app.clone();
+                            let builtin = builtin.clone();
+                            let mut ctx = Ctx::new(req, res);
+                            settings.h2(&mut ctx);
+                            requests.spawn(async move {
+                                if !builtin.h2(&mut ctx).await {
+                                    app.stream(ctx).await
+                                }
+                                drop(request);
+                            });
+                        };
+                        while requests.join_next().await.is_some() {}
+                        reason
+                    }
+                };
+                let reason = drain(serve, shutdown.clone(), drain_timeout).await;
+                app.on_close(reason, activity.stats()).await;
             }
         }
     }
 }
 
+/// Connection, Once the handshake of its protocol is completed.
+enum Handshake {
+    Http1(Stream),
+    H2(Box<h2::server::Connection<Stream, Bytes>>),
+}
+
+/// Accept a connection from any of the `listeners`.
+async fn accept(listeners: &[TcpListener]) -> io::Result<(TcpStream, SocketAddr)> {
+    poll_fn(|cx| {
+        listeners
+            .iter()
+            .find_map(|listener| match listener.poll_accept(cx) {
+                Poll::Ready(accepted) => Some(Poll::Ready(accepted)),
+                Poll::Pending => None,
+            })
+            .unwrap_or(Poll::Pending)
+    })
+    .await
+}
+
+/// Returns `None` if `timeout` is elapsed, `None` timeout never elapses.
+async fn within<F: Future>(timeout: Option<Duration>, fut: F) -> Option<F::Output> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, fut).await.ok(),
+        None => Some(fut.await),
+    }
+}
+
+fn close_reason(result: Result<(), impl std::fmt::Display>, closing: CloseReason) -> CloseReason {
+    match result {
+        Ok(()) => closing,
+        Err(err) => CloseReason::Error(err.to_string()),
+    }
+}
+
+/// Completes once a `PING` isn't answered in time, Never if keepalive is disabled.
+async fn keepalive_h2(
+    ping_pong: Option<PingPong>,
+    (interval, timeout): (Option<Duration>, Duration),
+) {
+    let (Some(mut ping_pong), Some(interval)) = (ping_pong, interval) else {
+        return pending().await;
+    };
+    loop {
+        tokio::time::sleep(interval).await;
+        if !matches!(
+            within(Some(timeout), ping_pong.ping(Ping::opaque())).await,
+            Some(Ok(_))
+        ) {
+            return;
+        }
+    }
+}
+
+/// Runs `serve` to completion, Or until `timeout` is elapsed after shutdown is triggered.
+async fn drain(
+    serve: impl Future<Output = CloseReason>,
+    shutdown: Option<Shutdown>,
+    timeout: Duration,
+) -> CloseReason {
+    let deadline = async {
+        shutdown::signal(shutdown).await;
+        tokio::time::sleep(timeout).await
+    };
+    tokio::select! {
+        reason = serve => reason,
+        _ = deadline => CloseReason::DrainTimeout,
+    }
+}
+
+/// `http/1.1` is only advertised, If it's served.
+fn alpn(config: Arc<rustls::ServerConfig>, http1: bool) -> Arc<rustls::ServerConfig> {
+    const HTTP1: &[u8] = b"http/1.1";
+    if http1 || !config.alpn_protocols.iter().any(|p| p == HTTP1) {
+        return config;
+    }
+    let mut config = Arc::unwrap_or_clone(config);
+    config.alpn_protocols.retain(|p| p != HTTP1);
+    Arc::new(config)
+}
+
+fn invalid_data(err: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Verifies client certificates, Signed by one of the PEM encoded `client_ca` certificates.
+fn client_verifier(
+    client_ca: &[u8],
+    auth: ClientAuth,
+) -> io::Result<Arc<dyn rustls::server::danger::ClientCertVerifier>> {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut &*client_ca) {
+        roots.add(cert?).map_err(invalid_data)?;
+    }
+    let mut verifier = rustls::server::WebPkiClientVerifier::builder(Arc::new(roots));
+    if auth == ClientAuth::Optional {
+        verifier = verifier.allow_unauthenticated();
+    }
+    verifier.build().map_err(invalid_data)
+}
+
+/// Verified by [`ClientAuth`], Only the leaf certificate is kept.
+fn peer_identity(stream: &Stream) -> Option<Arc<Identity>> {
+    let Stream::Tls(stream) = stream else {
+        return None;
+    };
+    let cert = stream.get_ref().1.peer_certificates()?.first()?;
+    Some(Arc::new(Identity {
+        certificate: Some(cert.as_ref().into()),
+        ..Default::default()
+    }))
+}
+
+/// Settings of a connection, Applied to every request of it.
+#[derive(Clone)]
+struct Settings {
+    addr: SocketAddr,
+    shutdown: Option<Shutdown>,
+    limits: Limits,
+    rate_limit: Option<Arc<RateLimiter>>,
+    identity: Option<Arc<Identity>>,
+    token_validator: Option<Arc<dyn TokenValidator>>,
+    heartbeat: Option<Duration>,
+}
+
+macro_rules! apply {
+    ($name: ident, $ctx: ty) => {
+        fn $name(&self, ctx: &mut $ctx) {
+            ctx.peer_addr = Some(self.addr);
+            ctx.shutdown = self.shutdown.clone();
+            ctx.limits = self.limits.clone();
+            ctx.rate_limit = self.rate_limit.clone();
+            ctx.identity = self.identity.clone();
+            ctx.token_validator = self.token_validator.clone();
+            ctx.heartbeat = self.heartbeat;
+        }
+    };
+}
+
+impl Settings {
+    apply!(h2, Ctx);
+    apply!(http1, http1::Ctx);
+}
+
+/// CORS and routes, That are handled before the request reaches the application.
+#[derive(Clone)]
+struct Builtin {
+    metrics_path: Option<&'static str>,
+    routes: Arc<[(&'static str, Arc<dyn Route>)]>,
+    cors: Option<Arc<Cors>>,
+}
+
+macro_rules! builtin {
+    ($name: ident, $ctx: ty) => {
+        /// Returns `false` if the request isn't handled.
+        async fn $name(&self, ctx: &mut $ctx) -> bool {
+            if let Some(cors) = &self.cors {
+                let (req, res) = (&ctx.req, &mut ctx.res);
+                if let Some(status) = cors.apply(&req.method, &req.headers, &mut res.headers) {
+                    res.status = status;
+                    return true;
+                }
+            }
+            let path = ctx.req.uri.path();
+            if self.metrics_path == Some(path) {
+                ctx.res.status = ctx.serve_metrics().await;
+            } else if let Some((_, route)) = self.routes.iter().find(|(p, _)| *p == path) {
+                route.$name(ctx).await;
+            } else {
+                return false;
+            }
+            true
+        }
+    };
+}
+
+impl Builtin {
+    builtin!(h2, Ctx);
+    builtin!(http1, http1::Ctx);
+}
+
+type BoxFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// Service and its state, See [`Server::route`].
+trait Route: Send + Sync {
+    fn h2<'a>(&'a self, ctx: &'a mut Ctx) -> BoxFuture<'a>;
+    fn http1<'a>(&'a self, ctx: &'a mut http1::Ctx) -> BoxFuture<'a>;
+}
+
+macro_rules! route {
+    ($name: ident, $ctx: ty) => {
+        fn $name<'a>(&'a self, ctx: &'a mut $ctx) -> BoxFuture<'a> {
+            Box::pin(async {
+                ctx.res.status = ctx.serve(self.0.clone(), self.1.clone()).await;
+            })
+        }
+    };
+}
+
+impl<E, S> Route for (E, S)
+where
+    E: Service<State = S> + Clone + Send + Sync,
+    S: Clone + Send + Sync,
+{
+    route!(h2, Ctx);
+    route!(http1, http1::Ctx);
+}
+
+/// Newly accepted connection, The protocol is negotiated by ALPN or detected by the connection preface.
+pub enum Connection<'a> {
+    H2(&'a mut Conn<Stream>),
+    Http1(&'a mut Stream),
+}
+
 pub trait Application: Clone + Send + 'static {
     fn stream(self, ctx: Ctx) -> impl Future<Output = ()> + Send;
+
+    /// Same as [`Application::stream`], But for HTTP/1.1 requests, See [`Server::http1`].
+    ///
+    /// By default, requests are rejected with `505 HTTP Version Not Supported`.
+    fn stream_http1(self, mut ctx: http1::Ctx) -> impl Future<Output = ()> + Send {
+        ctx.res.status = http1::StatusCode::HTTP_VERSION_NOT_SUPPORTED;
+        async {}
+    }
+
+    /// Called once the handshake is completed, Before any request is served.
+    ///
+    /// Connection is closed with [`CloseReason::Rejected`] if it returns `false`.
+    /// See [`Server::admission`] to reject by IP, Before the TLS handshake.
+    fn on_connect(self, addr: SocketAddr) -> impl Future<Output = bool> + Send {
+        let _ = addr;
+        async { true }
+    }
+
     fn close(self) -> impl Future<Output = ()> + Send {
         async {}
     }
+
+    /// Called once the connection is closed, Calls [`Application::close`] by default.
+    fn on_close(
+        self,
+        reason: CloseReason,
+        stats: ConnectionStats,
+    ) -> impl Future<Output = ()> + Send {
+        let _ = (reason, stats);
+        self.close()
+    }
 }
 
 #[macro_export]
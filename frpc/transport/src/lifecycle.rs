@@ -0,0 +1,98 @@
+use std::{
+    future::pending,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Why a connection was closed, Given to [`Application::on_close`](crate::Application::on_close).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseReason {
+    /// Closed by the client.
+    Client,
+    /// Server is shutting down, In-flight calls were completed.
+    Shutdown,
+    /// In-flight calls weren't completed within drain timeout, After shutdown.
+    DrainTimeout,
+    /// There was no request for idle timeout.
+    Idle,
+    /// HTTP/2 `PING` wasn't answered in time.
+    KeepaliveTimeout,
+    /// Protocol or I/O error.
+    Error(String),
+    /// Rejected by [`Application::on_connect`](crate::Application::on_connect).
+    Rejected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// Number of requests, Including rejected ones.
+    pub requests: u64,
+    /// Time since the connection was accepted.
+    pub duration: Duration,
+}
+
+/// Requests of a connection, Tracked for idle timeout and stats.
+pub(crate) struct Activity {
+    opened: Instant,
+    requests: AtomicU64,
+    active: AtomicUsize,
+    /// Milliseconds since `opened`, When the last request was completed.
+    idle_since: AtomicU64,
+}
+
+impl Activity {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            opened: Instant::now(),
+            requests: AtomicU64::new(0),
+            active: AtomicUsize::new(0),
+            idle_since: AtomicU64::new(0),
+        })
+    }
+
+    /// Returned guard must be held until the request is completed.
+    pub(crate) fn request(self: &Arc<Self>) -> Request {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.active.fetch_add(1, Ordering::Relaxed);
+        Request(self.clone())
+    }
+
+    pub(crate) fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            duration: self.opened.elapsed(),
+        }
+    }
+
+    /// Completes once there is no request for `timeout`, Never without a `timeout`.
+    pub(crate) async fn idle(&self, timeout: Option<Duration>) {
+        let Some(timeout) = timeout else {
+            return pending().await;
+        };
+        loop {
+            let idle_since = Duration::from_millis(self.idle_since.load(Ordering::Relaxed));
+            let deadline = self.opened + idle_since + timeout;
+            let now = Instant::now();
+            let wait = match self.active.load(Ordering::Relaxed) {
+                0 if now >= deadline => return,
+                0 => deadline - now,
+                _ => timeout,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+pub(crate) struct Request(Arc<Activity>);
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        let activity = &self.0;
+        let now = activity.opened.elapsed().as_millis() as u64;
+        activity.idle_since.store(now, Ordering::Relaxed);
+        activity.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
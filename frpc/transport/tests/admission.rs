@@ -0,0 +1,123 @@
+use frpc_transport::admission::{Admission, IpNet};
+use std::{net::IpAddr, sync::Arc};
+
+fn net(s: &str) -> IpNet {
+    s.parse().unwrap()
+}
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn ipv4() {
+    let private = net("10.0.0.0/8");
+    assert_eq!(private, IpNet::new(ip("10.0.0.0"), 8));
+    assert!(private.contains(ip("10.0.0.0")));
+    assert!(private.contains(ip("10.255.255.255")));
+    assert!(!private.contains(ip("11.0.0.0")));
+    assert!(!private.contains(ip("::a00:1")));
+
+    let host = net("192.168.1.1");
+    assert_eq!(host.prefix, 32);
+    assert!(host.contains(ip("192.168.1.1")));
+    assert!(!host.contains(ip("192.168.1.2")));
+
+    let any = net("0.0.0.0/0");
+    assert!(any.contains(ip("255.255.255.255")));
+    assert!(!any.contains(ip("::1")));
+}
+
+#[test]
+fn ipv6() {
+    let doc = net("2001:db8::/32");
+    assert!(doc.contains(ip("2001:db8::1")));
+    assert!(doc.contains(ip("2001:db8:ffff::")));
+    assert!(!doc.contains(ip("2001:db9::")));
+    assert!(!doc.contains(ip("10.0.0.1")));
+
+    let loopback = net("::1");
+    assert_eq!(loopback.prefix, 128);
+    assert!(loopback.contains(ip("::1")));
+    assert!(!loopback.contains(ip("::2")));
+    assert!(!loopback.contains(ip("127.0.0.1")));
+
+    assert!(net("::/0").contains(ip("ffff::")));
+}
+
+#[test]
+fn ipv4_mapped() {
+    // Mapped address of a client, Matches IPv4 network.
+    assert!(net("10.0.0.0/8").contains(ip("::ffff:10.1.2.3")));
+    assert!(!net("10.0.0.0/8").contains(ip("::ffff:11.1.2.3")));
+
+    let mapped = net("::ffff:10.0.0.0/104");
+    assert_eq!(mapped, net("10.0.0.0/8"));
+    assert!(mapped.contains(ip("10.1.2.3")));
+    assert!(mapped.contains(ip("::ffff:10.1.2.3")));
+    assert_eq!(IpNet::from(ip("::ffff:10.1.2.3")), net("10.1.2.3"));
+}
+
+#[test]
+fn invalid() {
+    for s in [
+        "10.0.0.0/33",
+        "::/129",
+        "10.0.0.0/x",
+        "10.0.0.0/",
+        "10.0.0.0/-1",
+        "10.0.0/8",
+        "",
+    ] {
+        let err = s.parse::<IpNet>().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{s}");
+    }
+    // Only `FromStr` rejects it, `new` clamps.
+    assert_eq!(IpNet::new(ip("10.0.0.0"), 40).prefix, 32);
+}
+
+#[test]
+fn deny_takes_precedence() {
+    let admission = Arc::new(
+        Admission::new()
+            .allow(net("10.0.0.0/8"))
+            .deny(net("10.1.0.0/16")),
+    );
+    assert!(admission.admit(ip("10.0.0.1")).is_some());
+    assert!(admission.admit(ip("::ffff:10.0.0.1")).is_some());
+    assert!(admission.admit(ip("10.1.0.1")).is_none());
+    assert!(admission.admit(ip("::ffff:10.1.0.1")).is_none());
+    assert!(admission.admit(ip("11.0.0.1")).is_none());
+
+    // Everyone is allowed, Without an allow list.
+    let admission = Arc::new(Admission::new().deny(ip("::1")));
+    assert!(admission.admit(ip("::1")).is_none());
+    assert!(admission.admit(ip("127.0.0.1")).is_some());
+}
+
+#[test]
+fn max_per_ip() {
+    let admission = Arc::new(Admission::new().max_per_ip(2));
+    let a = ip("10.0.0.1");
+    let first = admission.admit(a).unwrap();
+    let _second = admission.admit(a).unwrap();
+    assert!(admission.admit(a).is_none());
+    // Mapped address is the same client.
+    assert!(admission.admit(ip("::ffff:10.0.0.1")).is_none());
+
+    // Other IPs are counted separately.
+    let _other = admission.admit(ip("10.0.0.2")).unwrap();
+
+    drop(first);
+    let _third = admission.admit(a).unwrap();
+    assert!(admission.admit(a).is_none());
+}
+
+#[test]
+fn max_per_ipv6_network() {
+    let admission = Arc::new(Admission::new().max_per_ip(1));
+    let _first = admission.admit(ip("2001:db8::1")).unwrap();
+    // Same `/64` network.
+    assert!(admission.admit(ip("2001:db8::2")).is_none());
+    let _other = admission.admit(ip("2001:db8:0:1::1")).unwrap();
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::*;
+use frpc_transport::frpc_transport_http::{auth::UNAUTHENTICATED, limit::STATUS_HEADER};
+use frpc_transport::*;
+use frpc_transport_core::{Service, Transport};
+use std::{future::Future, net::SocketAddr};
+
+/// Rpc `1` takes the authenticated caller, Others are anonymous.
+struct Private;
+
+impl Service for Private {
+    type State = ();
+
+    fn execute<'fut, TR>(
+        _: Self::State,
+        _: u16,
+        _: &'fut mut &[u8],
+        transport: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        Some(transport.unary_sync(|w| w.write_all(&[1])))
+    }
+
+    fn requires_identity(id: u16) -> bool {
+        id == 1
+    }
+}
+
+#[derive(Clone)]
+struct PrivateApp;
+
+impl Application for PrivateApp {
+    async fn stream(self, mut ctx: Ctx) {
+        ctx.res.status = ctx.serve(Private, ()).await;
+    }
+}
+
+/// Status and `frpc-status` header of the response.
+async fn call(addr: SocketAddr, id: u16) -> (u16, Option<String>) {
+    let res = request(&connect(addr).await, &id.to_le_bytes())
+        .await
+        .unwrap();
+    let status = res.headers().get(STATUS_HEADER);
+    let status = status.map(|v| v.to_str().unwrap().to_owned());
+    (res.status().as_u16(), status)
+}
+
+#[tokio::test]
+async fn anonymous_call_is_rejected() {
+    let addr = spawn(builder().build().unwrap(), PrivateApp);
+    assert_eq!(call(addr, 0).await, (200, None));
+    assert_eq!(call(addr, 1).await, (401, Some(UNAUTHENTICATED.to_owned())));
+}
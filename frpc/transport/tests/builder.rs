@@ -0,0 +1,364 @@
+mod common;
+
+use common::*;
+use frpc_transport::frpc_transport_http::tokio_tls_listener::{
+    rustls::{self, pki_types::ServerName},
+    tokio_rustls::TlsConnector,
+};
+use frpc_transport::*;
+use std::{
+    future::poll_fn,
+    io::ErrorKind,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::net::TcpStream;
+
+const A_KEY: &[u8] = include_bytes!("fixtures/a.key.pem");
+const A_CERT: &[u8] = include_bytes!("fixtures/a.cert.pem");
+const B_KEY: &[u8] = include_bytes!("fixtures/b.key.pem");
+const B_CERT: &[u8] = include_bytes!("fixtures/b.cert.pem");
+
+fn addrs(server: &Server) -> Vec<SocketAddr> {
+    let addrs = server.listeners.iter().map(|l| l.local_addr().unwrap());
+    addrs.collect()
+}
+
+/// Response of [`Slow`], To `[42]`.
+async fn echo(client: &Client) -> Vec<u8> {
+    let res = request(client, &[1, 0, 42]).await.unwrap();
+    assert_eq!(res.status(), 200);
+    read_body(res).await
+}
+
+#[tokio::test]
+async fn multiple_listeners() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let server = builder()
+        .bind("127.0.0.1:0".parse().unwrap())
+        .listener(listener)
+        .build()
+        .unwrap();
+    let addrs = addrs(&server);
+    assert_eq!(addrs.len(), 3);
+    tokio::spawn(server.serve(|_, _| async { App }));
+
+    for addr in addrs {
+        assert_eq!(echo(&connect(addr).await).await, [42]);
+    }
+}
+
+#[tokio::test]
+async fn without_listeners() {
+    let server = Server::builder().build().unwrap();
+    let err = server.serve(|_, _| async { App }).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn h2_config() {
+    let server = builder()
+        .h2(H2Config {
+            max_concurrent_streams: Some(1),
+            initial_window_size: Some(1000),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let addr = spawn(server, App);
+    let client = connect(addr).await;
+    // Settings of the server are received.
+    echo(&client).await;
+
+    // Second call waits for the first one.
+    let start = Instant::now();
+    tokio::join!(echo(&client), echo(&client));
+    assert!(start.elapsed() >= Duration::from_millis(550));
+
+    let mut client = client.ready().await.unwrap();
+    let req = http::Request::post("http://localhost/")
+        .header("content-length", "3")
+        .body(())
+        .unwrap();
+    let (_res, mut body) = client.send_request(req, false).unwrap();
+    body.reserve_capacity(64 * 1024);
+    let capacity = poll_fn(|cx| body.poll_capacity(cx)).await.unwrap().unwrap();
+    assert_eq!(capacity, 1000);
+}
+
+/// Every request is rejected, Unless it's routed.
+#[derive(Clone)]
+struct NotFound;
+
+impl Application for NotFound {
+    async fn stream(self, mut ctx: Ctx) {
+        ctx.res.status = http::StatusCode::NOT_FOUND;
+    }
+}
+
+#[tokio::test]
+async fn route() {
+    let server = builder().build().unwrap().route("/", Slow, ());
+    let addr = spawn(server, NotFound);
+    let mut client = connect(addr).await;
+    assert_eq!(echo(&client).await, [42]);
+
+    client = client.ready().await.unwrap();
+    let req = http::Request::post("http://localhost/other")
+        .body(())
+        .unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+    assert_eq!(res.await.unwrap().status(), 404);
+}
+
+/// Handshake succeeds, If the server presents the certificate of `cert`.
+async fn presents(addr: SocketAddr, cert: &[u8]) -> bool {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut &*cert) {
+        roots.add(cert.unwrap()).unwrap();
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let connector = TlsConnector::from(Arc::new(config));
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let name = ServerName::try_from("localhost").unwrap();
+    connector.connect(name, stream).await.is_ok()
+}
+
+/// Polls until the handshake with `cert` succeeds.
+async fn eventually_presents(addr: SocketAddr, cert: &[u8]) {
+    let wait = async {
+        while !presents(addr, cert).await {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), wait)
+        .await
+        .expect("certificate isn't reloaded");
+}
+
+#[tokio::test]
+async fn set_pem() {
+    let server = builder().tls_pem(A_KEY, A_CERT).build().unwrap();
+    let cert = server.cert.clone().unwrap();
+    let addr = spawn(server, App);
+    assert!(presents(addr, A_CERT).await);
+    assert!(!presents(addr, B_CERT).await);
+
+    cert.set_pem(B_KEY, B_CERT).unwrap();
+    assert!(presents(addr, B_CERT).await);
+    assert!(!presents(addr, A_CERT).await);
+
+    // Old certificate is kept.
+    assert!(cert.set_pem(b"invalid", b"invalid").is_err());
+    assert!(cert.set_pem(A_KEY, B_CERT).is_err());
+    assert!(presents(addr, B_CERT).await);
+    // Reloading isn't possible, Without files.
+    cert.reload().unwrap();
+    assert!(presents(addr, B_CERT).await);
+}
+
+/// Offers `protocols`, And presents `cert` if the server asks for one.
+///
+/// Certificate of the server isn't verified.
+fn connector(protocols: &[&[u8]], cert: Option<(&[u8], &[u8])>) -> TlsConnector {
+    let builder =
+        rustls::ClientConfig::builder().with_root_certificates(rustls::RootCertStore::empty());
+    let mut config = match cert {
+        Some((key, cert)) => {
+            let certs = rustls_pemfile::certs(&mut &*cert).collect::<Result<_, _>>();
+            let key = rustls_pemfile::private_key(&mut &*key).unwrap().unwrap();
+            let certs = certs.unwrap();
+            builder.with_client_auth_cert(certs, key).unwrap()
+        }
+        None => builder.with_no_client_auth(),
+    };
+    config.alpn_protocols = protocols.iter().map(|p| p.to_vec()).collect();
+    config
+        .dangerous()
+        .set_certificate_verifier(Arc::new(SkipVerify));
+    TlsConnector::from(Arc::new(config))
+}
+
+/// ALPN protocol negotiated by a client, That offers `protocols`.
+async fn negotiate(addr: SocketAddr, protocols: &[&[u8]]) -> Option<Vec<u8>> {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let name = ServerName::try_from("localhost").unwrap();
+    let stream = connector(protocols, None)
+        .connect(name, stream)
+        .await
+        .ok()?;
+    stream.get_ref().1.alpn_protocol().map(<[u8]>::to_vec)
+}
+
+#[tokio::test]
+async fn http1_is_advertised_once_enabled() {
+    let tls = || builder().tls_pem(A_KEY, A_CERT).build().unwrap();
+    let server = tls();
+    let addr = spawn(server, App);
+    assert_eq!(negotiate(addr, &[b"http/1.1"]).await, None);
+    assert_eq!(
+        negotiate(addr, &[b"http/1.1", b"h2"]).await,
+        Some(b"h2".to_vec())
+    );
+
+    let server = tls().http1();
+    let addr = spawn(server, App);
+    assert_eq!(
+        negotiate(addr, &[b"http/1.1"]).await,
+        Some(b"http/1.1".to_vec())
+    );
+}
+
+/// Responds with `200 OK`, If the client is authenticated by its certificate.
+#[derive(Clone)]
+struct Certified;
+
+impl Application for Certified {
+    async fn stream(self, mut ctx: Ctx) {
+        let identity = ctx.identity.as_ref();
+        ctx.res.status = match identity.is_some_and(|id| id.certificate.is_some()) {
+            true => http::StatusCode::OK,
+            false => http::StatusCode::FORBIDDEN,
+        };
+    }
+}
+
+/// Status of a request over TLS, `None` if the connection is rejected.
+async fn request_with(addr: SocketAddr, cert: Option<(&[u8], &[u8])>) -> Option<u16> {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let name = ServerName::try_from("localhost").unwrap();
+    let stream = connector(&[b"h2"], cert).connect(name, stream).await.ok()?;
+    let (client, conn) = h2::client::handshake(stream).await.ok()?;
+    tokio::spawn(conn);
+    let mut client = client.ready().await.ok()?;
+    let req = http::Request::get("https://localhost/").body(()).unwrap();
+    let (res, _) = client.send_request(req, true).ok()?;
+    Some(res.await.ok()?.status().as_u16())
+}
+
+#[tokio::test]
+async fn client_auth() {
+    let server = |auth| {
+        let server = builder().tls_pem(A_KEY, A_CERT).client_auth(A_CERT, auth);
+        spawn(server.build().unwrap(), Certified)
+    };
+    let addr = server(ClientAuth::Required);
+    assert_eq!(request_with(addr, Some((A_KEY, A_CERT))).await, Some(200));
+    assert_eq!(request_with(addr, None).await, None);
+    // Not signed by the trusted CA.
+    assert_eq!(request_with(addr, Some((B_KEY, B_CERT))).await, None);
+
+    let addr = server(ClientAuth::Optional);
+    assert_eq!(request_with(addr, Some((A_KEY, A_CERT))).await, Some(200));
+    assert_eq!(request_with(addr, None).await, Some(403));
+}
+
+#[test]
+fn invalid_tls_options() {
+    let err = |builder: ServerBuilder| builder.build().err().unwrap().kind();
+    let reload = builder().reload_every(Duration::from_secs(1));
+    assert_eq!(err(reload.tls_pem(A_KEY, A_CERT)), ErrorKind::InvalidInput);
+    let reload = builder().reload_every(Duration::from_secs(1));
+    assert_eq!(err(reload), ErrorKind::InvalidInput);
+
+    let client_auth = builder().client_auth(A_CERT, ClientAuth::Required);
+    assert_eq!(err(client_auth), ErrorKind::InvalidInput);
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(ReloadableCert::from_pem(A_KEY, A_CERT).unwrap()));
+    let client_auth = builder()
+        .tls_config(config)
+        .client_auth(A_CERT, ClientAuth::Required);
+    assert_eq!(err(client_auth), ErrorKind::InvalidInput);
+}
+
+#[derive(Debug)]
+struct SkipVerify;
+
+impl rustls::client::danger::ServerCertVerifier for SkipVerify {
+    fn verify_server_cert(
+        &self,
+        _: &rustls::pki_types::CertificateDer,
+        _: &[rustls::pki_types::CertificateDer],
+        _: &ServerName,
+        _: &[u8],
+        _: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _: &[u8],
+        _: &rustls::pki_types::CertificateDer,
+        _: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _: &[u8],
+        _: &rustls::pki_types::CertificateDer,
+        _: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        rustls::crypto::ring::default_provider()
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("frpc-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[tokio::test]
+async fn reload_files() {
+    let dir = TempDir::new("reload");
+    let (key, cert) = (dir.0.join("key.pem"), dir.0.join("cert.pem"));
+    std::fs::write(&key, A_KEY).unwrap();
+    std::fs::write(&cert, A_CERT).unwrap();
+
+    let server = builder()
+        .tls_files(&key, &cert)
+        .reload_every(Duration::from_millis(20))
+        .build()
+        .unwrap();
+    let addr = spawn(server, App);
+    assert!(presents(addr, A_CERT).await);
+
+    std::fs::write(&key, B_KEY).unwrap();
+    std::fs::write(&cert, B_CERT).unwrap();
+    eventually_presents(addr, B_CERT).await;
+    assert!(!presents(addr, A_CERT).await);
+
+    // Failed reload keeps the old certificate.
+    std::fs::write(&cert, b"partially written").unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(presents(addr, B_CERT).await);
+
+    // Retried, Until the files are valid.
+    std::fs::write(&key, A_KEY).unwrap();
+    std::fs::write(&cert, A_CERT).unwrap();
+    eventually_presents(addr, A_CERT).await;
+}
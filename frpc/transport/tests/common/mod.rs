@@ -0,0 +1,98 @@
+//! Shared by the integration tests, Not every test uses every item.
+#![allow(dead_code)]
+use bytes::Bytes;
+use frpc_transport::*;
+use frpc_transport_core::{Service, Transport};
+use std::{future::Future, net::SocketAddr, time::Duration};
+use tokio::net::TcpStream;
+
+/// Responds with its input, After a while.
+#[derive(Clone)]
+pub struct Slow;
+
+impl Slow {
+    pub const DELAY: Duration = Duration::from_millis(300);
+}
+
+impl Service for Slow {
+    type State = ();
+
+    fn execute<'fut, TR>(
+        _: Self::State,
+        _: u16,
+        cursor: &'fut mut &[u8],
+        transport: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        Some(async move {
+            tokio::time::sleep(Slow::DELAY).await;
+            let data = cursor.to_vec();
+            transport.unary_sync(move |w| w.write_all(&data)).await
+        })
+    }
+}
+
+/// Every request is served by [`Slow`].
+#[derive(Clone)]
+pub struct App;
+
+impl Application for App {
+    async fn stream(self, mut ctx: Ctx) {
+        ctx.res.status = ctx.serve(Slow, ()).await;
+    }
+}
+
+/// Bound to a random port of localhost.
+pub fn builder() -> ServerBuilder {
+    Server::builder().bind("127.0.0.1:0".parse().unwrap())
+}
+
+/// Every connection is served by a clone of `app`, Returns the address of the first listener.
+pub fn spawn(server: Server, app: impl Application + Sync) -> SocketAddr {
+    let addr = server.listeners[0].local_addr().unwrap();
+    tokio::spawn(server.serve(move |_, _| {
+        let app = app.clone();
+        async move { app }
+    }));
+    addr
+}
+
+pub type Client = h2::client::SendRequest<Bytes>;
+
+/// HTTP/2 connection with prior knowledge.
+pub async fn connect(addr: SocketAddr) -> Client {
+    let (client, conn) = h2::client::handshake(TcpStream::connect(addr).await.unwrap())
+        .await
+        .unwrap();
+    tokio::spawn(conn);
+    client
+}
+
+/// Sends `body` to `/`, `None` if the request fails.
+pub async fn request(client: &Client, body: &[u8]) -> Option<http::Response<h2::RecvStream>> {
+    let mut client = client.clone().ready().await.ok()?;
+    let req = http::Request::post("http://localhost/")
+        .header("content-length", body.len())
+        .body(())
+        .unwrap();
+    let (res, mut send) = client.send_request(req, false).ok()?;
+    send.send_data(Bytes::copy_from_slice(body), true).ok()?;
+    res.await.ok()
+}
+
+/// Calls rpc `1` with `[42]`, Returns the status.
+pub async fn call(client: &Client) -> Option<u16> {
+    let res = request(client, &[1, 0, 42]).await?;
+    Some(res.status().as_u16())
+}
+
+pub async fn read_body(res: http::Response<h2::RecvStream>) -> Vec<u8> {
+    let mut body = res.into_body();
+    let mut data = Vec::new();
+    while let Some(bytes) = body.data().await {
+        data.extend_from_slice(&bytes.unwrap());
+    }
+    data
+}
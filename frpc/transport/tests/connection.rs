@@ -0,0 +1,107 @@
+use frpc_transport::*;
+use std::{net::SocketAddr, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+#[derive(Clone)]
+struct App;
+
+impl Application for App {
+    async fn stream(self, _: Ctx) {}
+}
+
+/// Never responds to HTTP/1.1 requests.
+#[derive(Clone)]
+struct Http1;
+
+impl Application for Http1 {
+    async fn stream(self, _: Ctx) {}
+    async fn stream_http1(self, _: http1::Ctx) {}
+}
+
+fn spawn(server: ServerBuilder) -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = server.listener(listener).build().unwrap();
+    tokio::spawn(server.serve(|_, _| async { App }));
+    addr
+}
+
+async fn get(addr: SocketAddr) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).await.unwrap();
+    res
+}
+
+#[tokio::test]
+async fn quiet_client_does_not_block_accept() {
+    let addr = spawn(Server::builder().read_timeout(Some(Duration::from_secs(60))));
+    // Connected, But never sends the preface.
+    let _quiet = TcpStream::connect(addr).await.unwrap();
+    let res = tokio::time::timeout(Duration::from_secs(5), get(addr))
+        .await
+        .expect("accept loop is blocked");
+    assert!(res.starts_with("HTTP/1.1 505"), "{res}");
+}
+
+#[tokio::test]
+async fn quiet_client_is_closed_after_read_timeout() {
+    let addr = spawn(Server::builder().read_timeout(Some(Duration::from_millis(100))));
+    let mut quiet = TcpStream::connect(addr).await.unwrap();
+    let mut buf = [0; 1];
+    let read = tokio::time::timeout(Duration::from_secs(5), quiet.read(&mut buf)).await;
+    assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))));
+}
+
+#[tokio::test]
+async fn request_split_within_preface_is_replayed() {
+    let addr = spawn(Server::builder());
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    // `P` matches the preface, Protocol is only known after `U`.
+    stream.write_all(b"P").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    stream
+        .write_all(b"UT / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).await.unwrap();
+    assert!(res.starts_with("HTTP/1.1 505"), "{res}");
+}
+
+#[tokio::test]
+async fn h2c_with_prior_knowledge() {
+    let addr = spawn(Server::builder());
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(stream).await.unwrap();
+    tokio::spawn(conn);
+    let req = http::Request::get("http://localhost/").body(()).unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+    let res = tokio::time::timeout(Duration::from_secs(5), res).await;
+    assert!(matches!(res, Ok(Ok(_))));
+}
+
+#[tokio::test]
+async fn http1_is_opt_in() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::builder().listener(listener).build().unwrap();
+    tokio::spawn(server.serve(|_, _| async { Http1 }));
+    let res = get(addr).await;
+    assert!(res.starts_with("HTTP/1.1 505"), "{res}");
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::builder().listener(listener).build().unwrap();
+    tokio::spawn(server.http1().serve(|_, _| async { Http1 }));
+    // Handler hasn't set the status.
+    let res = get(addr).await;
+    assert!(res.starts_with("HTTP/1.1 500"), "{res}");
+}
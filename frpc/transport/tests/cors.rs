@@ -0,0 +1,141 @@
+use frpc_transport::Cors;
+use http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use std::time::Duration;
+
+fn request(origin: Option<&'static str>, preflight: bool) -> (Method, HeaderMap) {
+    let mut headers = HeaderMap::new();
+    if let Some(origin) = origin {
+        headers.insert(header::ORIGIN, HeaderValue::from_static(origin));
+    }
+    if !preflight {
+        return (Method::POST, headers);
+    }
+    headers.insert(
+        header::ACCESS_CONTROL_REQUEST_METHOD,
+        HeaderValue::from_static("POST"),
+    );
+    (Method::OPTIONS, headers)
+}
+
+fn apply(
+    cors: &Cors,
+    origin: Option<&'static str>,
+    preflight: bool,
+) -> (Option<StatusCode>, HeaderMap) {
+    let (method, req) = request(origin, preflight);
+    let mut res = HeaderMap::new();
+    let status = cors.apply(&method, &req, &mut res);
+    (status, res)
+}
+
+fn get(res: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    res.get(name).map(|v| v.to_str().unwrap())
+}
+
+#[test]
+fn preflight() {
+    let cors = Cors::default().max_age(Duration::from_secs(600));
+    let (status, res) = apply(&cors, Some("https://a.com"), true);
+    assert_eq!(status, Some(StatusCode::NO_CONTENT));
+    assert_eq!(get(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN), Some("*"));
+    assert_eq!(
+        get(&res, header::ACCESS_CONTROL_ALLOW_METHODS),
+        Some("POST")
+    );
+    assert_eq!(
+        get(&res, header::ACCESS_CONTROL_ALLOW_HEADERS),
+        Some(
+            "content-type, content-encoding, authorization, \
+             frpc-accept-encoding, traceparent, frpc-resume-cursor"
+        )
+    );
+    assert_eq!(get(&res, header::ACCESS_CONTROL_MAX_AGE), Some("600"));
+    assert_eq!(get(&res, header::VARY), Some("origin"));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_EXPOSE_HEADERS));
+}
+
+#[test]
+fn actual_request() {
+    let cors = Cors::default();
+    let (status, res) = apply(&cors, Some("https://a.com"), false);
+    assert_eq!(status, None);
+    assert_eq!(get(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN), Some("*"));
+    assert_eq!(
+        get(&res, header::ACCESS_CONTROL_EXPOSE_HEADERS),
+        Some("frpc-status, retry-after, frpc-frame-encoding, traceparent")
+    );
+    assert_eq!(get(&res, header::VARY), Some("origin"));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_METHODS));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_HEADERS));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_MAX_AGE));
+}
+
+#[test]
+fn vary_without_origin() {
+    let (status, res) = apply(&Cors::default(), None, false);
+    assert_eq!(status, None);
+    assert_eq!(get(&res, header::VARY), Some("origin"));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[test]
+fn allowed_origins() {
+    let cors = Cors::default().origins(["https://a.com"]);
+
+    let (status, res) = apply(&cors, Some("https://a.com"), true);
+    assert_eq!(status, Some(StatusCode::NO_CONTENT));
+    assert_eq!(
+        get(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        Some("https://a.com")
+    );
+
+    let (status, res) = apply(&cors, Some("https://b.com"), true);
+    assert_eq!(status, Some(StatusCode::FORBIDDEN));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    assert_eq!(get(&res, header::VARY), Some("origin"));
+
+    let (status, res) = apply(&cors, Some("https://b.com"), false);
+    assert_eq!(status, None);
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[test]
+fn credentials() {
+    let cors = Cors::default().credentials(["https://a.com"]);
+    assert_eq!(
+        cors.origins.as_deref(),
+        Some(&["https://a.com".to_string()][..])
+    );
+
+    for preflight in [true, false] {
+        let (_, res) = apply(&cors, Some("https://a.com"), preflight);
+        assert_eq!(
+            get(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            Some("https://a.com")
+        );
+        assert_eq!(
+            get(&res, header::ACCESS_CONTROL_ALLOW_CREDENTIALS),
+            Some("true")
+        );
+    }
+    let (status, res) = apply(&cors, Some("https://b.com"), true);
+    assert_eq!(status, Some(StatusCode::FORBIDDEN));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+}
+
+#[test]
+fn credentials_without_origins() {
+    // Set directly, Bypassing `Cors::credentials`.
+    let cors = Cors {
+        credentials: true,
+        ..Cors::default()
+    };
+    let (status, res) = apply(&cors, Some("https://a.com"), true);
+    assert_eq!(status, Some(StatusCode::FORBIDDEN));
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+    let (status, res) = apply(&cors, Some("https://a.com"), false);
+    assert_eq!(status, None);
+    assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
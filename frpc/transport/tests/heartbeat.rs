@@ -0,0 +1,99 @@
+mod common;
+
+use common::*;
+use frpc_transport::*;
+use frpc_transport_core::{Service, Transport};
+use std::{
+    future::Future,
+    net::SocketAddr,
+    task::{ready, Poll},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+const WAIT: Duration = Duration::from_millis(350);
+
+/// Streams `[1]` and `[2]`, After a while each.
+struct Quiet;
+
+impl Service for Quiet {
+    type State = ();
+
+    fn execute<'fut, TR>(
+        _: Self::State,
+        _: u16,
+        _: &'fut mut &[u8],
+        transport: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        Some(async move {
+            let mut sleep = Box::pin(tokio::time::sleep(WAIT));
+            let mut item = 0;
+            transport
+                .server_stream(move |cx, w| {
+                    ready!(sleep.as_mut().poll(cx));
+                    sleep.as_mut().reset(Instant::now() + WAIT);
+                    item += 1;
+                    Poll::Ready(w.write_all(&[item]).map(|_| item == 2))
+                })
+                .await
+        })
+    }
+}
+
+#[derive(Clone)]
+struct QuietApp;
+
+impl Application for QuietApp {
+    async fn stream(self, mut ctx: Ctx) {
+        ctx.res.status = ctx.serve(Quiet, ()).await;
+    }
+}
+
+/// `(flags, payload)` of every frame of the response.
+async fn frames(addr: SocketAddr) -> Vec<(u8, Vec<u8>)> {
+    let res = request(&connect(addr).await, &[1, 0]).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let data = read_body(res).await;
+
+    let mut frames = Vec::new();
+    let mut rest = &data[..];
+    while !rest.is_empty() {
+        let header = u32::from_le_bytes(rest[..4].try_into().unwrap());
+        let len = (header & 0x1FFF_FFFF) as usize;
+        frames.push((rest[3] & 0xE0, rest[4..4 + len].to_vec()));
+        rest = &rest[4 + len..];
+    }
+    frames
+}
+
+#[tokio::test]
+async fn sent_while_quiet() {
+    let server = builder()
+        .build()
+        .unwrap()
+        .heartbeat(Duration::from_millis(100));
+    let frames = frames(spawn(server, QuietApp)).await;
+
+    let items: Vec<_> = frames.iter().filter(|(_, data)| !data.is_empty()).collect();
+    assert_eq!(items, [&(0, vec![1]), &(0x80, vec![2])]);
+
+    // Heartbeat is a zero-length frame without any flag.
+    let first = frames.iter().position(|frame| frame.1 == [1]).unwrap();
+    assert!(first >= 2, "{frames:?}");
+    assert!(frames[..first].iter().all(|frame| *frame == (0, vec![])));
+    let between = &frames[first + 1..frames.len() - 1];
+    assert!(between.len() >= 2, "{frames:?}");
+    assert!(between.iter().all(|frame| *frame == (0, vec![])));
+
+    // Nothing is sent after `FIN`.
+    assert_eq!(frames.last(), Some(&(0x80, vec![2])));
+}
+
+#[tokio::test]
+async fn disabled_by_default() {
+    let frames = frames(spawn(builder().build().unwrap(), QuietApp)).await;
+    assert_eq!(frames, [(0, vec![1]), (0x80, vec![2])]);
+}
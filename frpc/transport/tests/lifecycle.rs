@@ -0,0 +1,160 @@
+mod common;
+
+use common::*;
+use frpc_transport::*;
+use std::{net::SocketAddr, time::Duration};
+use tokio::{
+    io::AsyncWriteExt,
+    net::TcpStream,
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+};
+
+type Closed = (CloseReason, ConnectionStats);
+
+/// Reports why the connection was closed.
+#[derive(Clone)]
+struct Report(UnboundedSender<Closed>);
+
+impl Application for Report {
+    async fn stream(self, mut ctx: Ctx) {
+        ctx.res.status = ctx.serve(Slow, ()).await;
+    }
+
+    async fn on_close(self, reason: CloseReason, stats: ConnectionStats) {
+        let _ = self.0.send((reason, stats));
+    }
+}
+
+fn spawn_reporting(server: Server) -> (SocketAddr, UnboundedReceiver<Closed>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (spawn(server, Report(tx)), rx)
+}
+
+async fn closed(rx: &mut UnboundedReceiver<Closed>) -> CloseReason {
+    let closed = tokio::time::timeout(Duration::from_secs(5), rx.recv());
+    closed.await.expect("connection isn't closed").unwrap().0
+}
+
+/// HTTP/2 connection preface, Followed by empty `SETTINGS` frame.
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\0\0\0\x04\0\0\0\0\0";
+
+#[tokio::test]
+async fn client() {
+    let (addr, mut rx) = spawn_reporting(builder().build().unwrap());
+    let (client, conn) = h2::client::handshake(TcpStream::connect(addr).await.unwrap())
+        .await
+        .unwrap();
+    let conn = tokio::spawn(conn);
+    assert_eq!(call(&client).await, Some(200));
+    assert_eq!(call(&client).await, Some(200));
+    // Connection is closed, Once every handle is dropped.
+    drop(client);
+    conn.await.unwrap().unwrap();
+
+    let (reason, stats) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reason, CloseReason::Client);
+    assert_eq!(stats.requests, 2);
+    assert!(stats.duration >= Duration::from_millis(600));
+}
+
+#[tokio::test]
+async fn idle() {
+    let server = builder()
+        .idle_timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+    let (addr, mut rx) = spawn_reporting(server);
+    let client = connect(addr).await;
+    // In-flight call isn't idle, Even though it's longer than the timeout.
+    assert_eq!(call(&client).await, Some(200));
+    assert!(rx.try_recv().is_err());
+    assert_eq!(closed(&mut rx).await, CloseReason::Idle);
+}
+
+#[tokio::test]
+async fn shutdown() {
+    let signal = Shutdown::new();
+    let server = builder().build().unwrap().shutdown(signal.clone());
+    let (addr, mut rx) = spawn_reporting(server);
+    let client = connect(addr).await;
+    assert_eq!(call(&client).await, Some(200));
+    signal.trigger();
+    assert_eq!(closed(&mut rx).await, CloseReason::Shutdown);
+}
+
+#[tokio::test]
+async fn drain_timeout() {
+    let signal = Shutdown::new();
+    let server = builder()
+        .build()
+        .unwrap()
+        .shutdown(signal.clone())
+        .drain_timeout(Duration::from_millis(50));
+    let (addr, mut rx) = spawn_reporting(server);
+    let client = connect(addr).await;
+    let (status, reason) = tokio::join!(call(&client), async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        signal.trigger();
+        closed(&mut rx).await
+    });
+    assert_eq!(reason, CloseReason::DrainTimeout);
+    assert_ne!(status, Some(200));
+}
+
+#[tokio::test]
+async fn keepalive_timeout() {
+    let server = builder()
+        .h2(H2Config {
+            keepalive_interval: Some(Duration::from_millis(100)),
+            keepalive_timeout: Duration::from_millis(100),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let (addr, mut rx) = spawn_reporting(server);
+    // `PING` isn't answered, As nothing is read.
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(PREFACE).await.unwrap();
+    assert_eq!(closed(&mut rx).await, CloseReason::KeepaliveTimeout);
+}
+
+#[tokio::test]
+async fn error() {
+    let (addr, mut rx) = spawn_reporting(builder().build().unwrap());
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(PREFACE).await.unwrap();
+    // `DATA` frame on the connection stream is a protocol error.
+    stream.write_all(b"\0\0\0\0\0\0\0\0\0").await.unwrap();
+    assert!(matches!(closed(&mut rx).await, CloseReason::Error(_)));
+}
+
+/// Rejects every connection.
+#[derive(Clone)]
+struct Reject(UnboundedSender<Closed>);
+
+impl Application for Reject {
+    async fn stream(self, _: Ctx) {
+        unreachable!("connection is rejected")
+    }
+
+    async fn on_connect(self, addr: SocketAddr) -> bool {
+        assert!(addr.ip().is_loopback());
+        false
+    }
+
+    async fn on_close(self, reason: CloseReason, stats: ConnectionStats) {
+        let _ = self.0.send((reason, stats));
+    }
+}
+
+#[tokio::test]
+async fn rejected_on_connect() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let addr = spawn(builder().build().unwrap(), Reject(tx));
+    let client = connect(addr).await;
+    assert_eq!(call(&client).await, None);
+    assert_eq!(closed(&mut rx).await, CloseReason::Rejected);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::*;
+use frpc_transport::frpc_transport_http::limit::{
+    LimitConfig, Overflow, RESOURCE_EXHAUSTED, STATUS_HEADER,
+};
+use std::{net::SocketAddr, time::Duration};
+
+fn spawn_limited(config: LimitConfig) -> SocketAddr {
+    spawn(builder().build().unwrap().limits(config), App)
+}
+
+/// Status and `frpc-status` header of the response.
+async fn call(client: &Client) -> (u16, Option<String>) {
+    let res = request(client, &[1, 0, 42]).await.unwrap();
+    let status = res.headers().get(STATUS_HEADER);
+    let status = status.map(|v| v.to_str().unwrap().to_owned());
+    (res.status().as_u16(), status)
+}
+
+/// Second call starts, While the first one is running.
+async fn concurrent(a: &Client, b: &Client) -> ((u16, Option<String>), (u16, Option<String>)) {
+    let first = call(a);
+    let second = async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        call(b).await
+    };
+    tokio::join!(first, second)
+}
+
+#[tokio::test]
+async fn rejected_with_status_header() {
+    let addr = spawn_limited(LimitConfig {
+        max_calls: Some(1),
+        overflow: Overflow::Reject,
+        ..Default::default()
+    });
+    let client = connect(addr).await;
+    let (first, second) = concurrent(&client, &client).await;
+    assert_eq!(first, (200, None));
+    assert_eq!(second, (429, Some(RESOURCE_EXHAUSTED.to_owned())));
+    // Permit is released, Once the call is completed.
+    assert_eq!(call(&client).await, (200, None));
+}
+
+#[tokio::test]
+async fn queued_until_released() {
+    let addr = spawn_limited(LimitConfig {
+        max_calls: Some(1),
+        ..Default::default()
+    });
+    let client = connect(addr).await;
+    let (first, second) = concurrent(&client, &client).await;
+    assert_eq!(first, (200, None));
+    assert_eq!(second, (200, None));
+}
+
+#[tokio::test]
+async fn per_connection() {
+    let addr = spawn_limited(LimitConfig {
+        max_calls_per_connection: Some(1),
+        overflow: Overflow::Reject,
+        ..Default::default()
+    });
+    let (a, b) = (connect(addr).await, connect(addr).await);
+    let (first, second) = concurrent(&a, &b).await;
+    assert_eq!(first, (200, None));
+    assert_eq!(second, (200, None));
+
+    let (first, second) = concurrent(&a, &a).await;
+    assert_eq!(first, (200, None));
+    assert_eq!(second, (429, Some(RESOURCE_EXHAUSTED.to_owned())));
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::*;
+use frpc_transport::*;
+use std::time::Duration;
+
+#[tokio::test]
+async fn in_flight_call_completes_during_graceful_shutdown() {
+    let shutdown = Shutdown::new();
+    let server = builder().build().unwrap().shutdown(shutdown.clone());
+    let addr = server.listeners[0].local_addr().unwrap();
+    let server = tokio::spawn(server.serve(|_, _| async { App }));
+
+    let client = connect(addr).await;
+    let (res, _) = tokio::join!(request(&client, &[1, 0, 42, 7]), async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        shutdown.trigger();
+    });
+    let res = res.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(read_body(res).await, [42, 7]);
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server didn't stop")
+        .unwrap()
+        .unwrap();
+}
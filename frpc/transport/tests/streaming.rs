@@ -0,0 +1,178 @@
+use bytes::Bytes;
+use frpc_transport::*;
+use frpc_transport_core::{Service, Transport};
+use std::{
+    future::{poll_fn, Future},
+    net::SocketAddr,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// Larger than the default `max_unary_payload_size`.
+const LEN: usize = 1024 * 1024;
+
+/// Response of [`Upload`], For the whole body of rpc `1`.
+fn uploaded() -> Vec<u8> {
+    (LEN as u64 - 2).to_le_bytes().repeat(2)
+}
+
+/// `1`: Responds with the number of bytes it has received, Including `cursor`.
+/// Followed by the length of `cursor` and the remaining bytes, That the body has reported.
+struct Upload;
+
+impl Service for Upload {
+    type State = ();
+
+    fn execute<'fut, TR>(
+        _: Self::State,
+        _: u16,
+        cursor: &'fut mut &[u8],
+        transport: &'fut mut TR,
+    ) -> Option<impl Future<Output = ()> + Send + 'fut>
+    where
+        TR: Transport + Send,
+    {
+        Some(async move {
+            let mut len = cursor.len() as u64;
+            let mut reported = len;
+            if let Some(mut body) = transport.take_body() {
+                reported += body.remaining().unwrap() as u64;
+                while let Some(bytes) = poll_fn(|cx| body.poll_data(cx)).await {
+                    len += bytes.unwrap().len() as u64;
+                }
+            }
+            transport
+                .unary_sync(move |w| {
+                    w.write_all(&len.to_le_bytes())?;
+                    w.write_all(&reported.to_le_bytes())
+                })
+                .await
+        })
+    }
+
+    fn is_streaming(id: u16) -> bool {
+        id == 1
+    }
+}
+
+#[derive(Clone)]
+struct App;
+
+impl Application for App {
+    async fn stream(self, mut ctx: Ctx) {
+        ctx.res.status = ctx.serve(Upload, ()).await;
+    }
+
+    async fn stream_http1(self, mut ctx: http1::Ctx) {
+        ctx.res.status = ctx.serve(Upload, ()).await;
+    }
+}
+
+fn spawn() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::builder()
+        .listener(listener)
+        .build()
+        .unwrap()
+        .http1();
+    tokio::spawn(server.serve(|_, _| async { App }));
+    addr
+}
+
+/// Rpc id `1`, Followed by `LEN - 2` bytes of args.
+fn body() -> Bytes {
+    let mut body = vec![7; LEN];
+    body[..2].copy_from_slice(&1u16.to_le_bytes());
+    body.into()
+}
+
+async fn h2(addr: SocketAddr, mut body: Bytes, encoding: Option<&str>) -> (u16, Vec<u8>) {
+    let (mut client, conn) = h2::client::handshake(TcpStream::connect(addr).await.unwrap())
+        .await
+        .unwrap();
+    tokio::spawn(conn);
+    let mut req = http::Request::post("http://localhost/").header("content-length", body.len());
+    if let Some(encoding) = encoding {
+        req = req.header("content-encoding", encoding);
+    }
+    let (res, mut stream) = client.send_request(req.body(()).unwrap(), false).unwrap();
+    let send = async move {
+        while !body.is_empty() {
+            stream.reserve_capacity(body.len());
+            let Some(Ok(cap)) = poll_fn(|cx| stream.poll_capacity(cx)).await else {
+                // Rejected before the whole body is sent.
+                return;
+            };
+            let chunk = body.split_to(cap.min(body.len()));
+            if stream.send_data(chunk, body.is_empty()).is_err() {
+                return;
+            }
+        }
+    };
+    let recv = async {
+        let res = res.await.unwrap();
+        let status = res.status().as_u16();
+        let mut body = res.into_body();
+        let mut data = Vec::new();
+        while let Some(bytes) = body.data().await {
+            data.extend_from_slice(&bytes.unwrap());
+        }
+        (status, data)
+    };
+    tokio::join!(send, recv).1
+}
+
+async fn http1(addr: SocketAddr, body: Bytes, encoding: Option<&str>) -> Vec<u8> {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let mut head = format!(
+        "POST / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\ncontent-length: {}\r\n",
+        body.len()
+    );
+    if let Some(encoding) = encoding {
+        head += &format!("content-encoding: {encoding}\r\n");
+    }
+    head += "\r\n";
+    let (mut reader, mut writer) = stream.split();
+    let send = async {
+        writer.write_all(head.as_bytes()).await.unwrap();
+        // Server may respond and close, Before the whole body is sent.
+        let _ = writer.write_all(&body).await;
+    };
+    let recv = async {
+        let mut res = Vec::new();
+        let _ = reader.read_to_end(&mut res).await;
+        res
+    };
+    tokio::join!(send, recv).1
+}
+
+#[tokio::test]
+async fn h2_body_larger_than_max_unary_payload_size() {
+    let (status, data) = h2(spawn(), body(), None).await;
+    assert_eq!(status, 200);
+    assert_eq!(data, uploaded());
+}
+
+#[tokio::test]
+async fn http1_body_larger_than_max_unary_payload_size() {
+    let res = http1(spawn(), body(), None).await;
+    assert!(res.starts_with(b"HTTP/1.1 200"));
+    assert!(res.ends_with(&uploaded()));
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn compressed_streaming_body_is_rejected() {
+    use frpc_transport_http::compression::Encoding;
+    let addr = spawn();
+    let body = Bytes::from(Encoding::Gzip.encode(&body()[..64]).unwrap());
+
+    let (status, _) = h2(addr, body.clone(), Some("gzip")).await;
+    assert_eq!(status, 415);
+
+    let res = http1(addr, body, Some("gzip")).await;
+    assert!(res.starts_with(b"HTTP/1.1 415"));
+}
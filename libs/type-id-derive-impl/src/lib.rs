@@ -14,6 +14,7 @@ pub fn expand(
     output: &mut TokenStream,
     is_unit_enum: bool,
     enum_repr: Option<&String>,
+    remote: Option<&str>,
 ) {
     let DeriveInput {
         attrs,
@@ -24,7 +25,14 @@ pub fn expand(
     } = input;
 
     let doc = get_comments_from(attrs);
-    let fmt_str = format!("{{}}::{ident}");
+    let name = quote(|o| match remote {
+        // Remote type is registered with its original path, So that codegen names stay stable.
+        Some(path) => quote!(o, { __crate::remote_path(::std::module_path!(), #path) }),
+        None => {
+            let fmt_str = format!("{{}}::{ident}");
+            quote!(o, { ::std::format!(#fmt_str, ::std::module_path!()) });
+        }
+    });
 
     if let Some(param) = generics.type_params().next() {
         return output.extend(
@@ -121,7 +129,7 @@ pub fn expand(
             impl #impl_generics __crate::TypeId for #ident #ty_generics #where_clause {
                 fn ty(__c: &mut __crate::CostomTypes) -> __crate::Ty {
                     __c.register(
-                        ::std::format!(#fmt_str, ::std::module_path!()),
+                        #name,
                         |__c| __crate::CustomTypeKind::#kind(__crate::CustomType::new(#doc, ::std::vec!#body))
                     )
                 }
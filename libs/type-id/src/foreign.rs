@@ -0,0 +1,45 @@
+//! `TypeId` implementations for types from third-party crates.
+//!
+//! Each of them is behind a cargo feature with the same name as the crate.
+//! See [`Ty`] for the wire representation of each type.
+
+#[allow(unused_imports)]
+use super::*;
+
+#[cfg(feature = "uuid")]
+impl TypeId for uuid::Uuid {
+    fn ty(_: &mut CostomTypes) -> Ty {
+        Ty::Uuid
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TypeId for chrono::DateTime<chrono::Utc> {
+    fn ty(_: &mut CostomTypes) -> Ty {
+        Ty::DateTime
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl TypeId for rust_decimal::Decimal {
+    fn ty(_: &mut CostomTypes) -> Ty {
+        Ty::Decimal
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl TypeId for bytes::Bytes {
+    fn ty(_: &mut CostomTypes) -> Ty {
+        Ty::Set {
+            variant: SetVariant::Vec,
+            ty: Box::new(Ty::u8),
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl TypeId for serde_json::Value {
+    fn ty(_: &mut CostomTypes) -> Ty {
+        Ty::Json
+    }
+}
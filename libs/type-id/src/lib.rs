@@ -1,5 +1,6 @@
 mod basic;
 mod collection;
+mod foreign;
 mod wrapper;
 
 use std::collections::btree_map;
@@ -43,6 +44,15 @@ pub enum Ty {
     // char,
     String,
 
+    /// 16 bytes, in big-endian order.
+    Uuid,
+    /// Seconds since unix epoch (`i64`), followed by sub-second nanoseconds (`u32`).
+    DateTime,
+    /// Arbitrary precision decimal number, encoded as string.
+    Decimal,
+    /// Any json value, encoded as json text.
+    Json,
+
     Option(Box<Ty>),
     Result(Box<(Ty, Ty)>),
 
@@ -99,6 +109,32 @@ impl CostomTypes {
     }
 }
 
+/// Path of a remote type, Resolved against `module` of its shadow definition.
+///
+/// `crate::`, `self::` and `super::` prefixes are resolved like Rust paths,
+/// A leading `::` is an absolute path, Otherwise the path is relative to `module`.
+#[doc(hidden)]
+pub fn remote_path(module: &str, path: &str) -> String {
+    if let Some(path) = path.strip_prefix("::") {
+        return path.to_owned();
+    }
+    let mut segments: Vec<&str> = module.split("::").collect();
+    let mut path = path;
+    if let Some(rest) = path.strip_prefix("crate::") {
+        segments.truncate(1);
+        path = rest;
+    }
+    path = path.strip_prefix("self::").unwrap_or(path);
+    while let Some(rest) = path.strip_prefix("super::") {
+        if segments.len() > 1 {
+            segments.pop();
+        }
+        path = rest;
+    }
+    segments.push(path);
+    segments.join("::")
+}
+
 impl Deref for CostomTypes {
     type Target = BTreeMap<String, CustomTypeKind>;
 
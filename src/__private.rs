@@ -12,28 +12,89 @@ pub fn fn_sig<F, Args>(
 ) -> Func
 where
     F: std_lib::FnOnce<Args>,
-    Args: TypeId,
+    Args: ArgsTy,
     F::Output: OutputType,
 {
-    let Ty::Tuple(mut args) = Args::ty(costom_types) else {
-        unreachable!()
-    };
-    if let Some(ty) = args.first() {
-        if ty.is_empty_tuple() {
-            args.remove(0);
-        }
-    }
     Func {
         index,
         ident: frpc_message::Ident(ident.to_string()),
-        args,
+        args: Args::tys(costom_types),
         output: <F::Output as OutputType>::fn_output_ty(costom_types),
         docs: docs.to_string(),
     }
 }
 
-impl<T> TypeId for crate::State<T> {
-    fn ty(_: &mut CostomTypes) -> frpc_message::Ty {
-        Ty::Tuple(vec![])
+pub fn is_streaming<State, F, Args>(_: &F) -> bool
+where
+    F: std_lib::FnOnce<Args>,
+    Args: crate::input::Input<'static, State>,
+{
+    Args::BODY
+}
+
+pub fn requires_identity<State, F, Args>(_: &F) -> bool
+where
+    F: std_lib::FnOnce<Args>,
+    Args: crate::input::Input<'static, State>,
+{
+    Args::IDENTITY
+}
+
+/// Type of an rpc argument, `None` if it's an extractor.
+///
+/// Extractors (`State`, `Principal`, `ResumeCursor`) aren't sent by the client.
+pub trait ArgTy {
+    fn arg_ty(_: &mut CostomTypes) -> Option<Ty>;
+}
+
+impl<T: TypeId> ArgTy for T {
+    fn arg_ty(c: &mut CostomTypes) -> Option<Ty> {
+        Some(T::ty(c))
+    }
+}
+
+impl<T> ArgTy for crate::State<T> {
+    fn arg_ty(_: &mut CostomTypes) -> Option<Ty> {
+        None
     }
 }
+
+/// Types of the arguments, That are sent by the client.
+pub trait ArgsTy {
+    fn tys(_: &mut CostomTypes) -> Vec<Ty>;
+}
+
+macro_rules! args_ty {
+    [$(($($name: ident)*))*] => {
+        $(
+            impl<$($name: ArgTy,)*> ArgsTy for ($($name,)*) {
+                fn tys(_c: &mut CostomTypes) -> Vec<Ty> {
+                    let mut tys = Vec::new();
+                    $(tys.extend($name::arg_ty(_c));)*
+                    tys
+                }
+            }
+        )*
+    };
+}
+
+args_ty! {
+    ()
+    (T0)
+    (T0 T1)
+    (T0 T1 T2)
+    (T0 T1 T2 T3)
+    (T0 T1 T2 T3 T4)
+    (T0 T1 T2 T3 T4 T5)
+    (T0 T1 T2 T3 T4 T5 T6)
+    (T0 T1 T2 T3 T4 T5 T6 T7)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15 T16)
+}
@@ -0,0 +1,171 @@
+use super::*;
+use async_gen::futures_core::Stream;
+use frpc_message::{CostomTypes, Ty};
+use std::future::poll_fn;
+
+/// Large binary argument, that is received incrementally instead of being buffered in memory.
+///
+/// On the wire, It's encoded exactly like `Vec<u8>`, So clients send it as a regular byte array.
+/// It must be the last argument of the rpc, Otherwise the service fails to compile.
+///
+/// ```rust
+/// use frpc::*;
+///
+/// async fn upload(mut file: ByteStream) -> Result<usize, String> {
+///     let mut total = 0;
+///     while let Some(chunk) = file.chunk().await {
+///         total += chunk.map_err(|err| err.to_string())?.len();
+///     }
+///     Ok(total)
+/// }
+///
+/// declare! {
+///     service Storage {
+///         rpc upload = 1;
+///     }
+/// }
+/// ```
+pub struct ByteStream {
+    len: usize,
+    remaining: usize,
+    head: Vec<u8>,
+    body: Option<Box<dyn Body>>,
+}
+
+impl std::fmt::Debug for ByteStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ByteStream")
+            .field("len", &self.len)
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ByteStream {
+    pub(crate) fn decode(data: &mut &[u8], body: Option<Box<dyn Body>>) -> databuf::Result<Self> {
+        let len = read_len(data)?;
+        let head = match body {
+            Some(_) if data.len() <= len => std::mem::take(data),
+            _ => {
+                if data.len() < len {
+                    return Err(eof().into());
+                }
+                let (head, rest) = data.split_at(len);
+                *data = rest;
+                head
+            }
+        };
+        let remaining = len - head.len();
+        // Length is sent by the client, It can't be longer than the request body.
+        if let Some(body_len) = body.as_ref().and_then(|body| body.remaining()) {
+            if remaining > body_len {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "byte stream is longer than the request body",
+                )
+                .into());
+            }
+        }
+        Ok(Self {
+            len,
+            remaining,
+            head: head.to_vec(),
+            body,
+        })
+    }
+
+    /// Total number of bytes, Including the bytes that are already received.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the stream has no bytes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `None` when every byte has been received.
+    pub async fn chunk(&mut self) -> Option<io::Result<Vec<u8>>> {
+        poll_fn(|cx| self.poll_chunk(cx)).await
+    }
+
+    /// Receive all the remaining bytes, Same as decoding `Vec<u8>`.
+    ///
+    /// Buffer grows as the bytes arrive, Instead of trusting [`ByteStream::len`] up front.
+    pub async fn collect(mut self) -> io::Result<Vec<u8>> {
+        let mut buf = std::mem::take(&mut self.head);
+        while let Some(chunk) = self.chunk().await {
+            buf.extend_from_slice(&chunk?);
+        }
+        Ok(buf)
+    }
+
+    fn poll_chunk(&mut self, cx: &mut Context) -> Poll<Option<io::Result<Vec<u8>>>> {
+        if !self.head.is_empty() {
+            return Poll::Ready(Some(Ok(std::mem::take(&mut self.head))));
+        }
+        if self.remaining == 0 {
+            return Poll::Ready(None);
+        }
+        let Some(body) = self.body.as_mut() else {
+            return Poll::Ready(Some(Err(eof())));
+        };
+        Poll::Ready(Some(match body.poll_data(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Some(Ok(chunk))) if chunk.len() > self.remaining => {
+                self.remaining = 0;
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "request body is longer than the byte stream",
+                ))
+            }
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.remaining -= chunk.len();
+                Ok(chunk)
+            }
+            Poll::Ready(Some(Err(err))) => Err(err),
+            Poll::Ready(None) => {
+                self.remaining = 0;
+                Err(eof())
+            }
+        }))
+    }
+}
+
+impl Stream for ByteStream {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_chunk(cx)
+    }
+}
+
+impl TypeId for ByteStream {
+    fn ty(c: &mut CostomTypes) -> Ty {
+        <Vec<u8>>::ty(c)
+    }
+}
+
+fn eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "byte stream ended early")
+}
+
+/// Length prefix of `Vec<u8>`, encoded with [`databuf::config::len::BEU30`].
+fn read_len(data: &mut &[u8]) -> databuf::Result<usize> {
+    let [first, rest @ ..] = *data else {
+        return Err(eof().into());
+    };
+    let extra = (first >> 6) as usize;
+    if rest.len() < extra {
+        return Err(eof().into());
+    }
+    let len = rest[..extra]
+        .iter()
+        .fold((first & 0x3F) as usize, |len, &byte| {
+            (len << 8) | byte as usize
+        });
+    *data = &rest[extra..];
+    Ok(len)
+}
@@ -0,0 +1,164 @@
+//! Wrappers of third-party types, That can be used as arguments and outputs of rpc.
+//!
+//! Each of them is behind a cargo feature with the same name as the crate,
+//! And is encoded exactly like the TypeScript client does.
+#![allow(unused_imports)]
+
+use databuf::{Decode, Encode};
+use frpc_message::{CostomTypes, Ty, TypeId};
+use std::{
+    io,
+    ops::{Deref, DerefMut},
+};
+
+macro_rules! wrapper {
+    ($(#[$doc:meta])* $feature: literal, $name: ident($ty: ty)) => {
+        $(#[$doc])*
+        #[cfg(feature = $feature)]
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name(pub $ty);
+
+        #[cfg(feature = $feature)]
+        impl Deref for $name {
+            type Target = $ty;
+            #[inline]
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        impl DerefMut for $name {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        impl From<$ty> for $name {
+            fn from(value: $ty) -> Self {
+                Self(value)
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        impl TypeId for $name {
+            fn ty(c: &mut CostomTypes) -> Ty {
+                <$ty>::ty(c)
+            }
+        }
+    };
+}
+
+#[cfg(any(feature = "chrono", feature = "rust_decimal", feature = "serde_json"))]
+fn invalid_data(err: impl std::fmt::Display) -> databuf::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string()).into()
+}
+
+wrapper!(
+    /// 16 bytes, In the order of its string form.
+    "uuid", Uuid(uuid::Uuid)
+);
+
+#[cfg(feature = "uuid")]
+impl Encode for Uuid {
+    fn encode<const CONFIG: u16>(&self, c: &mut (impl io::Write + ?Sized)) -> io::Result<()> {
+        c.write_all(self.0.as_bytes())
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl<'de> Decode<'de> for Uuid {
+    fn decode<const CONFIG: u16>(c: &mut &'de [u8]) -> databuf::Result<Self> {
+        let Some((bytes, rest)) = c.split_first_chunk::<16>() else {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        };
+        *c = rest;
+        Ok(Self(uuid::Uuid::from_bytes(*bytes)))
+    }
+}
+
+wrapper!(
+    /// Seconds since the Unix epoch (`i64`), Followed by nanoseconds (`u32`).
+    "chrono", DateTime(chrono::DateTime<chrono::Utc>)
+);
+
+#[cfg(feature = "chrono")]
+impl Encode for DateTime {
+    fn encode<const CONFIG: u16>(&self, c: &mut (impl io::Write + ?Sized)) -> io::Result<()> {
+        self.0.timestamp().encode::<CONFIG>(c)?;
+        self.0.timestamp_subsec_nanos().encode::<CONFIG>(c)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<'de> Decode<'de> for DateTime {
+    fn decode<const CONFIG: u16>(c: &mut &'de [u8]) -> databuf::Result<Self> {
+        let secs = i64::decode::<CONFIG>(c)?;
+        let nanos = u32::decode::<CONFIG>(c)?;
+        chrono::DateTime::from_timestamp(secs, nanos)
+            .map(Self)
+            .ok_or_else(|| invalid_data("datetime is out of range"))
+    }
+}
+
+wrapper!(
+    /// Decimal string, So that no precision is lost.
+    "rust_decimal", Decimal(rust_decimal::Decimal)
+);
+
+#[cfg(feature = "rust_decimal")]
+impl Encode for Decimal {
+    fn encode<const CONFIG: u16>(&self, c: &mut (impl io::Write + ?Sized)) -> io::Result<()> {
+        self.0.to_string().encode::<CONFIG>(c)
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl<'de> Decode<'de> for Decimal {
+    fn decode<const CONFIG: u16>(c: &mut &'de [u8]) -> databuf::Result<Self> {
+        let value = <&str>::decode::<CONFIG>(c)?;
+        value.parse().map(Self).map_err(invalid_data)
+    }
+}
+
+wrapper!(
+    /// Same as `Vec<u8>`.
+    "bytes", Bytes(bytes::Bytes)
+);
+
+#[cfg(feature = "bytes")]
+impl Encode for Bytes {
+    fn encode<const CONFIG: u16>(&self, c: &mut (impl io::Write + ?Sized)) -> io::Result<()> {
+        self.0[..].encode::<CONFIG>(c)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<'de> Decode<'de> for Bytes {
+    fn decode<const CONFIG: u16>(c: &mut &'de [u8]) -> databuf::Result<Self> {
+        let bytes = <&[u8]>::decode::<CONFIG>(c)?;
+        Ok(Self(bytes::Bytes::copy_from_slice(bytes)))
+    }
+}
+
+wrapper!(
+    /// JSON text, Encoded as a string.
+    "serde_json", Json(serde_json::Value)
+);
+
+#[cfg(feature = "serde_json")]
+impl Encode for Json {
+    fn encode<const CONFIG: u16>(&self, c: &mut (impl io::Write + ?Sized)) -> io::Result<()> {
+        self.0.to_string().encode::<CONFIG>(c)
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl<'de> Decode<'de> for Json {
+    fn decode<const CONFIG: u16>(c: &mut &'de [u8]) -> databuf::Result<Self> {
+        let value = <&str>::decode::<CONFIG>(c)?;
+        serde_json::from_str(value).map(Self).map_err(invalid_data)
+    }
+}
@@ -0,0 +1,164 @@
+//! Standard health check service, For liveness and readiness probes.
+//!
+//! Status of each service is set by the application through [`HealthReporter`],
+//! Empty service name is the status of the whole server.
+//!
+//! ```rust
+//! use frpc::{health::*, *};
+//!
+//! async fn hello() -> String {
+//!     "Hello!".into()
+//! }
+//!
+//! declare! {
+//!     service Greeter {
+//!         rpc hello = 1;
+//!     }
+//! }
+//!
+//! let reporter = HealthReporter::new();
+//! reporter.set_serving::<Greeter>();
+//! assert_eq!(reporter.status("Greeter"), ServingStatus::Serving);
+//! ```
+use crate::*;
+use std::{
+    collections::HashMap,
+    future::poll_fn,
+    sync::{Arc, Mutex},
+    task::Waker,
+};
+
+/// Status of a service.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServingStatus {
+    /// Status is not known yet.
+    Unknown,
+    /// Ready to serve requests.
+    Serving,
+    /// Running, But shouldn't receive requests.
+    NotServing,
+    /// No status was ever reported for the service.
+    ServiceUnknown,
+}
+
+#[derive(Default)]
+struct Inner {
+    statuses: HashMap<String, ServingStatus>,
+    /// Woken when any status is changed.
+    watchers: Vec<Waker>,
+}
+
+/// Shared status of services, Cheap to clone.
+#[derive(Clone)]
+pub struct HealthReporter {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for HealthReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthReporter {
+    /// The server is [`ServingStatus::Serving`], Other services are unknown.
+    pub fn new() -> Self {
+        let reporter = Self {
+            inner: Default::default(),
+        };
+        reporter.set("", ServingStatus::Serving);
+        reporter
+    }
+
+    /// Set the status of `service`, Watchers are notified if it's changed.
+    pub fn set(&self, service: impl Into<String>, status: ServingStatus) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.statuses.insert(service.into(), status) != Some(status) {
+            inner.watchers.drain(..).for_each(Waker::wake);
+        }
+    }
+
+    /// Set the status of every known service, Including the server.
+    pub fn set_all(&self, status: ServingStatus) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.statuses.values_mut().for_each(|s| *s = status);
+        inner.watchers.drain(..).for_each(Waker::wake);
+    }
+
+    /// Mark service `E` as [`ServingStatus::Serving`].
+    pub fn set_serving<E: Service>(&self) {
+        self.set(E::NAME, ServingStatus::Serving)
+    }
+
+    /// Mark service `E` as [`ServingStatus::NotServing`].
+    pub fn set_not_serving<E: Service>(&self) {
+        self.set(E::NAME, ServingStatus::NotServing)
+    }
+
+    /// Forget the status of `service`.
+    pub fn remove(&self, service: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.statuses.remove(service).is_some() {
+            inner.watchers.drain(..).for_each(Waker::wake);
+        }
+    }
+
+    /// Current status of `service`.
+    pub fn status(&self, service: &str) -> ServingStatus {
+        let inner = self.inner.lock().unwrap();
+        Self::get(&inner, service)
+    }
+
+    fn get(inner: &Inner, service: &str) -> ServingStatus {
+        inner
+            .statuses
+            .get(service)
+            .copied()
+            .unwrap_or(ServingStatus::ServiceUnknown)
+    }
+
+    /// Waits until the status of `service` is different from `last`.
+    pub async fn changed(&self, service: &str, last: Option<ServingStatus>) -> ServingStatus {
+        poll_fn(|cx| {
+            let mut inner = self.inner.lock().unwrap();
+            let status = Self::get(&inner, service);
+            if Some(status) != last {
+                return Poll::Ready(status);
+            }
+            if !inner.watchers.iter().any(|w| w.will_wake(cx.waker())) {
+                inner.watchers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+fn check(reporter: State<HealthReporter>, service: String) -> Return<ServingStatus> {
+    Return(reporter.status(&service))
+}
+
+fn watch(reporter: State<HealthReporter>, service: String) -> impl Output {
+    crate::sse! {
+        let mut last = None;
+        loop {
+            let status = reporter.changed(&service, last).await;
+            last = Some(status);
+            yield status;
+        }
+    }
+}
+
+declare! {
+    /// Health checking protocol, Modeled after gRPC health checking.
+    #[allow(missing_docs)]
+    pub service Health {
+        type State = HealthReporter;
+
+        /// Status of a service, Empty name is the status of the whole server.
+        rpc check = 1;
+
+        /// Current status of a service, Followed by every change.
+        rpc watch = 2;
+    }
+}
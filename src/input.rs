@@ -1,23 +1,74 @@
-use crate::State;
+use crate::{ByteStream, State};
 use databuf::{Decode, Result};
+use frpc_transport_core::{Body, Identity, Transport};
+use std::sync::Arc;
+
+/// Parts of the request, That aren't decoded from the arguments.
+pub struct Parts {
+    pub body: Option<Box<dyn Body>>,
+    pub identity: Option<Arc<Identity>>,
+    pub resume_cursor: Option<String>,
+}
+
+impl Parts {
+    pub fn new(transport: &mut impl Transport) -> Self {
+        Self {
+            body: transport.take_body(),
+            identity: transport.identity(),
+            resume_cursor: transport.resume_cursor(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------
+
+pub trait Arg<'de>: Sized {
+    /// `true` if the argument is read from [`Body`].
+    const BODY: bool = false;
+    /// `true` if the argument is the authenticated caller.
+    const IDENTITY: bool = false;
+    fn decode(_: &mut &'de [u8], _: &mut Parts) -> Result<Self>;
+}
+
+impl<'de, T> Arg<'de> for T
+where
+    T: Decode<'de>,
+{
+    fn decode(data: &mut &'de [u8], _: &mut Parts) -> Result<Self> {
+        <T as Decode<'de>>::decode::<{ crate::DATABUF_CONFIG }>(data)
+    }
+}
+
+impl Arg<'_> for ByteStream {
+    const BODY: bool = true;
+    fn decode(data: &mut &[u8], parts: &mut Parts) -> Result<Self> {
+        ByteStream::decode(data, parts.body.take())
+    }
+}
 
 // ----------------------------------------------------------------------
 
 pub trait FirstArg<'de, State>: Sized {
-    fn decode(state: State, _: &mut &'de [u8]) -> Result<Self>;
+    const BODY: bool;
+    const IDENTITY: bool;
+    fn decode(state: State, _: &mut &'de [u8], _: &mut Parts) -> Result<Self>;
 }
 
 impl<'de, State, Args> FirstArg<'de, State> for Args
 where
-    Args: Decode<'de>,
+    Args: Arg<'de>,
 {
-    fn decode(_: State, data: &mut &'de [u8]) -> Result<Self> {
-        Args::decode::<{ crate::DATABUF_CONFIG }>(data)
+    const BODY: bool = Args::BODY;
+    const IDENTITY: bool = Args::IDENTITY;
+    fn decode(_: State, data: &mut &'de [u8], parts: &mut Parts) -> Result<Self> {
+        Args::decode(data, parts)
     }
 }
 
 impl<T> FirstArg<'_, T> for State<T> {
-    fn decode(state: T, _: &mut &[u8]) -> databuf::Result<Self> {
+    const BODY: bool = false;
+    const IDENTITY: bool = false;
+    fn decode(state: T, _: &mut &[u8], _: &mut Parts) -> databuf::Result<Self> {
         Ok(State(state))
     }
 }
@@ -25,7 +76,11 @@ impl<T> FirstArg<'_, T> for State<T> {
 // ----------------------------------------------------------------------
 
 pub trait Input<'de, State>: Sized {
-    fn decode(state: State, _: &mut &'de [u8]) -> Result<Self>;
+    /// `true` if the last argument is read from [`Body`], No other argument may be.
+    const BODY: bool;
+    /// `true` if any argument is the authenticated caller.
+    const IDENTITY: bool;
+    fn decode(state: State, _: &mut &'de [u8], _: &mut Parts) -> Result<Self>;
 }
 
 macro_rules! args_with_ctx {
@@ -34,12 +89,21 @@ macro_rules! args_with_ctx {
             impl<'de, State, T0, $($name,)*> Input<'de, State> for (T0, $($name,)*)
             where
                 T0: FirstArg<'de, State>,
-                $($name: Decode<'de>,)*
+                $($name: Arg<'de>,)*
             {
-                fn decode(state: State, data: &mut &'de [u8]) -> Result<Self> {
+                const BODY: bool = {
+                    let body = T0::BODY;
+                    $(
+                        assert!(!body, "`ByteStream` must be the last argument");
+                        let body = $name::BODY;
+                    )*
+                    body
+                };
+                const IDENTITY: bool = T0::IDENTITY $(|| $name::IDENTITY)*;
+                fn decode(state: State, data: &mut &'de [u8], parts: &mut Parts) -> Result<Self> {
                     Ok((
-                        T0::decode(state, data)?,
-                        $($name::decode::<{ crate::DATABUF_CONFIG }>(data)?,)*
+                        T0::decode(state, data, parts)?,
+                        $($name::decode(data, parts)?,)*
                     ))
                 }
             }
@@ -48,7 +112,9 @@ macro_rules! args_with_ctx {
 }
 
 impl<State> Input<'_, State> for () {
-    fn decode(_: State, _: &mut &[u8]) -> Result<Self> {
+    const BODY: bool = false;
+    const IDENTITY: bool = false;
+    fn decode(_: State, _: &mut &[u8], _: &mut Parts) -> Result<Self> {
         Ok(())
     }
 }
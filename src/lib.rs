@@ -1,20 +1,33 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 
+// Paths generated by `declare!` and derive macros, Resolve within this crate.
+extern crate self as frpc;
+
+mod byte_stream;
 mod input;
 mod output;
 mod output_type;
+mod principal;
+mod resume;
 // mod service;
 
 #[doc(hidden)]
 pub mod __private;
+pub mod foreign;
+pub mod health;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub use async_gen;
+pub use byte_stream::ByteStream;
 
 pub use databuf;
 pub use frpc_macros::*;
 #[doc(hidden)]
 pub use frpc_transport_core::*;
 pub use output::*;
+pub use principal::Principal;
+pub use resume::{Resumable, ResumeCursor};
 
 use async_gen::GeneratorState;
 use databuf::Encode;
@@ -67,6 +80,11 @@ def!(
     struct SSE
 );
 
+def!(
+    #[doc(hidden)]
+    struct ResumableSSE
+);
+
 def!(
     /// Represent synchronous function.
     ///
@@ -101,6 +119,17 @@ macro_rules! sse {
     }
 }
 
+/// Same as [`sse!`], But every item is a [`Resumable`] that carries a cursor.
+///
+/// Client reconnects automatically with the cursor of the last received item,
+/// Handler receives it as [`ResumeCursor`] and continues from that point.
+#[macro_export]
+macro_rules! resumable_sse {
+    ($($tt:tt)*) => {
+        $crate::ResumableSSE($crate::async_gen::__private::gen_inner!([$crate::async_gen] $($tt)*))
+    }
+}
+
 /// Generators, also commonly referred to as coroutines.
 pub trait AsyncGenerator {
     /// The type of value this generator yields.
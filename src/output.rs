@@ -1,5 +1,3 @@
-use async_gen::futures_core::future::BoxFuture;
-
 use super::*;
 
 /// It represents the output of an rpc function.
@@ -33,24 +31,6 @@ pub trait Output: crate::output_type::OutputType {
     where
         State: Send,
         Args: input::Input<'data, State> + Send;
-
-    #[inline]
-    #[doc(hidden)]
-    fn _produce<'fut, 'cursor, 'data, 'transport, State, Args>(
-        func: impl std_lib::FnOnce<Args, Output = Self> + Send + 'fut,
-        state: State,
-        cursor: &'cursor mut &'data [u8],
-        transport: &'transport mut (impl Transport + Send),
-    ) -> Option<BoxFuture<'fut, ()>>
-    where
-        Self: 'fut,
-        'cursor: 'fut,
-        'transport: 'fut,
-        State: Send + 'fut,
-        Args: input::Input<'data, State> + Send + 'fut,
-    {
-        Some(Box::pin(Self::produce(func, state, cursor, transport)))
-    }
 }
 
 impl<T> Output for Return<T>
@@ -67,7 +47,8 @@ where
         State: Send,
         Args: input::Input<'data, State> + Send,
     {
-        transport.unary_sync(|buf| match Args::decode(state, cursor) {
+        let mut parts = input::Parts::new(transport);
+        transport.unary_sync(move |buf| match Args::decode(state, cursor, &mut parts) {
             Ok(args) => {
                 let this = func.call_once(args);
                 Encode::encode::<{ crate::DATABUF_CONFIG }>(&this.0, buf)
@@ -92,7 +73,8 @@ where
         State: Send,
         Args: input::Input<'data, State> + Send,
     {
-        let mut state = match Args::decode(state, cursor) {
+        let mut parts = input::Parts::new(transport);
+        let mut state = match Args::decode(state, cursor, &mut parts) {
             Ok(args) => Ok(func.call_once(args)),
             Err(error) => Err(Some(io::Error::new(io::ErrorKind::InvalidInput, error))),
         };
@@ -121,24 +103,59 @@ where
         State: Send,
         Args: input::Input<'data, State> + Send,
     {
-        let mut state = match Args::decode(state, cursor) {
-            Ok(args) => Ok(func.call_once(args)),
+        let mut parts = input::Parts::new(transport);
+        let state = match Args::decode(state, cursor, &mut parts) {
+            Ok(args) => Ok(func.call_once(args).0),
             Err(error) => Err(Some(io::Error::new(io::ErrorKind::InvalidInput, error))),
         };
-        transport.server_stream(move |cx, buf| match state {
-            Ok(ref mut async_generator) => unsafe { Pin::new_unchecked(&mut async_generator.0) }
-                .poll_resume(cx)
-                .map(|gen_state| match gen_state {
-                    GeneratorState::Yielded(val) => {
-                        Encode::encode::<{ crate::DATABUF_CONFIG }>(&val, buf).map(|()| false)
-                    }
-                    GeneratorState::Complete(val) => {
-                        Encode::encode::<{ crate::DATABUF_CONFIG }>(&val, buf).map(|()| true)
-                    }
-                }),
-            Err(ref mut err) => Poll::Ready(Err(err
-                .take()
-                .expect("Transport::server_stream(..)` polled after completion"))),
-        })
+        transport.server_stream(poll_generator(state))
+    }
+}
+
+impl<G, T> Output for ResumableSSE<G>
+where
+    G: AsyncGenerator<Yield = Resumable<T>> + Send,
+    T: Encode + TypeId,
+{
+    fn produce<'data, State, Args>(
+        func: impl std_lib::FnOnce<Args, Output = Self> + Send,
+        state: State,
+        cursor: &mut &'data [u8],
+        transport: &mut (impl Transport + Send),
+    ) -> impl Future<Output = ()> + Send
+    where
+        State: Send,
+        Args: input::Input<'data, State> + Send,
+    {
+        let mut parts = input::Parts::new(transport);
+        let state = match Args::decode(state, cursor, &mut parts) {
+            Ok(args) => Ok(func.call_once(args).0),
+            Err(error) => Err(Some(io::Error::new(io::ErrorKind::InvalidInput, error))),
+        };
+        // Cursor is encoded with every item, See `Resumable`.
+        transport.server_stream(poll_generator(state))
+    }
+}
+
+fn poll_generator<G>(
+    mut state: Result<G, Option<io::Error>>,
+) -> impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<bool>> + Send
+where
+    G: AsyncGenerator + Send,
+{
+    move |cx, buf| match state {
+        Ok(ref mut async_generator) => unsafe { Pin::new_unchecked(async_generator) }
+            .poll_resume(cx)
+            .map(|gen_state| match gen_state {
+                GeneratorState::Yielded(val) => {
+                    Encode::encode::<{ crate::DATABUF_CONFIG }>(&val, buf).map(|()| false)
+                }
+                GeneratorState::Complete(val) => {
+                    Encode::encode::<{ crate::DATABUF_CONFIG }>(&val, buf).map(|()| true)
+                }
+            }),
+        Err(ref mut err) => Poll::Ready(Err(err
+            .take()
+            .expect("Transport::server_stream(..)` polled after completion"))),
     }
 }
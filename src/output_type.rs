@@ -33,3 +33,16 @@ where
         }
     }
 }
+
+impl<G, T> OutputType for ResumableSSE<G>
+where
+    G: AsyncGenerator<Yield = Resumable<T>>,
+    T: Encode + TypeId,
+{
+    fn fn_output_ty(c: &mut CostomTypes) -> FuncOutput {
+        FuncOutput::ResumableStream {
+            yield_ty: <T as TypeId>::ty(c),
+            return_ty: <G::Return as TypeId>::ty(c),
+        }
+    }
+}
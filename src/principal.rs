@@ -0,0 +1,52 @@
+use super::*;
+use crate::{
+    __private::ArgTy,
+    input::{Arg, Parts},
+};
+use frpc_message::{CostomTypes, Ty};
+use std::sync::Arc;
+
+/// Authenticated caller of the rpc, By a bearer token or a client certificate.
+///
+/// It's an extractor, So the client doesn't send it as an argument.
+/// Anonymous calls are rejected by the transport with `401 Unauthorized`, Before the rpc is called.
+///
+/// ```rust
+/// use frpc::*;
+///
+/// async fn whoami(user: Principal) -> String {
+///     user.subject.clone().unwrap_or_default()
+/// }
+///
+/// declare! {
+///     service Account {
+///         rpc whoami = 1;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Principal(pub Arc<Identity>);
+
+impl Deref for Principal {
+    type Target = Identity;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Arg<'_> for Principal {
+    const IDENTITY: bool = true;
+    fn decode(_: &mut &[u8], parts: &mut Parts) -> databuf::Result<Self> {
+        match &parts.identity {
+            Some(identity) => Ok(Principal(identity.clone())),
+            None => Err(io::Error::new(io::ErrorKind::PermissionDenied, "unauthenticated").into()),
+        }
+    }
+}
+
+impl ArgTy for Principal {
+    fn arg_ty(_: &mut CostomTypes) -> Option<Ty> {
+        None
+    }
+}
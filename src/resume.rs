@@ -0,0 +1,138 @@
+use super::*;
+use crate::{
+    __private::ArgTy,
+    input::{Arg, Parts},
+};
+use frpc_message::{CostomTypes, Ty};
+use std::borrow::Cow;
+
+/// Item of a resumable server stream, Yielded by [`resumable_sse!`](crate::resumable_sse).
+///
+/// `cursor` is defined by the server, Such as an offset or an event id.
+/// Client only sees `item`, And keeps the cursor of the last received item.
+///
+/// Cursor is sent back in a request header, So it's percent-encoded on the wire,
+/// Except for visible ASCII characters. [`ResumeCursor`] receives the original value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resumable<T> {
+    /// Position after this item, Sent back by the client when it reconnects.
+    pub cursor: String,
+    /// Value received by the client.
+    pub item: T,
+}
+
+impl<T> Resumable<T> {
+    /// `cursor` is usually a number or an id, So it accepts anything that converts into `String`.
+    pub fn new(cursor: impl Into<String>, item: T) -> Self {
+        Self {
+            cursor: cursor.into(),
+            item,
+        }
+    }
+}
+
+/// Cursor is sent before the item, So that the client can strip it without knowing the item type.
+impl<T: Encode> Encode for Resumable<T> {
+    fn encode<const CONFIG: u16>(&self, c: &mut (impl io::Write + ?Sized)) -> io::Result<()> {
+        match encode_cursor(&self.cursor) {
+            Cow::Borrowed(_) => self.cursor.encode::<CONFIG>(c)?,
+            Cow::Owned(cursor) => cursor.encode::<CONFIG>(c)?,
+        }
+        self.item.encode::<CONFIG>(c)
+    }
+}
+
+impl<T: TypeId> TypeId for Resumable<T> {
+    fn ty(c: &mut CostomTypes) -> Ty {
+        T::ty(c)
+    }
+}
+
+/// Cursor of the last item received by the client, `None` unless the stream is resumed.
+///
+/// It's an extractor, So the client doesn't send it as an argument.
+///
+/// ```rust
+/// use frpc::*;
+///
+/// fn events(cursor: ResumeCursor) -> impl Output {
+///     resumable_sse! {
+///         let start = cursor.as_deref().and_then(|c| c.parse().ok()).map_or(0, |n: u32| n + 1);
+///         for n in start..10 {
+///             yield Resumable::new(n.to_string(), n)
+///         }
+///     }
+/// }
+///
+/// declare! {
+///     service Feed {
+///         rpc events = 1;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResumeCursor(pub Option<String>);
+
+impl Deref for ResumeCursor {
+    type Target = Option<String>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Arg<'_> for ResumeCursor {
+    fn decode(_: &mut &[u8], parts: &mut Parts) -> databuf::Result<Self> {
+        match parts.resume_cursor.take() {
+            Some(cursor) => match decode_cursor(&cursor) {
+                Some(cursor) => Ok(ResumeCursor(Some(cursor))),
+                None => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("malformed resume cursor: {cursor:?}"),
+                )
+                .into()),
+            },
+            None => Ok(ResumeCursor(None)),
+        }
+    }
+}
+
+impl ArgTy for ResumeCursor {
+    fn arg_ty(_: &mut CostomTypes) -> Option<Ty> {
+        None
+    }
+}
+
+/// Header values can only contain visible ASCII, `%` is encoded too, So that decoding is unambiguous.
+fn encode_cursor(cursor: &str) -> Cow<'_, str> {
+    let is_plain = |b: u8| b.is_ascii_graphic() && b != b'%';
+    if cursor.bytes().all(is_plain) {
+        return Cow::Borrowed(cursor);
+    }
+    let mut encoded = String::with_capacity(cursor.len() * 3);
+    for b in cursor.bytes() {
+        if is_plain(b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{b:02X}"));
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+/// Returns `None`, If `cursor` isn't a valid output of [`encode_cursor`].
+fn decode_cursor(cursor: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(cursor.len());
+    let mut iter = cursor.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'%' => {
+                let mut digit = || (iter.next()? as char).to_digit(16);
+                bytes.push((digit()? * 16 + digit()?) as u8);
+            }
+            b if b.is_ascii_graphic() => bytes.push(b),
+            _ => return None,
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
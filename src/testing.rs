@@ -0,0 +1,281 @@
+//! In-process transport, Handlers can be tested without running a server.
+//!
+//! ```rust
+//! use frpc::{testing::*, *};
+//!
+//! fn add(a: u8, b: u8) -> Return<u8> {
+//!     Return(a + b)
+//! }
+//!
+//! declare! {
+//!     service Num {
+//!         rpc add = 1;
+//!     }
+//! }
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let client = TestClient::new(Num, ());
+//! assert_eq!(client.unary::<u8>(1, (2u8, 3u8)).await?, 5);
+//! # Ok(())
+//! # }
+//! ```
+use super::*;
+use async_gen::futures_core::{future::BoxFuture, Stream};
+use databuf::Decode;
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+/// Output of an rpc call, written by [`Loopback`].
+#[derive(Debug)]
+pub enum Frame {
+    /// Result of unary call.
+    Unary(Vec<u8>),
+    /// Item yielded by server stream.
+    Yield(Vec<u8>),
+    /// Return value of server stream.
+    Return(Vec<u8>),
+    /// Call failed, Such as invalid arguments or unknown rpc id.
+    Error(io::Error),
+}
+
+/// [`Transport`] that keeps every output frame in memory.
+///
+/// Server stream is suspended after each yielded item, So that infinite streams can be consumed lazily.
+#[derive(Debug, Default, Clone)]
+pub struct Loopback {
+    frames: Arc<Mutex<VecDeque<Frame>>>,
+    resume_cursor: Option<String>,
+}
+
+impl Loopback {
+    /// Takes the oldest output frame.
+    pub fn pop(&self) -> Option<Frame> {
+        self.frames.lock().unwrap().pop_front()
+    }
+
+    fn push(&self, frame: Frame) {
+        self.frames.lock().unwrap().push_back(frame);
+    }
+}
+
+impl Transport for Loopback {
+    async fn unary_sync(&mut self, cb: impl FnOnce(&mut dyn io::Write) -> io::Result<()> + Send) {
+        let mut buf = vec![];
+        self.push(match cb(&mut buf) {
+            Ok(()) => Frame::Unary(buf),
+            Err(err) => Frame::Error(err),
+        });
+    }
+
+    async fn unary(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<()>> + Send,
+    ) {
+        let mut buf = vec![];
+        self.push(match poll_fn(|cx| poll(cx, &mut buf)).await {
+            Ok(()) => Frame::Unary(buf),
+            Err(err) => Frame::Error(err),
+        });
+    }
+
+    async fn server_stream(
+        &mut self,
+        mut poll: impl FnMut(&mut Context, &mut dyn io::Write) -> Poll<io::Result<bool>> + Send,
+    ) {
+        loop {
+            let mut buf = vec![];
+            match poll_fn(|cx| poll(cx, &mut buf)).await {
+                Ok(false) => self.push(Frame::Yield(buf)),
+                Ok(true) => break self.push(Frame::Return(buf)),
+                Err(err) => break self.push(Frame::Error(err)),
+            }
+            let mut suspended = false;
+            poll_fn(|cx| {
+                if suspended {
+                    return Poll::Ready(());
+                }
+                suspended = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })
+            .await;
+        }
+    }
+
+    fn resume_cursor(&self) -> Option<String> {
+        self.resume_cursor.clone()
+    }
+}
+
+/// Call rpc of a service directly, Arguments are encoded and outputs are decoded, same as a real client.
+pub struct TestClient<S: Service> {
+    /// State passed to every call.
+    pub state: S::State,
+    _service: PhantomData<S>,
+}
+
+impl<S> TestClient<S>
+where
+    S: Service + 'static,
+    S::State: Clone + Send + 'static,
+{
+    /// Create a client for `service`.
+    pub fn new(_: S, state: S::State) -> Self {
+        Self {
+            state,
+            _service: PhantomData,
+        }
+    }
+
+    fn call(&self, id: u16, args: impl Encode, transport: Loopback) -> BoxFuture<'static, ()> {
+        let mut data = vec![];
+        let encoded = Encode::encode::<{ crate::DATABUF_CONFIG }>(&args, &mut data);
+        let state = self.state.clone();
+        Box::pin(async move {
+            if let Err(err) = encoded {
+                return transport.push(Frame::Error(err));
+            }
+            let mut cursor = data.as_slice();
+            let mut responder = transport.clone();
+            match S::execute(state, id, &mut cursor, &mut responder) {
+                Some(fut) => fut.await,
+                None => transport.push(Frame::Error(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("unknown rpc id: {id}"),
+                ))),
+            }
+        })
+    }
+
+    /// Call unary rpc, `args` is a tuple of arguments (excluding [`State`]).
+    pub async fn unary<T>(&self, id: u16, args: impl Encode) -> io::Result<T>
+    where
+        T: for<'de> Decode<'de>,
+    {
+        let transport = Loopback::default();
+        self.call(id, args, transport.clone()).await;
+        match transport.pop() {
+            Some(Frame::Unary(data)) => decode(&data),
+            Some(Frame::Error(err)) => Err(err),
+            frame => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected unary frame, got: {frame:?}"),
+            )),
+        }
+    }
+
+    /// Call server stream rpc, `args` is a tuple of arguments (excluding [`State`]).
+    pub fn server_stream<Y, R>(&self, id: u16, args: impl Encode) -> ServerStream<Y, R>
+    where
+        Y: for<'de> Decode<'de>,
+        R: for<'de> Decode<'de>,
+    {
+        self.stream(id, args, Loopback::default())
+    }
+
+    /// Same as [`TestClient::server_stream`], But the stream is resumed from `cursor`, See [`ResumeCursor`].
+    ///
+    /// Items of a resumable stream are decoded as `(cursor, item)`.
+    pub fn resume_stream<Y, R>(
+        &self,
+        id: u16,
+        args: impl Encode,
+        cursor: impl Into<String>,
+    ) -> ServerStream<Y, R>
+    where
+        Y: for<'de> Decode<'de>,
+        R: for<'de> Decode<'de>,
+    {
+        let transport = Loopback {
+            resume_cursor: Some(cursor.into()),
+            ..Loopback::default()
+        };
+        self.stream(id, args, transport)
+    }
+
+    fn stream<Y, R>(&self, id: u16, args: impl Encode, transport: Loopback) -> ServerStream<Y, R> {
+        ServerStream {
+            fut: Some(self.call(id, args, transport.clone())),
+            transport,
+            _output: PhantomData,
+        }
+    }
+}
+
+/// Item of [`ServerStream`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum StreamItem<Y, R> {
+    /// Item yielded by server stream.
+    Yield(Y),
+    /// Return value of server stream, It's the last item.
+    Return(R),
+}
+
+/// Stream of yielded items and the return value, Ends after the return value or an error.
+pub struct ServerStream<Y, R> {
+    fut: Option<BoxFuture<'static, ()>>,
+    transport: Loopback,
+    _output: PhantomData<fn() -> (Y, R)>,
+}
+
+impl<Y, R> ServerStream<Y, R>
+where
+    Y: for<'de> Decode<'de>,
+    R: for<'de> Decode<'de>,
+{
+    /// Returns `None` when the stream has ended.
+    pub async fn next(&mut self) -> Option<io::Result<StreamItem<Y, R>>> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+}
+
+impl<Y, R> Stream for ServerStream<Y, R>
+where
+    Y: for<'de> Decode<'de>,
+    R: for<'de> Decode<'de>,
+{
+    type Item = io::Result<StreamItem<Y, R>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Some(fut) = self.fut.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let done = fut.as_mut().poll(cx).is_ready();
+        let item = match self.transport.pop() {
+            Some(Frame::Yield(data)) => decode(&data).map(StreamItem::Yield),
+            Some(Frame::Return(data)) => {
+                self.fut = None;
+                decode(&data).map(StreamItem::Return)
+            }
+            Some(Frame::Error(err)) => {
+                self.fut = None;
+                Err(err)
+            }
+            Some(frame) => {
+                self.fut = None;
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected server stream frame, got: {frame:?}"),
+                ))
+            }
+            None if done => {
+                self.fut = None;
+                return Poll::Ready(None);
+            }
+            None => return Poll::Pending,
+        };
+        Poll::Ready(Some(item))
+    }
+}
+
+fn decode<T>(data: &[u8]) -> io::Result<T>
+where
+    T: for<'de> Decode<'de>,
+{
+    T::decode::<{ crate::DATABUF_CONFIG }>(&mut &data[..])
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
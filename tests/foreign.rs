@@ -0,0 +1,73 @@
+//! Encoding of [`frpc::foreign`] wrappers, Expected bytes are the output of the TypeScript client (`databuf.ts`).
+#![allow(unused_imports)]
+
+use frpc::{
+    databuf::{Decode, Encode},
+    DATABUF_CONFIG,
+};
+use std::fmt::Debug;
+
+#[allow(dead_code)]
+fn round_trip<T>(value: T, expected: &[u8])
+where
+    T: Encode + for<'de> Decode<'de> + PartialEq + Debug,
+{
+    let mut buf = Vec::new();
+    value.encode::<DATABUF_CONFIG>(&mut buf).unwrap();
+    assert_eq!(buf, expected);
+
+    let mut cursor = &buf[..];
+    let decoded = T::decode::<DATABUF_CONFIG>(&mut cursor).unwrap();
+    assert!(cursor.is_empty());
+    assert_eq!(decoded, value);
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid() {
+    let value = "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap();
+    round_trip(
+        frpc::foreign::Uuid(value),
+        &[
+            0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f,
+            0xe0, 0xc8,
+        ],
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn datetime() {
+    // 2024-01-01T00:00:00.500Z
+    let value = chrono::DateTime::from_timestamp(1_704_067_200, 500_000_000).unwrap();
+    round_trip(
+        frpc::foreign::DateTime(value),
+        &[
+            // seconds, zigzag LEB128
+            128, 130, 144, 217, 12, //
+            // nanoseconds, LEB128
+            128, 202, 181, 238, 1,
+        ],
+    );
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn decimal() {
+    let value = "12.50".parse().unwrap();
+    round_trip(frpc::foreign::Decimal(value), b"\x0512.50");
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn bytes() {
+    let value = bytes::Bytes::from_static(&[1, 2, 3]);
+    round_trip(frpc::foreign::Bytes(value), &[3, 1, 2, 3]);
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn json() {
+    let value = serde_json::json!({ "a": 1 });
+    round_trip(frpc::foreign::Json(value), b"\x07{\"a\":1}");
+}
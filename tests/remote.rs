@@ -0,0 +1,166 @@
+//! `#[frpc(remote = "...")]` shadow definitions, And `#[frpc(with = "...")]` fields.
+use frpc::{
+    __private::frpc_message::{CostomTypes, Ty, TypeId},
+    databuf::{Decode, Encode},
+    Message, DATABUF_CONFIG,
+};
+use std::fmt::Debug;
+
+mod other {
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Meters(pub f64);
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Shape {
+        Empty,
+        Circle(u32),
+        Rect { width: u32, height: u32 },
+    }
+}
+
+#[derive(Message, Debug, PartialEq)]
+#[frpc(remote = "other::Point")]
+struct PointDef {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Message, Debug, PartialEq)]
+#[frpc(remote = "other::Meters")]
+struct MetersDef(f64);
+
+#[derive(Message, Debug, PartialEq)]
+#[frpc(remote = "other::Shape")]
+enum ShapeDef {
+    Empty,
+    Circle(u32),
+    Rect { width: u32, height: u32 },
+}
+
+/// Foreign type as a field of another message.
+#[derive(Message, Debug, PartialEq)]
+struct Line {
+    #[frpc(with = "PointDef")]
+    start: other::Point,
+    #[frpc(with = "PointDef")]
+    end: other::Point,
+    width: u8,
+}
+
+fn encode(value: &impl Encode) -> Vec<u8> {
+    let mut buf = Vec::new();
+    value.encode::<DATABUF_CONFIG>(&mut buf).unwrap();
+    buf
+}
+
+fn decode<T: for<'de> Decode<'de>>(buf: &[u8]) -> T {
+    let mut cursor = buf;
+    let value = T::decode::<DATABUF_CONFIG>(&mut cursor).unwrap();
+    assert!(cursor.is_empty());
+    value
+}
+
+fn name<T: TypeId>() -> String {
+    match T::ty(&mut CostomTypes::default()) {
+        Ty::CustomType(name) => name,
+        _ => panic!("not a custom type"),
+    }
+}
+
+fn convert<R, S>(remote: R, shadow: S)
+where
+    R: From<S> + Clone + PartialEq + Debug,
+    S: From<R> + PartialEq + Debug,
+{
+    assert_eq!(S::from(remote.clone()), shadow);
+    assert_eq!(R::from(shadow), remote);
+}
+
+#[test]
+fn struct_conversions() {
+    convert(other::Point { x: 1, y: -2 }, PointDef { x: 1, y: -2 });
+    convert(other::Meters(1.5), MetersDef(1.5));
+}
+
+#[test]
+fn enum_conversions() {
+    convert(other::Shape::Empty, ShapeDef::Empty);
+    convert(other::Shape::Circle(5), ShapeDef::Circle(5));
+    convert(
+        other::Shape::Rect {
+            width: 2,
+            height: 3,
+        },
+        ShapeDef::Rect {
+            width: 2,
+            height: 3,
+        },
+    );
+
+    let buf = encode(&ShapeDef::Rect {
+        width: 2,
+        height: 3,
+    });
+    let shape: other::Shape = decode::<ShapeDef>(&buf).into();
+    assert_eq!(
+        shape,
+        other::Shape::Rect {
+            width: 2,
+            height: 3
+        }
+    );
+}
+
+#[test]
+fn with_field() {
+    let line = Line {
+        start: other::Point { x: 1, y: 2 },
+        end: other::Point { x: 3, y: 4 },
+        width: 5,
+    };
+    // Encoded the same as the shadow definitions.
+    let mut expected = encode(&PointDef { x: 1, y: 2 });
+    expected.extend(encode(&PointDef { x: 3, y: 4 }));
+    expected.extend(encode(&5u8));
+    let buf = encode(&line);
+    assert_eq!(buf, expected);
+    assert_eq!(decode::<Line>(&buf), line);
+
+    // Fields are registered as the shadow definition.
+    let mut costom_types = CostomTypes::default();
+    Line::ty(&mut costom_types);
+    assert!(costom_types.contains_key(&name::<PointDef>()));
+}
+
+mod a {
+    use super::*;
+
+    pub struct Point(pub u8);
+
+    #[derive(Message)]
+    #[frpc(remote = "Point")]
+    pub struct PointDef(u8);
+}
+
+mod b {
+    use super::*;
+
+    pub struct Point(pub u8);
+
+    #[derive(Message)]
+    #[frpc(remote = "self::Point")]
+    pub struct PointDef(u8);
+}
+
+#[test]
+fn names_are_resolved_against_the_module() {
+    assert_eq!(name::<PointDef>(), "remote::other::Point");
+    assert_eq!(name::<a::PointDef>(), "remote::a::Point");
+    assert_eq!(name::<b::PointDef>(), "remote::b::Point");
+}
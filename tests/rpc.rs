@@ -5,7 +5,8 @@ mod echo;
 mod sse;
 mod validate;
 
-use frpc_transport_http::{http::HeaderValue, Ctx, Incoming, Request, Response, Server};
+use frpc_transport::Cors;
+use frpc_transport_http::{Ctx, Incoming, Request, Response, Server};
 use std::{
     collections::HashSet,
     io::Result,
@@ -87,8 +88,10 @@ struct App {
 
 impl Incoming for App {
     async fn stream(self, req: Request, mut res: Response) {
-        res.headers
-            .append("access-control-allow-origin", HeaderValue::from_static("*"));
+        if let Some(status) = Cors::default().apply(&req.method, &req.headers, &mut res.headers) {
+            res.status = status;
+            return;
+        }
 
         let mut ctx = Ctx::new(req, res);
 
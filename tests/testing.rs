@@ -0,0 +1,145 @@
+use frpc::{
+    testing::{StreamItem, TestClient},
+    *,
+};
+
+fn add(a: u8, b: u8) -> Return<u8> {
+    Return(a.wrapping_add(b))
+}
+
+async fn greet(name: String) -> String {
+    format!("Hello, {name}!")
+}
+
+async fn checksum(seed: u32, mut data: ByteStream) -> u32 {
+    let mut sum = seed;
+    while let Some(chunk) = data.chunk().await {
+        sum = chunk.unwrap().iter().fold(sum, |sum, &b| sum + b as u32);
+    }
+    sum
+}
+
+fn count(to: u8) -> impl Output {
+    sse!({
+        for i in 0..to {
+            yield i;
+        }
+        "Done!"
+    })
+}
+
+/// Cursor isn't visible ASCII, So it's percent-encoded on the wire.
+fn feed(cursor: ResumeCursor) -> impl Output {
+    resumable_sse! {
+        let start = match cursor.as_deref() {
+            Some(cursor) => cursor.strip_prefix("пункт ").unwrap().parse::<u32>().unwrap() + 1,
+            None => 0,
+        };
+        for n in start..6 {
+            yield Resumable::new(format!("пункт {n}"), n * 10)
+        }
+    }
+}
+
+declare! {
+    service Test {
+        rpc add = 1;
+        rpc greet = 2;
+        rpc count = 3;
+        rpc checksum = 4;
+        rpc feed = 5;
+    }
+}
+
+#[tokio::test]
+async fn unary() {
+    let client = TestClient::new(Test, ());
+    assert_eq!(client.unary::<u8>(1, (2u8, 3u8)).await.unwrap(), 5);
+    assert_eq!(
+        client
+            .unary::<String>(2, ("Nur".to_string(),))
+            .await
+            .unwrap(),
+        "Hello, Nur!"
+    );
+    assert_eq!(
+        client
+            .unary::<u32>(4, (10u32, vec![1u8; 300]))
+            .await
+            .unwrap(),
+        310
+    );
+    let err = client.unary::<u8>(42, ()).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn server_stream() {
+    let client = TestClient::new(Test, ());
+    let mut stream = client.server_stream::<u8, String>(3, (3u8,));
+    let mut items = vec![];
+    while let Some(item) = stream.next().await {
+        items.push(item.unwrap());
+    }
+    assert_eq!(
+        items,
+        [
+            StreamItem::Yield(0),
+            StreamItem::Yield(1),
+            StreamItem::Yield(2),
+            StreamItem::Return("Done!".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn health() {
+    use frpc::health::{Health, HealthReporter, ServingStatus};
+
+    let reporter = HealthReporter::new();
+    let client = TestClient::new(Health, reporter.clone());
+    let check = |service: &str| client.unary::<ServingStatus>(1, (service.to_string(),));
+    assert_eq!(check("").await.unwrap(), ServingStatus::Serving);
+    assert_eq!(check("Test").await.unwrap(), ServingStatus::ServiceUnknown);
+
+    let mut stream = client.server_stream::<ServingStatus, ()>(2, ("Test".to_string(),));
+    let item = stream.next().await.unwrap().unwrap();
+    assert_eq!(item, StreamItem::Yield(ServingStatus::ServiceUnknown));
+
+    reporter.set_serving::<Test>();
+    let item = stream.next().await.unwrap().unwrap();
+    assert_eq!(item, StreamItem::Yield(ServingStatus::Serving));
+    assert_eq!(check("Test").await.unwrap(), ServingStatus::Serving);
+}
+
+#[tokio::test]
+async fn resume_from_cursor() {
+    let client = TestClient::new(Test, ());
+    let mut stream = client.server_stream::<(String, u32), ()>(5, ());
+    let mut received = vec![];
+    for _ in 0..3 {
+        match stream.next().await.unwrap().unwrap() {
+            StreamItem::Yield(item) => received.push(item),
+            item => panic!("unexpected: {item:?}"),
+        }
+    }
+    // Disconnected, After the third item.
+    drop(stream);
+    let (cursor, _) = received.last().unwrap().clone();
+    assert_eq!(cursor, "%D0%BF%D1%83%D0%BD%D0%BA%D1%82%202");
+    assert!(cursor.bytes().all(|b| b.is_ascii_graphic()));
+
+    let mut stream = client.resume_stream::<(String, u32), ()>(5, (), cursor);
+    while let Some(item) = stream.next().await {
+        match item.unwrap() {
+            StreamItem::Yield(item) => received.push(item),
+            StreamItem::Return(()) => break,
+        }
+    }
+    let items: Vec<_> = received.iter().map(|(_, item)| *item).collect();
+    assert_eq!(items, [0, 10, 20, 30, 40, 50]);
+
+    let mut stream = client.resume_stream::<(String, u32), ()>(5, (), "%zz");
+    let err = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
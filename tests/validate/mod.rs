@@ -22,6 +22,26 @@ struct r#class {
     r#new: (),
 }
 
+mod other {
+    #[derive(Debug, PartialEq)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+}
+
+#[derive(Message)]
+#[frpc(remote = "other::Point")]
+struct PointDef {
+    x: i32,
+    y: i32,
+}
+
+async fn shift(point: PointDef) -> PointDef {
+    let other::Point { x, y } = point.into();
+    other::Point { x: x + 1, y: y + 1 }.into()
+}
+
 #[repr(i8)]
 #[derive(Message, PartialEq)]
 enum r#enum {
@@ -33,5 +53,6 @@ frpc::declare! {
     pub service r#ValidateTest {
         rpc r#get_data = 1;
         rpc validate = 2;
+        rpc shift = 3;
     }
 }
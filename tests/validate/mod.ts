@@ -1,5 +1,6 @@
 #!/usr/bin/env -S deno run --allow-net="localhost" --unsafely-ignore-certificate-errors="localhost"
 
+import { assertEquals } from "https://deno.land/std@0.175.0/testing/asserts.ts";
 import { HttpTransport } from "../../target/rpc/http.transport.ts";
 import Lib from "../../target/rpc/ValidateTest.ts";
 
@@ -7,3 +8,5 @@ let lib = new Lib(new HttpTransport("https://localhost:4433/rpc/validate"));
 
 let data = await lib.get_data()();
 await lib.validate(data)();
+
+assertEquals(await lib.shift({ x: 1, y: 2 })(), { x: 2, y: 3 });